@@ -0,0 +1,131 @@
+use anyhow::{anyhow, Result};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path};
+
+use crate::config_schema::{format_path, migrate_config, unknown_keys};
+use crate::docker_compose::auth::AUTH_DIR;
+use crate::docker_compose::tls::CERTS_DIR;
+use crate::instance_config::InstanceConfig;
+
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Instance directories copied into bundles. Host specific files, e.g. allocated ports, stay behind.
+const BUNDLED_DIRS: [&str; 2] = [CERTS_DIR, AUTH_DIR];
+
+/// Everything needed to recreate an instance on another machine.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct InstanceBundle {
+    pub bundle_version: u32,
+
+    pub instance_name: String,
+
+    pub instance_config: InstanceConfig,
+
+    /// Certificates and tokens keyed by path relative to the instance directory, base64-encoded
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub files: IndexMap<String, String>,
+}
+
+pub fn export_bundle(
+    instance_name: &str,
+    instance_config: &InstanceConfig,
+    instance_dir: &Path,
+) -> Result<InstanceBundle> {
+    let mut files = IndexMap::new();
+
+    for dir in BUNDLED_DIRS {
+        let dir_path = instance_dir.join(dir);
+        if !dir_path.exists() {
+            continue;
+        }
+
+        let mut entries = std::fs::read_dir(&dir_path)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            files.insert(
+                format!("{dir}/{}", entry.file_name().to_string_lossy()),
+                BASE64_STANDARD.encode(std::fs::read(entry.path())?),
+            );
+        }
+    }
+
+    Ok(InstanceBundle {
+        bundle_version: BUNDLE_VERSION,
+        instance_name: instance_name.to_string(),
+        instance_config: instance_config.clone(),
+        files,
+    })
+}
+
+/// Parses the bundle and checks the config against the current [InstanceConfig] schema.
+/// Fields this version of puls doesn't know about are rejected instead of silently dropped.
+pub fn parse_bundle(bundle_yaml: &str) -> Result<InstanceBundle> {
+    let mut bundle_value: serde_yaml::Value = serde_yaml::from_str(bundle_yaml)?;
+
+    let bundle_version = bundle_value
+        .get("bundle_version")
+        .and_then(|version| version.as_u64())
+        .ok_or(anyhow!("Not a puls bundle, bundle_version is missing"))?;
+    if bundle_version != u64::from(BUNDLE_VERSION) {
+        return Err(anyhow!(
+            "Unsupported bundle version {bundle_version}, expected {BUNDLE_VERSION}"
+        ));
+    }
+
+    // Bundles created by older versions of puls
+    if let Some(instance_config) = bundle_value.get_mut("instance_config") {
+        migrate_config(instance_config).map_err(|err| anyhow!("Invalid bundle. {err}"))?;
+    }
+
+    let bundle: InstanceBundle = serde_yaml::from_str(&serde_yaml::to_string(&bundle_value)?)
+        .map_err(|err| anyhow!("Invalid bundle. {err}"))?;
+
+    let known_fields = serde_yaml::to_value(&bundle.instance_config)?;
+    let unknown_fields = unknown_keys(&bundle_value["instance_config"], &known_fields);
+    if let Some(unknown_field) = unknown_fields.first() {
+        return Err(anyhow!(
+            "Invalid bundle. Unknown instance_config field {}",
+            format_path(unknown_field)
+        ));
+    }
+
+    for file_path in bundle.files.keys() {
+        let path = Path::new(file_path);
+        let is_bundled_dir = path
+            .parent()
+            .is_some_and(|parent| BUNDLED_DIRS.iter().any(|dir| parent == Path::new(dir)));
+        let is_normal = path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+
+        if !is_bundled_dir || !is_normal {
+            return Err(anyhow!("Invalid bundle. Unexpected file {file_path}"));
+        }
+    }
+
+    Ok(bundle)
+}
+
+/// Writes bundled certificates and tokens into the instance directory.
+pub fn write_bundle_files(bundle: &InstanceBundle, instance_dir: &Path) -> Result<()> {
+    for (file_path, content) in &bundle.files {
+        let file = instance_dir.join(file_path);
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let content = BASE64_STANDARD
+            .decode(content)
+            .map_err(|err| anyhow!("Invalid content of {file_path} in the bundle. {err}"))?;
+        std::fs::write(file, content)?;
+    }
+
+    Ok(())
+}
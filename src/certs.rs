@@ -0,0 +1,105 @@
+use anyhow::{anyhow, Result};
+use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, KeyPair};
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+const CA_COMMON_NAME: &str = "puls local CA";
+
+pub fn ca_cert_file_name() -> String {
+    "ca.cert.pem".to_string()
+}
+
+pub fn cert_file_name(service_name: &str) -> String {
+    format!("{service_name}.cert.pem")
+}
+
+pub fn key_file_name(service_name: &str) -> String {
+    format!("{service_name}.key.pem")
+}
+
+/// Pulsar only loads RSA keys in PKCS#8 format. The ring backend of rcgen can't generate RSA keys,
+/// so they come from openssl.
+fn generate_rsa_key() -> Result<KeyPair> {
+    let output = Command::new("openssl")
+        .args([
+            "genpkey",
+            "-algorithm",
+            "RSA",
+            "-pkeyopt",
+            "rsa_keygen_bits:2048",
+        ])
+        .output()
+        .map_err(|e| anyhow!("Failed to run openssl to generate an RSA key: {e}"))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "openssl failed to generate an RSA key: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(KeyPair::from_pem(&String::from_utf8(output.stdout)?)?)
+}
+
+/// Creates a file only the owner can read, whoever holds the CA key can sign trusted certificates.
+fn write_private_file(path: &Path, contents: &str) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    options.open(path)?.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+/// Writes a CA and a certificate per service into the directory. Existing files are kept,
+/// so clients that already trust the CA keep working after the instance is regenerated.
+///
+/// Each service certificate is valid for the given host names and IP addresses.
+pub fn write_certificates(certs_dir: &Path, services: &[(String, Vec<String>)]) -> Result<()> {
+    std::fs::create_dir_all(certs_dir)?;
+
+    let ca_key_file = certs_dir.join("ca.key.pem");
+    let ca_cert_file = certs_dir.join(ca_cert_file_name());
+
+    let ca_key = if ca_key_file.exists() {
+        KeyPair::from_pem(&std::fs::read_to_string(&ca_key_file)?)?
+    } else {
+        let ca_key = generate_rsa_key()?;
+        write_private_file(&ca_key_file, &ca_key.serialize_pem())?;
+        ca_key
+    };
+
+    // The CA certificate is recreated from the same key and subject on every run,
+    // it signs exactly like the one written to disk the first time.
+    let mut ca_params = CertificateParams::new(Vec::new())?;
+    ca_params
+        .distinguished_name
+        .push(DnType::CommonName, CA_COMMON_NAME);
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca_cert = ca_params.self_signed(&ca_key)?;
+
+    if !ca_cert_file.exists() {
+        std::fs::write(&ca_cert_file, ca_cert.pem())?;
+    }
+
+    for (service_name, hosts) in services {
+        let cert_file = certs_dir.join(cert_file_name(service_name));
+        let key_file = certs_dir.join(key_file_name(service_name));
+        if cert_file.exists() && key_file.exists() {
+            continue;
+        }
+
+        let key = generate_rsa_key()?;
+        let mut params = CertificateParams::new(hosts.clone())?;
+        params
+            .distinguished_name
+            .push(DnType::CommonName, service_name.as_str());
+        let cert = params.signed_by(&key, &ca_cert, &ca_key)?;
+
+        std::fs::write(&key_file, key.serialize_pem())?;
+        std::fs::write(&cert_file, cert.pem())?;
+    }
+
+    Ok(())
+}
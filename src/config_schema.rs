@@ -0,0 +1,381 @@
+use anyhow::{anyhow, Result};
+use serde_yaml::{Mapping, Value};
+use std::fmt;
+
+use crate::docker_compose::validation::{check_topology, Severity};
+use crate::instance_config::InstanceConfig;
+
+/// Version of the instance config layout written by this version of puls.
+/// Bump it together with a new entry in [MIGRATIONS] when fields are renamed or restructured.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Migration from version `i` to version `i + 1` is at index `i`.
+const MIGRATIONS: [fn(&mut Mapping); CONFIG_VERSION as usize] = [migrate_v0_to_v1];
+
+/// Version 0 configs predate the `version` field and have the same layout otherwise.
+fn migrate_v0_to_v1(_config: &mut Mapping) {}
+
+/// Configs without the `version` field are version 0.
+pub fn config_version(config: &Value) -> Result<u32> {
+    match config.get("version") {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or(anyhow!("version must be a non-negative integer")),
+    }
+}
+
+/// Upgrades the config to [CONFIG_VERSION]. Returns the original version if the config was migrated.
+pub fn migrate_config(config: &mut Value) -> Result<Option<u32>> {
+    let version = config_version(config)?;
+    if version > CONFIG_VERSION {
+        return Err(anyhow!(
+            "Config version {version} is newer than {CONFIG_VERSION} supported by this version of puls. Upgrade puls"
+        ));
+    }
+    if version == CONFIG_VERSION {
+        return Ok(None);
+    }
+
+    let Some(fields) = config.as_mapping_mut() else {
+        return Err(anyhow!("Expected a mapping of instance config fields"));
+    };
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(fields);
+    }
+    fields.insert("version".into(), CONFIG_VERSION.into());
+
+    Ok(Some(version))
+}
+
+/// Deserializes the migrated config. Uses the original text if migrations changed nothing but
+/// the version, so errors point to lines of the file.
+fn deserialize_config(
+    config_yaml: &str,
+    original_config: &Value,
+    config: &Value,
+) -> Result<InstanceConfig, serde_yaml::Error> {
+    let mut versioned_config = original_config.clone();
+    if let Some(fields) = versioned_config.as_mapping_mut() {
+        fields.insert("version".into(), CONFIG_VERSION.into());
+    }
+
+    if *config == versioned_config {
+        serde_yaml::from_str(config_yaml)
+    } else {
+        serde_yaml::from_str(&serde_yaml::to_string(config)?)
+    }
+}
+
+fn parse_config_value(config_yaml: &str) -> Result<Value, serde_yaml::Error> {
+    match serde_yaml::from_str::<Value>(config_yaml)? {
+        Value::Null => Ok(Value::Mapping(Mapping::new())),
+        config => Ok(config),
+    }
+}
+
+/// Parses the config and migrates it to [CONFIG_VERSION] in memory.
+/// Returns the version the config was migrated from, if it was.
+pub fn parse_config(config_yaml: &str) -> Result<(InstanceConfig, Option<u32>)> {
+    let original_config = parse_config_value(config_yaml)?;
+    let mut config = original_config.clone();
+    let from_version = migrate_config(&mut config)?;
+    let instance_config = deserialize_config(config_yaml, &original_config, &config)?;
+
+    Ok((instance_config, from_version))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+pub fn format_path(path: &[PathSegment]) -> String {
+    let mut formatted = String::new();
+    for segment in path {
+        match segment {
+            PathSegment::Key(key) if formatted.is_empty() => formatted.push_str(key),
+            PathSegment::Key(key) => formatted.push_str(&format!(".{key}")),
+            PathSegment::Index(index) => formatted.push_str(&format!("[{index}]")),
+        }
+    }
+    formatted
+}
+
+/// Inverse of [format_path], e.g. `clusters[1].num_bookies`.
+pub fn parse_path(path: &str) -> Vec<PathSegment> {
+    let mut segments = Vec::new();
+    for part in path.split('.') {
+        let mut indexes = part.split('[');
+        if let Some(key) = indexes.next().filter(|key| !key.is_empty()) {
+            segments.push(PathSegment::Key(key.to_string()));
+        }
+        for index in indexes {
+            match index.trim_end_matches(']').parse() {
+                Ok(index) => segments.push(PathSegment::Index(index)),
+                Err(_) => segments.push(PathSegment::Key(format!("[{index}"))),
+            }
+        }
+    }
+    segments
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    /// Field path, e.g. `resources.tenants[0].name`. Empty for issues with the whole file
+    pub path: String,
+
+    /// 1-based line in the config file, if the issue can be pinned to one
+    pub line: Option<usize>,
+
+    pub message: String,
+
+    /// The instance starts anyway, but likely not the way the user expects
+    pub is_warning: bool,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {line}: ")?;
+        }
+        if self.is_warning {
+            write!(f, "warning: ")?;
+        }
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Keys of the parsed config that don't survive a round trip through [InstanceConfig].
+/// Fields skipped on serialization only disappear when empty, so empty values are never reported.
+pub fn unknown_keys(config: &Value, known: &Value) -> Vec<Vec<PathSegment>> {
+    let mut unknown = Vec::new();
+    collect_unknown_keys(config, known, &mut Vec::new(), &mut unknown);
+    unknown
+}
+
+fn is_empty_value(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Sequence(sequence) => sequence.is_empty(),
+        Value::Mapping(mapping) => mapping.is_empty(),
+        _ => false,
+    }
+}
+
+fn collect_unknown_keys(
+    config: &Value,
+    known: &Value,
+    path: &mut Vec<PathSegment>,
+    unknown: &mut Vec<Vec<PathSegment>>,
+) {
+    match (config, known) {
+        (Value::Mapping(fields), Value::Mapping(known_fields)) => {
+            for (key, value) in fields {
+                let key_name = key.as_str().map(|key| key.to_string()).unwrap_or(
+                    serde_yaml::to_string(key)
+                        .unwrap_or_default()
+                        .trim()
+                        .to_string(),
+                );
+                path.push(PathSegment::Key(key_name));
+                match known_fields.get(key) {
+                    Some(known_value) => collect_unknown_keys(value, known_value, path, unknown),
+                    None if !is_empty_value(value) => unknown.push(path.clone()),
+                    None => {}
+                }
+                path.pop();
+            }
+        }
+        (Value::Sequence(items), Value::Sequence(known_items)) => {
+            for (index, (item, known_item)) in items.iter().zip(known_items).enumerate() {
+                path.push(PathSegment::Index(index));
+                collect_unknown_keys(item, known_item, path, unknown);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Checks the config file content. `extra_keys` are top-level keys allowed besides the
+/// [InstanceConfig] fields, e.g. `instance_name` in project configs.
+pub fn validate_config(config_yaml: &str, extra_keys: &[&str]) -> Vec<ConfigIssue> {
+    let parse_issue = |err: serde_yaml::Error| {
+        let message = err.to_string();
+        // The line is reported separately
+        let message = match (err.location(), message.rfind(" at line ")) {
+            (Some(_), Some(position)) => message[..position].to_string(),
+            _ => message,
+        };
+        ConfigIssue {
+            path: String::new(),
+            line: err.location().map(|location| location.line()),
+            message,
+            is_warning: false,
+        }
+    };
+
+    let original_config = match parse_config_value(config_yaml) {
+        Ok(config) => config,
+        Err(err) => return vec![parse_issue(err)],
+    };
+
+    let mut config = original_config.clone();
+    if let Err(err) = migrate_config(&mut config) {
+        return vec![ConfigIssue {
+            path: "version".to_string(),
+            line: find_line(config_yaml, &[PathSegment::Key("version".to_string())]),
+            message: err.to_string(),
+            is_warning: false,
+        }];
+    }
+
+    let instance_config =
+        deserialize_config(config_yaml, &original_config, &config).map_err(parse_issue);
+    let instance_config = match instance_config {
+        Ok(instance_config) => instance_config,
+        Err(issue) => return vec![issue],
+    };
+
+    let mut issues = Vec::new();
+
+    let mut known = serde_yaml::to_value(&instance_config).unwrap_or_default();
+    if let Some(known_fields) = known.as_mapping_mut() {
+        for key in extra_keys {
+            if let Some(value) = config.get(*key) {
+                known_fields.insert((*key).into(), value.clone());
+            }
+        }
+    }
+    for path in unknown_keys(&config, &known) {
+        issues.push(ConfigIssue {
+            path: format_path(&path),
+            line: find_line(config_yaml, &path),
+            message: "unknown field".to_string(),
+            is_warning: false,
+        });
+    }
+
+    // Value constraints the config schema can't express
+    for issue in check_topology(&instance_config) {
+        issues.push(ConfigIssue {
+            line: find_line(config_yaml, &parse_path(&issue.field)),
+            path: issue.field,
+            message: issue.message,
+            is_warning: issue.severity == Severity::Warning,
+        });
+    }
+
+    issues
+}
+
+/// Indentation of the line and the column its key starts at, skipping a sequence item dash.
+fn line_columns(line: &str) -> (usize, usize, &str) {
+    let indent = line.len() - line.trim_start().len();
+    let content = line.trim_start();
+    match content.strip_prefix("- ") {
+        Some(item_content) => (
+            indent,
+            indent + 2 + item_content.len() - item_content.trim_start().len(),
+            item_content.trim_start(),
+        ),
+        None => (indent, indent, content),
+    }
+}
+
+fn is_key_line(content: &str, key: &str) -> bool {
+    [key.to_string(), format!("\"{key}\""), format!("'{key}'")]
+        .iter()
+        .any(|quoted_key| {
+            content
+                .strip_prefix(quoted_key.as_str())
+                .is_some_and(|rest| rest.trim_start().starts_with(':'))
+        })
+}
+
+/// Finds the 1-based line of the field in block-style YAML, as written by puls and most editors.
+pub fn find_line(config_yaml: &str, path: &[PathSegment]) -> Option<usize> {
+    let lines = config_yaml
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let content = line.trim_start();
+            !content.is_empty() && !content.starts_with('#') && content != "---"
+        })
+        .collect::<Vec<(usize, &str)>>();
+
+    // Position in `lines` and key column of the current node. The top level has no key line.
+    let mut position: Option<usize> = None;
+    let mut column: Option<usize> = None;
+    // Sequence items start with their first key on the item line
+    let mut is_item = false;
+
+    for segment in path {
+        let start = match (position, is_item) {
+            (Some(position), true) => position,
+            (Some(position), false) => position + 1,
+            (None, _) => 0,
+        };
+
+        match segment {
+            PathSegment::Key(key) => {
+                let mut child_column = if is_item { column } else { None };
+                let mut found = None;
+                for (i, (_, line)) in lines.iter().enumerate().skip(start) {
+                    let (indent, key_column, content) = line_columns(line);
+                    // Keys of an item are aligned with its first key, the next item is outdented
+                    let is_outside = match column {
+                        Some(column) if is_item => i != start && indent < column,
+                        Some(column) => key_column <= column,
+                        None => false,
+                    };
+                    if is_outside {
+                        break;
+                    }
+                    let child_column = *child_column.get_or_insert(key_column);
+                    if key_column == child_column && is_key_line(content, key) {
+                        found = Some((i, key_column));
+                        break;
+                    }
+                }
+                let (i, key_column) = found?;
+                position = Some(i);
+                column = Some(key_column);
+                is_item = false;
+            }
+            PathSegment::Index(index) => {
+                let mut item_indent = None;
+                let mut item_count = 0;
+                let mut found = None;
+                for (i, (_, line)) in lines.iter().enumerate().skip(start) {
+                    let (indent, key_column, _) = line_columns(line);
+                    let is_item_line = line.trim_start().starts_with("- ");
+                    if !is_item_line && column.is_some_and(|column| key_column <= column) {
+                        break;
+                    }
+                    if !is_item_line || *item_indent.get_or_insert(indent) != indent {
+                        continue;
+                    }
+                    if item_count == *index {
+                        found = Some((i, key_column));
+                        break;
+                    }
+                    item_count += 1;
+                }
+                let (i, key_column) = found?;
+                position = Some(i);
+                column = Some(key_column);
+                is_item = true;
+            }
+        }
+    }
+
+    position.map(|position| lines[position].0 + 1)
+}
@@ -1 +1,15 @@
+pub mod auth;
+pub mod clusters;
+pub mod compose;
+pub mod config_overrides;
+#[allow(clippy::module_inception)]
 pub mod docker_compose;
+pub mod functions;
+pub mod metadata_store;
+pub mod monitoring;
+pub mod profiles;
+pub mod replication;
+pub mod resources;
+pub mod tiered_storage;
+pub mod tls;
+pub mod validation;
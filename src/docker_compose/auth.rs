@@ -0,0 +1,155 @@
+use indexmap::IndexMap;
+
+use crate::instance_config::AuthConfig;
+use crate::tokens::{secret_key_file_name, token_file_name};
+
+/// Directory with the secret key and tokens, relative to the instance directory.
+pub const AUTH_DIR: &str = "auth";
+
+/// Env file with Dekaf settings that can't be passed by file path, relative to [AUTH_DIR].
+pub const DEKAF_ENV_FILE: &str = "dekaf.env";
+
+/// Role proxies use to talk to brokers on behalf of clients.
+pub const PROXY_ROLE: &str = "proxy";
+
+const AUTH_MOUNT_PATH: &str = "/pulsar/auth";
+
+const AUTHENTICATION_PROVIDER_TOKEN: &str =
+    "org.apache.pulsar.broker.authentication.AuthenticationProviderToken";
+const AUTHENTICATION_TOKEN: &str = "org.apache.pulsar.client.impl.auth.AuthenticationToken";
+
+pub fn auth_volume() -> String {
+    format!("./{AUTH_DIR}:{AUTH_MOUNT_PATH}:ro")
+}
+
+pub fn dekaf_env_file() -> String {
+    format!("./{AUTH_DIR}/{DEKAF_ENV_FILE}")
+}
+
+/// Roles puls generates tokens for: the superuser, the proxy and the configured ones.
+pub fn auth_roles(auth_config: &AuthConfig) -> Vec<String> {
+    let mut roles = vec![auth_config.superuser_role.clone(), PROXY_ROLE.to_string()];
+    for role in &auth_config.roles {
+        if !roles.contains(role) {
+            roles.push(role.clone());
+        }
+    }
+    roles
+}
+
+pub fn dekaf_env(superuser_token: &str) -> String {
+    format!("DEKAF_DEFAULT_PULSAR_AUTH={{\"type\":\"jwt\",\"token\":\"{superuser_token}\"}}\n")
+}
+
+fn mounted_secret_key_file() -> String {
+    format!("{AUTH_MOUNT_PATH}/{}", secret_key_file_name())
+}
+
+/// Value for `authParams` and `brokerClientAuthenticationParameters`
+pub fn mounted_token_file(role: &str) -> String {
+    format!("file://{AUTH_MOUNT_PATH}/{}", token_file_name(role))
+}
+
+/// Header for calls to the admin API from shell commands run inside containers.
+pub fn shell_curl_auth_header(role: &str) -> String {
+    format!(
+        "-H \"Authorization: Bearer $(cat {AUTH_MOUNT_PATH}/{})\"",
+        token_file_name(role)
+    )
+}
+
+/// Header for calls to the admin API from healthchecks that run with `CMD-SHELL`.
+/// `$$` stops docker compose from interpolating the command substitution.
+pub fn curl_auth_header(role: &str) -> String {
+    shell_curl_auth_header(role).replace('$', "$$")
+}
+
+fn to_environment(env: &[(&str, String)]) -> IndexMap<String, String> {
+    env.iter()
+        .map(|(k, v)| (k.to_string(), v.clone()))
+        .collect()
+}
+
+pub fn broker_auth_environment(auth_config: &AuthConfig) -> IndexMap<String, String> {
+    to_environment(&[
+        ("authenticationEnabled", "true".to_string()),
+        ("authorizationEnabled", "true".to_string()),
+        (
+            "authenticationProviders",
+            AUTHENTICATION_PROVIDER_TOKEN.to_string(),
+        ),
+        (
+            "tokenSecretKey",
+            format!("file://{}", mounted_secret_key_file()),
+        ),
+        (
+            "superUserRoles",
+            format!("{},{PROXY_ROLE}", auth_config.superuser_role),
+        ),
+        ("proxyRoles", PROXY_ROLE.to_string()),
+        (
+            "brokerClientAuthenticationPlugin",
+            AUTHENTICATION_TOKEN.to_string(),
+        ),
+        (
+            "brokerClientAuthenticationParameters",
+            mounted_token_file(&auth_config.superuser_role),
+        ),
+    ])
+}
+
+/// Proxies authenticate clients and forward their credentials, brokers do the authorization.
+/// Client settings go to client.conf, so `puls exec` runs as the superuser.
+pub fn proxy_auth_environment(auth_config: &AuthConfig) -> IndexMap<String, String> {
+    let mut environment = to_environment(&[
+        ("authenticationEnabled", "true".to_string()),
+        (
+            "authenticationProviders",
+            AUTHENTICATION_PROVIDER_TOKEN.to_string(),
+        ),
+        (
+            "tokenSecretKey",
+            format!("file://{}", mounted_secret_key_file()),
+        ),
+        (
+            "superUserRoles",
+            format!("{},{PROXY_ROLE}", auth_config.superuser_role),
+        ),
+        ("forwardAuthorizationCredentials", "true".to_string()),
+        (
+            "brokerClientAuthenticationPlugin",
+            AUTHENTICATION_TOKEN.to_string(),
+        ),
+        (
+            "brokerClientAuthenticationParameters",
+            mounted_token_file(PROXY_ROLE),
+        ),
+    ]);
+    environment.extend(client_auth_environment(auth_config));
+    environment
+}
+
+/// Makes `pulsar-admin` use the superuser token via client.conf
+pub fn client_auth_environment(auth_config: &AuthConfig) -> IndexMap<String, String> {
+    to_environment(&[
+        ("authPlugin", AUTHENTICATION_TOKEN.to_string()),
+        (
+            "authParams",
+            mounted_token_file(&auth_config.superuser_role),
+        ),
+    ])
+}
+
+/// Lets a dedicated functions worker talk to brokers as the superuser.
+pub fn functions_worker_auth_environment(auth_config: &AuthConfig) -> IndexMap<String, String> {
+    to_environment(&[
+        (
+            "PF_brokerClientAuthenticationPlugin",
+            AUTHENTICATION_TOKEN.to_string(),
+        ),
+        (
+            "PF_brokerClientAuthenticationParameters",
+            mounted_token_file(&auth_config.superuser_role),
+        ),
+    ])
+}
@@ -0,0 +1,79 @@
+use crate::instance_config::{
+    ClusterConfig, ComponentResources, ComponentResourcesConfig, InstanceConfig,
+};
+
+pub fn cluster_config(
+    instance_config: &InstanceConfig,
+    cluster_index: u32,
+) -> Option<&ClusterConfig> {
+    instance_config.clusters.get(cluster_index as usize)
+}
+
+fn merge_component_resources(
+    resources: &mut Option<ComponentResources>,
+    cluster_resources: &Option<ComponentResources>,
+) {
+    let Some(cluster_resources) = cluster_resources else {
+        return;
+    };
+    let resources = resources.get_or_insert_with(ComponentResources::default);
+    for (field, cluster_field) in [
+        (&mut resources.heap, &cluster_resources.heap),
+        (
+            &mut resources.direct_memory,
+            &cluster_resources.direct_memory,
+        ),
+        (&mut resources.memory, &cluster_resources.memory),
+        (&mut resources.cpus, &cluster_resources.cpus),
+    ] {
+        if cluster_field.is_some() {
+            field.clone_from(cluster_field);
+        }
+    }
+}
+
+/// Instance config as seen by the services of the cluster, with the `clusters` entry applied.
+/// Config overrides are merged separately, see `config_overrides`.
+pub fn cluster_instance_config(
+    instance_config: &InstanceConfig,
+    cluster_index: u32,
+) -> InstanceConfig {
+    let mut config = instance_config.clone();
+    let Some(cluster_config) = cluster_config(instance_config, cluster_index) else {
+        return config;
+    };
+
+    if let Some(pulsar_version) = &cluster_config.pulsar_version {
+        config.pulsar_version.clone_from(pulsar_version);
+    }
+    if let Some(num_brokers) = cluster_config.num_brokers {
+        config.num_brokers = num_brokers;
+    }
+    if let Some(num_bookies) = cluster_config.num_bookies {
+        config.num_bookies = num_bookies;
+    }
+    if let Some(with_dekaf) = cluster_config.with_dekaf {
+        config.with_dekaf = with_dekaf;
+    }
+    if let Some(functions_worker) = cluster_config.functions_worker {
+        config.functions_worker = functions_worker;
+    }
+    if let Some(resource_profile) = cluster_config.resource_profile {
+        config.resource_profile = resource_profile;
+    }
+    if let Some(cluster_resources) = &cluster_config.component_resources {
+        let resources = config
+            .component_resources
+            .get_or_insert_with(ComponentResourcesConfig::default);
+        merge_component_resources(&mut resources.zookeeper, &cluster_resources.zookeeper);
+        merge_component_resources(&mut resources.broker, &cluster_resources.broker);
+        merge_component_resources(&mut resources.bookie, &cluster_resources.bookie);
+        merge_component_resources(&mut resources.proxy, &cluster_resources.proxy);
+        merge_component_resources(
+            &mut resources.functions_worker,
+            &cluster_resources.functions_worker,
+        );
+    }
+
+    config
+}
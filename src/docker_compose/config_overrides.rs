@@ -0,0 +1,137 @@
+use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
+
+use super::clusters::{cluster_config, cluster_instance_config};
+use super::docker_compose::managed_ledger_ensemble_size;
+use crate::instance_config::{ClusterConfig, ConfigOverrides, InstanceConfig};
+
+/// Makes `apply-config-from-env.py` set the key even if the config file doesn't have it.
+/// Variables without the prefix only replace keys that are already in the file.
+const CONFIG_ENV_PREFIX: &str = "PULSAR_PREFIX_";
+
+/// Adds the overrides to the service environment. Generated variables for the same keys are
+/// removed, otherwise the script could apply them after the overrides.
+pub fn apply_config_overrides(
+    environment: &mut IndexMap<String, String>,
+    overrides: IndexMap<String, String>,
+) {
+    for (key, value) in overrides {
+        environment.shift_remove(&key);
+        environment.insert(format!("{CONFIG_ENV_PREFIX}{key}"), value);
+    }
+}
+
+/// Instance-wide overrides with the cluster ones applied on top.
+fn merge_overrides(
+    instance_config: &InstanceConfig,
+    cluster_index: u32,
+    instance_overrides: &ConfigOverrides,
+    cluster_overrides: fn(&ClusterConfig) -> &ConfigOverrides,
+) -> IndexMap<String, String> {
+    let mut overrides = instance_overrides.clone();
+    if let Some(cluster_config) = cluster_config(instance_config, cluster_index) {
+        overrides.extend(
+            cluster_overrides(cluster_config)
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+    }
+    overrides
+}
+
+pub fn broker_config_overrides(
+    instance_config: &InstanceConfig,
+    cluster_index: u32,
+) -> IndexMap<String, String> {
+    merge_overrides(
+        instance_config,
+        cluster_index,
+        &instance_config.broker_config,
+        |cluster_config| &cluster_config.broker_config,
+    )
+}
+
+pub fn bookie_config_overrides(
+    instance_config: &InstanceConfig,
+    cluster_index: u32,
+) -> IndexMap<String, String> {
+    merge_overrides(
+        instance_config,
+        cluster_index,
+        &instance_config.bookie_config,
+        |cluster_config| &cluster_config.bookie_config,
+    )
+}
+
+pub fn proxy_config_overrides(
+    instance_config: &InstanceConfig,
+    cluster_index: u32,
+) -> IndexMap<String, String> {
+    merge_overrides(
+        instance_config,
+        cluster_index,
+        &instance_config.proxy_config,
+        |cluster_config| &cluster_config.proxy_config,
+    )
+}
+
+pub fn zookeeper_config_overrides(instance_config: &InstanceConfig) -> IndexMap<String, String> {
+    instance_config.zookeeper_config.clone()
+}
+
+/// Overrides with their field paths, e.g. `clusters[1].broker_config`, for validation.
+pub fn all_config_overrides(instance_config: &InstanceConfig) -> Vec<(String, &ConfigOverrides)> {
+    let mut overrides = vec![
+        ("broker_config".to_string(), &instance_config.broker_config),
+        ("bookie_config".to_string(), &instance_config.bookie_config),
+        ("proxy_config".to_string(), &instance_config.proxy_config),
+        (
+            "zookeeper_config".to_string(),
+            &instance_config.zookeeper_config,
+        ),
+    ];
+    for (cluster_index, cluster_config) in instance_config.clusters.iter().enumerate() {
+        overrides.extend([
+            (
+                format!("clusters[{cluster_index}].broker_config"),
+                &cluster_config.broker_config,
+            ),
+            (
+                format!("clusters[{cluster_index}].bookie_config"),
+                &cluster_config.bookie_config,
+            ),
+            (
+                format!("clusters[{cluster_index}].proxy_config"),
+                &cluster_config.proxy_config,
+            ),
+        ]);
+    }
+    overrides
+}
+
+pub const MANAGED_LEDGER_QUORUM_KEYS: [&str; 3] = [
+    "managedLedgerDefaultEnsembleSize",
+    "managedLedgerDefaultWriteQuorum",
+    "managedLedgerDefaultAckQuorum",
+];
+
+/// Ensemble size, write quorum and ack quorum of the cluster's ledgers, after overrides.
+pub fn managed_ledger_quorum(
+    instance_config: &InstanceConfig,
+    cluster_index: u32,
+) -> Result<[u32; 3]> {
+    let overrides = broker_config_overrides(instance_config, cluster_index);
+    let default_size =
+        managed_ledger_ensemble_size(&cluster_instance_config(instance_config, cluster_index));
+
+    let mut quorum = [default_size; 3];
+    for (size, key) in quorum.iter_mut().zip(MANAGED_LEDGER_QUORUM_KEYS) {
+        if let Some(value) = overrides.get(key) {
+            *size = value
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("{key} must be a non-negative integer, got {value}"))?;
+        }
+    }
+    Ok(quorum)
+}
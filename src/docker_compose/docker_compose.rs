@@ -1,7 +1,42 @@
 use anyhow::Result;
+use indexmap::IndexMap;
 use serde::Serialize;
 
-use crate::InstanceConfig;
+use super::auth::{
+    auth_volume, broker_auth_environment, client_auth_environment, curl_auth_header,
+    dekaf_env_file, proxy_auth_environment,
+};
+use super::clusters::cluster_instance_config;
+use super::compose::{
+    ComposeFile, DependsOnCondition, Deploy, Healthcheck, Network, RestartPolicy, Service, Volume,
+};
+use super::config_overrides::{
+    apply_config_overrides, bookie_config_overrides, broker_config_overrides,
+    proxy_config_overrides,
+};
+use super::functions::{
+    broker_functions_environment, functions_volume, functions_worker_service_name,
+    generate_functions_worker_service, is_functions_worker_enabled, proxy_functions_environment,
+};
+use super::metadata_store::{
+    bookkeeper_metadata_service_uri, cluster_configuration_metadata_store_url,
+    cluster_metadata_store_url, configuration_metadata_store_url, generate_metadata_store_services,
+    metadata_store_service_names, metadata_store_url, metadata_store_volumes,
+};
+use super::monitoring::{generate_monitoring, monitoring_volumes, MonitoringOutput};
+use super::profiles::{bookie_storage_environment, component_resources, Component};
+use super::replication::replication_clusters;
+use super::resources::{apply_resources_script, create_if_missing};
+use super::tiered_storage::{
+    broker_offload_environment, generate_tiered_storage_services,
+    minio_create_bucket_job_service_name, tiered_storage_volumes, OFFLOAD_THRESHOLD,
+};
+use super::tls::{
+    bookie_tls_environment, broker_tls_environment, certs_volumes, proxy_tls_environment,
+};
+use super::validation::{estimate_memory, format_memory, validate_topology};
+use crate::host_ports::{legacy_port, HostPorts};
+use crate::instance_config::{AuthConfig, FunctionsWorker, InstanceConfig};
 use std::cmp::min;
 
 pub trait PrintInfo {
@@ -14,16 +49,26 @@ pub struct PulsarProxyOutput {
     pub web_service_host_url: Option<String>,
     pub broker_service_url: Option<String>,
     pub broker_service_host_url: Option<String>,
-    pub docker_compose_template: String,
+    pub web_service_host_port: Option<u16>,
+    pub broker_service_host_port: Option<u16>,
+    pub service_name: String,
+    pub service: Service,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ClusterOutput {
     pub cluster_index: u32,
+    pub cluster_name: String,
     pub web_service_host_url: Option<String>,
     pub broker_service_host_url: Option<String>,
     pub dekaf_host_url: Option<String>,
-    pub docker_compose_template: String,
+    pub grafana_host_url: Option<String>,
+    pub web_service_host_port: Option<u16>,
+    pub broker_service_host_port: Option<u16>,
+    pub dekaf_host_port: Option<u16>,
+    pub grafana_host_port: Option<u16>,
+    #[serde(skip)]
+    pub services: IndexMap<String, Service>,
 }
 
 impl PrintInfo for ClusterOutput {
@@ -46,13 +91,26 @@ impl PrintInfo for ClusterOutput {
                 self.dekaf_host_url
             );
         }
+
+        if let Some(grafana_host_url) = &self.grafana_host_url {
+            println!("Grafana is available at URL: {grafana_host_url}");
+        }
     }
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct InstanceOutput {
     pub instance_name: String,
+    pub instance_config: InstanceConfig,
     pub clusters: Vec<ClusterOutput>,
+    pub prometheus_host_url: Option<String>,
+    /// Sum of service memory limits
+    pub estimated_memory: u64,
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+    #[serde(skip)]
+    pub docker_compose: ComposeFile,
+    #[serde(skip)]
     pub docker_compose_template: String,
 }
 
@@ -65,118 +123,197 @@ impl PrintInfo for InstanceOutput {
         for cluster in &self.clusters {
             cluster.print_info();
         }
+
+        if let Some(prometheus_host_url) = &self.prometheus_host_url {
+            println!("Prometheus is available at URL: {prometheus_host_url}");
+        }
+
+        println!(
+            "Estimated memory usage: {}",
+            format_memory(self.estimated_memory)
+        );
     }
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct DekafOutput {
     pub dekaf_host_url: String,
-    pub docker_compose_template: String,
+    pub dekaf_host_port: u16,
+    pub service_name: String,
+    pub service: Service,
+}
+
+/// Compose project name of the instance. Compose only allows lowercase project names
+/// that start with a letter or digit, so other names get a `puls` prefix.
+pub fn project_name(instance_name: &str) -> String {
+    let project_name = instance_name.to_lowercase();
+    if project_name.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        project_name
+    } else {
+        format!("puls{project_name}")
+    }
+}
+
+pub fn network_name(instance_name: &str) -> String {
+    format!("pulsar-net-{instance_name}")
+}
+
+pub fn broker_service_name(cluster_name: &str, broker_index: u32) -> String {
+    format!("broker-{cluster_name}-{broker_index}")
+}
+
+pub fn bookie_service_name(cluster_name: &str, bookie_index: u32) -> String {
+    format!("bookie-{cluster_name}-{bookie_index}")
+}
+
+pub fn pulsar_proxy_service_name(cluster_name: &str) -> String {
+    format!("pulsar-proxy-{cluster_name}")
+}
+
+pub fn pulsar_init_job_service_name(cluster_name: &str) -> String {
+    format!("pulsar-init-job-{cluster_name}")
+}
+
+pub fn post_cluster_create_job_service_name(cluster_name: &str) -> String {
+    format!("pulsar-post-cluster-create-job-{cluster_name}")
+}
+
+pub fn dekaf_service_name(cluster_name: &str) -> String {
+    format!("dekaf-ui-{cluster_name}")
+}
+
+pub fn cluster_name(cluster_index: u32) -> String {
+    format!("cluster-{cluster_index}")
+}
+
+/// Bookies each ledger is written to. Write and ack quorums are the same.
+pub fn managed_ledger_ensemble_size(instance_config: &InstanceConfig) -> u32 {
+    min(instance_config.num_bookies, 2)
 }
 
 pub fn generate_instance(
     instance_name: String,
     instance_config: InstanceConfig,
+    host_ports: &mut HostPorts,
 ) -> Result<InstanceOutput> {
-    let zookeepers_per_cluster = instance_config.num_zookeepers;
-    let zookeeper_templates = (0..zookeepers_per_cluster)
-        .map(|zookeeper_index| {
-            generate_zookeeper_template(
-                instance_name.clone(),
-                instance_config.clone(),
-                zookeeper_index,
-            )
-        })
-        .collect::<Vec<String>>()
-        .join("\n");
+    let warnings = validate_topology(&instance_config)?;
+
+    let mut docker_compose = ComposeFile {
+        name: Some(project_name(&instance_name)),
+        ..Default::default()
+    };
+
+    docker_compose.services =
+        generate_metadata_store_services(instance_name.clone(), instance_config.clone());
+    docker_compose
+        .services
+        .extend(generate_tiered_storage_services(
+            instance_name.clone(),
+            instance_config.clone(),
+        ));
 
     let num_clusters = instance_config.num_clusters;
-    let cluster_names = (0..num_clusters).map(|i| format!("cluster-{}", i));
+    let cluster_names = (0..num_clusters).map(cluster_name);
 
-    let clusters: Vec<ClusterOutput> = cluster_names
+    let mut clusters: Vec<ClusterOutput> = cluster_names
         .clone()
         .enumerate()
         .map(|(cluster_index, cluster_name)| {
+            let cluster_index = u32::try_from(cluster_index).unwrap();
             generate_cluster(
                 instance_name.clone(),
-                instance_config.clone(),
+                cluster_instance_config(&instance_config, cluster_index),
                 cluster_name,
-                u32::try_from(cluster_index).unwrap(),
+                cluster_index,
+                host_ports,
             )
-            .unwrap()
         })
-        .collect();
+        .collect::<Result<Vec<ClusterOutput>>>()?;
 
-    let clusters_template = clusters
-        .iter()
-        .map(|cluster| cluster.docker_compose_template.clone())
-        .collect::<Vec<String>>()
-        .join("\n\n");
-
-    let bookie_volumes_template = cluster_names.clone()
-        .map(|cluster_name| {
-            (0..instance_config.num_bookies)
-                .map(|i| format!("████bookie-data-{cluster_name}-{i}:"))
-                .collect::<Vec<String>>()
-                .join("\n")
-        })
-        .collect::<Vec<String>>()
-        .join("\n");
+    for cluster in &clusters {
+        docker_compose.services.extend(cluster.services.clone());
+    }
 
-    let zookeeper_volumes_template = (0..zookeepers_per_cluster)
-        .map(|i| format!("████zookeeper-data-{i}:"))
-        .collect::<Vec<String>>()
-        .join("\n");
+    let monitoring_output: Option<MonitoringOutput> = if instance_config.with_monitoring {
+        Some(generate_monitoring(
+            instance_name.clone(),
+            instance_config.clone(),
+            host_ports,
+        )?)
+    } else {
+        None
+    };
 
-    let pulsar_init_job_volumes_template = cluster_names.clone()
-        .map(|cluster_name| {
-            format!("████pulsar-init-job-{cluster_name}:")
-        })
-        .collect::<Vec<String>>()
-        .join("\n");
+    if let Some(monitoring) = &monitoring_output {
+        docker_compose.services.extend(monitoring.services.clone());
+        docker_compose.configs.extend(monitoring.configs.clone());
 
-    let pulsar_post_cluster_create_job_volumes_template = cluster_names
-        .map(|cluster_name| {
-            format!("████pulsar-post-cluster-create-job-{cluster_name}:")
-        })
-        .collect::<Vec<String>>()
-        .join("\n");
+        for (cluster, grafana_host_port) in clusters.iter_mut().zip(&monitoring.grafana_host_ports)
+        {
+            cluster.grafana_host_url = Some(format!("http://localhost:{grafana_host_port}"));
+            cluster.grafana_host_port = Some(*grafana_host_port);
+        }
+    }
+
+    let pulsar_init_job_volumes = cluster_names
+        .clone()
+        .map(|cluster_name| format!("pulsar-init-job-{cluster_name}"));
 
-    let instance_config_str = serde_yaml::to_string(&instance_config)
-        .unwrap()
+    let pulsar_post_cluster_create_job_volumes = cluster_names
+        .clone()
+        .map(|cluster_name| format!("pulsar-post-cluster-create-job-{cluster_name}"));
+
+    let bookie_volumes = (0..num_clusters).flat_map(|cluster_index| {
+        let num_bookies = cluster_instance_config(&instance_config, cluster_index).num_bookies;
+        let cluster_name = cluster_name(cluster_index);
+        (0..num_bookies).map(move |i| format!("bookie-data-{cluster_name}-{i}"))
+    });
+
+    let metadata_store_volumes = metadata_store_volumes(&instance_config);
+    let tiered_storage_volumes = tiered_storage_volumes(&instance_config);
+    let monitoring_volumes = monitoring_volumes(&instance_config);
+
+    docker_compose.volumes = pulsar_init_job_volumes
+        .chain(pulsar_post_cluster_create_job_volumes)
+        .chain(bookie_volumes)
+        .chain(metadata_store_volumes)
+        .chain(tiered_storage_volumes)
+        .chain(monitoring_volumes)
+        .map(|volume_name| (volume_name, Volume::default()))
+        .collect();
+
+    docker_compose.networks.insert(
+        network_name(&instance_name),
+        Network {
+            driver: Some("bridge".to_string()),
+        },
+    );
+
+    let instance_config_str = serde_yaml::to_string(&instance_config)?
         .lines()
-        .map(|l| format!("████# {}", l))
+        .map(|l| format!("#   {}", l))
         .collect::<Vec<String>>()
         .join("\n");
 
-    let docker_compose_template = format! {"
-# This file is autogenerated by puls, the tool to manage local Pulsar instances
+    let docker_compose_template = format!(
+        "# This file is autogenerated by puls, the tool to manage local Pulsar instances
 # More info https://github.com/tealtools/puls
 #
-# {instance_config_str}
-
-services:
-{zookeeper_templates}
-
-{clusters_template}
-
-volumes:
-{pulsar_init_job_volumes_template}
-{pulsar_post_cluster_create_job_volumes_template}
-{bookie_volumes_template}
-{zookeeper_volumes_template}
+{instance_config_str}
 
-networks:
-████pulsar-net-{instance_name}:
-████████driver: bridge
-"}
-    .trim()
-    .replace('█', " ");
+{}",
+        docker_compose.to_yaml()?
+    );
 
     let instance_output = InstanceOutput {
         instance_name: instance_name.clone(),
+        instance_config,
         clusters,
-        docker_compose_template: docker_compose_template.clone(),
+        prometheus_host_url: monitoring_output.map(|monitoring| monitoring.prometheus_host_url),
+        estimated_memory: estimate_memory(&docker_compose)?,
+        warnings,
+        docker_compose,
+        docker_compose_template,
     };
 
     Ok(instance_output)
@@ -187,89 +324,107 @@ pub fn generate_cluster(
     instance_config: InstanceConfig,
     cluster_name: String,
     cluster_index: u32,
+    host_ports: &mut HostPorts,
 ) -> Result<ClusterOutput> {
-    let pulsar_init_job_template = generate_pulsar_init_job_template(
-        instance_name.clone(),
-        instance_config.clone(),
-        cluster_name.clone(),
+    let mut services: IndexMap<String, Service> = IndexMap::new();
+
+    services.insert(
+        pulsar_init_job_service_name(&cluster_name),
+        generate_pulsar_init_job_service(
+            instance_name.clone(),
+            instance_config.clone(),
+            cluster_name.clone(),
+        ),
     );
 
-    let brokers_template = (0..instance_config.num_brokers)
-        .map(|i| {
-            generate_broker_template(
+    for i in 0..instance_config.num_brokers {
+        services.insert(
+            broker_service_name(&cluster_name, i),
+            generate_broker_service(
                 instance_name.clone(),
                 instance_config.clone(),
                 cluster_name.clone(),
+                cluster_index,
                 i,
-            )
-        })
-        .collect::<Vec<String>>()
-        .join("\n");
+            ),
+        );
+    }
 
-    let bookies_template = (0..instance_config.num_bookies)
-        .map(|i| {
-            generate_bookie_template(
+    if instance_config.functions_worker == FunctionsWorker::Dedicated {
+        services.insert(
+            functions_worker_service_name(&cluster_name),
+            generate_functions_worker_service(
                 instance_name.clone(),
                 instance_config.clone(),
                 cluster_name.clone(),
+            ),
+        );
+    }
+
+    for i in 0..instance_config.num_bookies {
+        services.insert(
+            bookie_service_name(&cluster_name, i),
+            generate_bookie_service(
+                instance_name.clone(),
+                instance_config.clone(),
+                cluster_name.clone(),
+                cluster_index,
                 i,
-            )
-        })
-        .collect::<Vec<String>>()
-        .join("\n");
+            ),
+        );
+    }
 
     let pulsar_proxy_output = generate_pulsar_proxy(
         instance_name.clone(),
         instance_config.clone(),
         cluster_name.clone(),
         cluster_index,
+        host_ports,
     )?;
 
-    let pulsar_proxy_template = pulsar_proxy_output.docker_compose_template;
+    services.insert(
+        pulsar_proxy_output.service_name.clone(),
+        pulsar_proxy_output.service.clone(),
+    );
 
-    let post_cluster_create_job_template = generate_post_cluster_create_job_template(
-        instance_name.clone(),
-        instance_config.clone(),
-        cluster_name.clone(),
-        cluster_index,
+    services.insert(
+        post_cluster_create_job_service_name(&cluster_name),
+        generate_post_cluster_create_job_service(
+            instance_name.clone(),
+            instance_config.clone(),
+            cluster_name.clone(),
+            cluster_index,
+        ),
     );
 
     let dekaf_output: Option<DekafOutput> = if instance_config.with_dekaf {
-        Some(generate_dekaf(instance_name.clone(), cluster_index)?)
+        Some(generate_dekaf(
+            instance_name.clone(),
+            instance_config.clone(),
+            cluster_index,
+            host_ports,
+        )?)
     } else {
         None
     };
 
-    let dekaf_template = dekaf_output
-        .clone()
-        .map(|dekaf| dekaf.docker_compose_template)
-        .unwrap_or("".to_string());
-
-    let docker_compose_template = format! {"
-████# BEGIN Pulsar cluster {cluster_name} definition
-
-{pulsar_init_job_template}
-
-{brokers_template}
-
-{bookies_template}
-
-{pulsar_proxy_template}
-
-{post_cluster_create_job_template}
-
-{dekaf_template}
-████# END Pulsar cluster {cluster_name} definition
-"}
-    .trim()
-    .to_string();
+    if let Some(dekaf) = &dekaf_output {
+        services.insert(dekaf.service_name.clone(), dekaf.service.clone());
+    }
 
     let cluster_output = ClusterOutput {
         cluster_index,
+        cluster_name,
         web_service_host_url: pulsar_proxy_output.web_service_host_url,
         broker_service_host_url: pulsar_proxy_output.broker_service_host_url,
-        dekaf_host_url: dekaf_output.map(|dekaf| dekaf.dekaf_host_url),
-        docker_compose_template,
+        web_service_host_port: pulsar_proxy_output.web_service_host_port,
+        broker_service_host_port: pulsar_proxy_output.broker_service_host_port,
+        dekaf_host_url: dekaf_output.clone().map(|dekaf| dekaf.dekaf_host_url),
+        dekaf_host_port: dekaf_output.map(|dekaf| dekaf.dekaf_host_port),
+        // Grafana runs next to Prometheus, see generate_monitoring
+        grafana_host_url: None,
+        grafana_host_port: None,
+        services,
     };
 
     Ok(cluster_output)
@@ -280,405 +435,591 @@ pub fn generate_pulsar_proxy(
     instance_config: InstanceConfig,
     cluster_name: String,
     cluster_index: u32,
+    host_ports: &mut HostPorts,
 ) -> Result<PulsarProxyOutput> {
-    let pulsar_version = instance_config.pulsar_version;
-
-    let depends_on_broker_template =
-        format!("████████████broker-{cluster_name}-0:\n████████████████condition: service_healthy");
+    let pulsar_version = instance_config.pulsar_version.clone();
+    let service_name = pulsar_proxy_service_name(&cluster_name);
+
+    let metadata_store_url = metadata_store_url(&instance_config);
+
+    // With TLS enabled only the TLS ports are published to the host
+    let (
+        web_service_scheme,
+        web_service_container_port,
+        broker_service_scheme,
+        broker_service_container_port,
+    ) = if instance_config.tls {
+        ("https", 8443, "pulsar+ssl", 6651)
+    } else {
+        ("http", 8080, "pulsar", 6650)
+    };
 
-    let metadata_store_url = (0..instance_config.num_zookeepers)
-        .map(|i| format!("zk:zookeeper-{i}:2181"))
-        .collect::<Vec<String>>()
-        .join(",");
+    let proxy_resources = component_resources(&instance_config, Component::Proxy);
 
-    let web_service_port = (cluster_index.to_string() + "8080").parse::<u32>().unwrap();
-    let web_service_url = format!("http://pulsar-proxy-{cluster_name}:8080");
-    let web_service_host_url = format!("http://localhost:{web_service_port}");
-
-    let broker_service_port = (cluster_index.to_string() + "6650").parse::<u32>().unwrap();
-    let broker_service_url = format!("pulsar://pulsar-proxy-{cluster_name}:6650");
-    let broker_service_host_url = format!("pulsar://localhost:{broker_service_port}");
-
-    let docker_compose_template = format! {"
-████# Pulsar proxy for cluster {cluster_name}
-████pulsar-proxy-{cluster_name}:
-████████image: apachepulsar/pulsar:{pulsar_version}
-████████user: pulsar
-████████restart: on-failure
-████████command: bash -c \"bin/apply-config-from-env.py conf/proxy.conf && bin/apply-config-from-env.py conf/pulsar_env.sh && bin/pulsar proxy\"
-████████ports:
-████████████- {web_service_port}:8080
-████████████- {broker_service_port}:6650
-████████environment:
-████████████- clusterName={cluster_name}
-████████████- metadataStoreUrl={metadata_store_url}
-████████████- configurationMetadataStoreUrl={metadata_store_url}
-████████████- PULSAR_MEM=-Xms128m -Xmx128m -XX:MaxDirectMemorySize=128m -XX:+ExitOnOutOfMemoryError
-████████healthcheck:
-████████████test: [\"CMD\", \"curl\", \"--fail\", \"http://127.0.0.1:8080/admin/v2/brokers/health\"]
-████████████interval: 5s
-████████████timeout: 5s
-████████████retries: 30
-████████depends_on:
-{depends_on_broker_template}
-████████deploy:
-████████████resources:
-████████████████limits:
-████████████████████memory: 256M
-████████networks:
-████████████- pulsar-net-{instance_name}
-"};
+    let web_service_port = host_ports.allocate(
+        &format!("{cluster_name}.web_service"),
+        legacy_port(cluster_index, web_service_container_port),
+    )?;
+    let web_service_url =
+        format!("{web_service_scheme}://{service_name}:{web_service_container_port}");
+    let web_service_host_url = format!("{web_service_scheme}://localhost:{web_service_port}");
 
-    let pulsar_proxy_output = PulsarProxyOutput {
-        web_service_url: Some(web_service_url.clone()),
-        web_service_host_url: Some(web_service_host_url.clone()),
-        broker_service_url: Some(broker_service_url.clone()),
-        broker_service_host_url: Some(broker_service_host_url.clone()),
-        docker_compose_template: docker_compose_template.clone(),
+    let broker_service_port = host_ports.allocate(
+        &format!("{cluster_name}.broker_service"),
+        legacy_port(cluster_index, broker_service_container_port),
+    )?;
+    let broker_service_url =
+        format!("{broker_service_scheme}://{service_name}:{broker_service_container_port}");
+    let broker_service_host_url =
+        format!("{broker_service_scheme}://localhost:{broker_service_port}");
+
+    let mut service = Service {
+        image: format!("apachepulsar/pulsar:{pulsar_version}"),
+        user: Some("pulsar".to_string()),
+        restart: Some(RestartPolicy::OnFailure),
+        command: Service::bash_command("bin/apply-config-from-env.py conf/proxy.conf && bin/apply-config-from-env.py conf/client.conf && bin/apply-config-from-env.py conf/pulsar_env.sh && bin/pulsar proxy"),
+        ports: vec![
+            format!("{web_service_port}:{web_service_container_port}"),
+            format!("{broker_service_port}:{broker_service_container_port}"),
+        ],
+        environment: IndexMap::from([
+            ("clusterName".to_string(), cluster_name.clone()),
+            ("metadataStoreUrl".to_string(), metadata_store_url),
+            (
+                "configurationMetadataStoreUrl".to_string(),
+                configuration_metadata_store_url(&instance_config, &cluster_name),
+            ),
+            ("PULSAR_MEM".to_string(), proxy_resources.pulsar_mem()),
+        ]),
+        healthcheck: Some(Healthcheck {
+            test: curl_healthcheck_test(
+                "http://127.0.0.1:8080/admin/v2/brokers/health",
+                instance_config.auth.as_ref(),
+            ),
+            interval: "5s".to_string(),
+            timeout: "5s".to_string(),
+            retries: 30,
+        }),
+        deploy: Some(proxy_resources.deploy()),
+        networks: vec![network_name(&instance_name)],
+        ..Default::default()
     };
 
-    Ok(pulsar_proxy_output)
-}
+    service.depends_on(
+        broker_service_name(&cluster_name, 0),
+        DependsOnCondition::ServiceHealthy,
+    );
 
-pub fn generate_zookeeper_template(
-    instance_name: String,
-    instance_config: InstanceConfig,
-    zookeeper_index: u32,
-) -> String {
-    let pulsar_version = instance_config.pulsar_version;
+    if instance_config.tls {
+        service
+            .environment
+            .extend(proxy_tls_environment(&service_name));
+        service.volumes.extend(certs_volumes(&service_name));
+    }
 
-    let zookeepers_per_cluster = instance_config.num_zookeepers;
-    let zookeeper_servers = (0..zookeepers_per_cluster)
-        .map(|i| format!("server.{i}=zookeeper-{i}:2888:3888"))
-        .collect::<Vec<String>>();
+    if let Some(auth_config) = &instance_config.auth {
+        service
+            .environment
+            .extend(proxy_auth_environment(auth_config));
+        service.volumes.push(auth_volume());
+    }
 
-    let append_zookeeper_servers = zookeeper_servers
-        .iter()
-        .map(|server| format!("echo \"{}\" >> /pulsar/conf/zookeeper.conf", server))
-        .collect::<Vec<String>>()
-        .join("; ");
+    // `puls functions deploy` uploads packages from the proxy container
+    if is_functions_worker_enabled(&instance_config) {
+        service.volumes.push(functions_volume(&instance_config));
+    }
+
+    if instance_config.functions_worker == FunctionsWorker::Dedicated {
+        service
+            .environment
+            .extend(proxy_functions_environment(&cluster_name));
+    }
+
+    apply_config_overrides(
+        &mut service.environment,
+        proxy_config_overrides(&instance_config, cluster_index),
+    );
+
+    let pulsar_proxy_output = PulsarProxyOutput {
+        web_service_url: Some(web_service_url),
+        web_service_host_url: Some(web_service_host_url),
+        broker_service_url: Some(broker_service_url),
+        broker_service_host_url: Some(broker_service_host_url),
+        web_service_host_port: Some(web_service_port),
+        broker_service_host_port: Some(broker_service_port),
+        service_name,
+        service,
+    };
 
-    let create_my_id_if_not_exists = format!("if [ ! -f /pulsar/data/zookeeper/myid ]; then mkdir -p /pulsar/data/zookeeper && echo {zookeeper_index} > /pulsar/data/zookeeper/myid; fi");
-
-    format! {"
-████# Zookeeper for Pulsar
-████zookeeper-{zookeeper_index}:
-████████image: apachepulsar/pulsar:{pulsar_version}
-████████user: pulsar
-████████hostname: zookeeper-{zookeeper_index}
-████████restart: on-failure
-████████command: bash -c \"bin/apply-config-from-env.py conf/zookeeper.conf && bin/apply-config-from-env.py conf/pulsar_env.sh && {append_zookeeper_servers} && {create_my_id_if_not_exists} && exec bin/pulsar zookeeper\"
-████████environment:
-████████████- PULSAR_MEM=-Xms128m -Xmx128m -XX:MaxDirectMemorySize=128m -XX:+ExitOnOutOfMemoryError
-████████healthcheck:
-████████████test: [\"CMD\", \"bin/pulsar-zookeeper-ruok.sh\"]
-████████████interval: 10s
-████████████timeout: 5s
-████████████retries: 10
-████████volumes:
-████████████- zookeeper-data-{zookeeper_index}:/pulsar/data
-████████deploy:
-████████████resources:
-████████████████limits:
-████████████████████memory: 256M
-████████networks:
-████████████- pulsar-net-{instance_name}
-"}
-    .trim()
-    .to_string()
+    Ok(pulsar_proxy_output)
 }
 
-pub fn generate_pulsar_init_job_template(
+pub fn generate_pulsar_init_job_service(
     instance_name: String,
     instance_config: InstanceConfig,
     cluster_name: String,
-) -> String {
-    let pulsar_version = instance_config.pulsar_version;
-    let web_service_url = "http://broker-{cluster_name}:8080";
-    let broker_service_url = "pulsar://broker-{custer_name}:6650";
-    let zookeepers_per_cluster = instance_config.num_zookeepers;
-    let depends_on_zookeeper_template = (0..zookeepers_per_cluster)
-        .map(|i| format!("████████████zookeeper-{i}:\n████████████████condition: service_healthy"))
-        .collect::<Vec<String>>()
-        .join("\n");
+) -> Service {
+    let pulsar_version = instance_config.pulsar_version.clone();
+    let pulsar_proxy_service_name = pulsar_proxy_service_name(&cluster_name);
+    let web_service_url = format!("http://{pulsar_proxy_service_name}:8080");
+    let broker_service_url = format!("pulsar://{pulsar_proxy_service_name}:6650");
+    let cluster_metadata_store_url = cluster_metadata_store_url(&instance_config, &cluster_name);
+    let cluster_configuration_metadata_store_url =
+        cluster_configuration_metadata_store_url(&instance_config, &cluster_name);
+
+    let tls_urls = if instance_config.tls {
+        format!(" --web-service-url-tls https://{pulsar_proxy_service_name}:8443 --broker-service-url-tls pulsar+ssl://{pulsar_proxy_service_name}:6651")
+    } else {
+        String::new()
+    };
 
-    let init_pulsar_cluster_script  = format!("bin/pulsar initialize-cluster-metadata --cluster {cluster_name} --metadata-store zk:zookeeper-0:2181/{cluster_name} --configuration-metadata-store zk:zookeeper-0:2181/{cluster_name} --web-service-url {web_service_url} --broker-service-url {broker_service_url};");
+    let init_pulsar_cluster_script  = format!("bin/pulsar initialize-cluster-metadata --cluster {cluster_name} --metadata-store {cluster_metadata_store_url} --configuration-metadata-store {cluster_configuration_metadata_store_url} --web-service-url {web_service_url} --broker-service-url {broker_service_url}{tls_urls};");
     let init_bookkeeper_cluster_script = "bin/bookkeeper shell initnewcluster;".to_string();
 
-    let metadata_service_uri = format!(
-        "zk+hierarchical://{}",
-        (0..zookeepers_per_cluster)
-            .map(|i| format!("zookeeper-{i}:2181"))
-            .collect::<Vec<String>>()
-            .join(";")
-    ) + &format!("/bookkeeper-{cluster_name}");
-
-    let mark_job_as_completed_script = "mkdir /pulsar/data/init-job && touch /pulsar/data/init-job/success;".to_string();
-    let exit_if_job_already_completed_script = "test -f /pulsar/data/init-job/success && exit 0;".to_string();
-
-    format! {"
-████# Pulsar init job for cluster {cluster_name}
-████pulsar-init-job-{cluster_name}:
-████████image: apachepulsar/pulsar:{pulsar_version}
-████████user: pulsar
-████████command: bash -c \"{exit_if_job_already_completed_script} bin/apply-config-from-env.py conf/pulsar_env.sh; bin/apply-config-from-env.py conf/bookkeeper.conf;  {init_pulsar_cluster_script} {init_bookkeeper_cluster_script} {mark_job_as_completed_script}\"
-████████environment:
-████████████- clusterName={cluster_name}
-████████████- metadataServiceUri={metadata_service_uri}
-████████████- PULSAR_MEM=-Xms256m -Xmx256m -XX:MaxDirectMemorySize=256m -XX:+ExitOnOutOfMemoryError
-████████depends_on:
-{depends_on_zookeeper_template}
-████████deploy:
-████████████resources:
-████████████████limits:
-████████████████████memory: 512M
-████████volumes:
-████████████- pulsar-init-job-{cluster_name}:/pulsar/data
-████████networks:
-████████████- pulsar-net-{instance_name}
-"}.trim().to_string()
+    let metadata_service_uri = bookkeeper_metadata_service_uri(&instance_config, &cluster_name);
+
+    let mark_job_as_completed_script =
+        "mkdir /pulsar/data/init-job && touch /pulsar/data/init-job/success;".to_string();
+    let exit_if_job_already_completed_script =
+        "test -f /pulsar/data/init-job/success && exit 0;".to_string();
+
+    let mut service = Service {
+        image: format!("apachepulsar/pulsar:{pulsar_version}"),
+        user: Some("pulsar".to_string()),
+        command: Service::bash_command(format!("{exit_if_job_already_completed_script} bin/apply-config-from-env.py conf/pulsar_env.sh; bin/apply-config-from-env.py conf/bookkeeper.conf; {init_pulsar_cluster_script} {init_bookkeeper_cluster_script} {mark_job_as_completed_script}")),
+        environment: IndexMap::from([
+            ("clusterName".to_string(), cluster_name.clone()),
+            ("metadataServiceUri".to_string(), metadata_service_uri),
+            (
+                "PULSAR_MEM".to_string(),
+                "-Xms256m -Xmx256m -XX:MaxDirectMemorySize=256m -XX:+ExitOnOutOfMemoryError"
+                    .to_string(),
+            ),
+        ]),
+        deploy: Some(Deploy::with_memory_limit("512M")),
+        volumes: vec![format!("pulsar-init-job-{cluster_name}:/pulsar/data")],
+        networks: vec![network_name(&instance_name)],
+        ..Default::default()
+    };
+
+    for metadata_store_service_name in metadata_store_service_names(&instance_config) {
+        service.depends_on(
+            metadata_store_service_name,
+            DependsOnCondition::ServiceHealthy,
+        );
+    }
+
+    service
 }
 
-pub fn generate_post_cluster_create_job_template(
+pub fn generate_post_cluster_create_job_service(
     instance_name: String,
     instance_config: InstanceConfig,
     cluster_name: String,
     cluster_index: u32,
-) -> String {
-    let pulsar_version = instance_config.pulsar_version;
-    let depends_on_proxy_template = format!("████████depends_on:\n████████████pulsar-proxy-{cluster_name}:\n████████████████condition: service_healthy");
-    let depends_on_prev_cluster_template = if cluster_index == 0 {
-        "".to_string()
-    } else {
-        let prev_cluster_name = format!("cluster-{}", cluster_index - 1);
-        format!("████████████pulsar-proxy-{prev_cluster_name}:\n████████████████condition: service_healthy")
-    };
+) -> Service {
+    let pulsar_version = instance_config.pulsar_version.clone();
 
-    let pulsar_proxy_admin_url = format!("http://pulsar-proxy-{cluster_name}:8080");
+    let pulsar_proxy_admin_url =
+        format!("http://{}:8080", pulsar_proxy_service_name(&cluster_name));
     let pulsar_admin = format!("bin/pulsar-admin --admin-url {pulsar_proxy_admin_url}");
 
     let num_clusters = instance_config.num_clusters;
     let register_clusters = (0..num_clusters)
-        .map(|cluster_index| format!("{pulsar_admin} clusters create --url http://pulsar-proxy-cluster-{cluster_index}:8080 --broker-url pulsar://pulsar-proxy-cluster-{cluster_index}:6650 cluster-{cluster_index}"))
+        .map(|i| {
+            let name = self::cluster_name(i);
+            let proxy = pulsar_proxy_service_name(&name);
+            let tls_urls = if instance_config.tls {
+                format!(" --url-secure https://{proxy}:8443 --broker-url-secure pulsar+ssl://{proxy}:6651")
+            } else {
+                String::new()
+            };
+            create_if_missing(
+                &format!("{pulsar_admin} clusters get {name}"),
+                &format!("{pulsar_admin} clusters create --url http://{proxy}:8080 --broker-url pulsar://{proxy}:6650{tls_urls} {name}"),
+            )
+        })
         .collect::<Vec<String>>()
         .join("; ");
-    let create_cluster_tenant = format!("{pulsar_admin} tenants create --allowed-clusters cluster-{cluster_index} cluster-{cluster_index}-local");
-    let create_cluster_namespace =
-        format!("{pulsar_admin} namespaces create --clusters cluster-{cluster_index} cluster-{cluster_index}-local/default");
+    let create_cluster_tenant = create_if_missing(
+        &format!("{pulsar_admin} tenants get {cluster_name}-local"),
+        &format!(
+            "{pulsar_admin} tenants create --allowed-clusters {cluster_name} {cluster_name}-local"
+        ),
+    );
+    let create_cluster_namespace = create_if_missing(
+        &format!("{pulsar_admin} namespaces policies {cluster_name}-local/default"),
+        &format!(
+            "{pulsar_admin} namespaces create --clusters {cluster_name} {cluster_name}-local/default"
+        ),
+    );
 
     let all_cluster_names = (0..num_clusters)
-        .map(|i| format!("cluster-{}", i))
+        .map(self::cluster_name)
         .collect::<Vec<String>>()
         .join(",");
 
-    let create_global_tenant =
-        format!("{pulsar_admin} tenants create --allowed-clusters {all_cluster_names} global");
-    let create_global_namespace = format!("{pulsar_admin} namespaces create global/default");
+    let create_global_tenant = create_if_missing(
+        &format!("{pulsar_admin} tenants get global"),
+        &format!("{pulsar_admin} tenants create --allowed-clusters {all_cluster_names} global"),
+    );
+    let create_global_namespace = create_if_missing(
+        &format!("{pulsar_admin} namespaces policies global/default"),
+        &format!("{pulsar_admin} namespaces create global/default"),
+    );
     let set_global_namespace_clusters = format!(
-        "{pulsar_admin} namespaces set-clusters --clusters {all_cluster_names} global/default"
+        "{pulsar_admin} namespaces set-clusters --clusters {} global/default",
+        replication_clusters(&instance_config, cluster_index).join(",")
     );
 
-    let create_resources_script = format!("{register_clusters}; {create_cluster_tenant}; {create_cluster_namespace}; {create_global_tenant}; {create_global_namespace}; {set_global_namespace_clusters};");
-
-    format! {"
-████# Register new cluster {cluster_name}
-████pulsar-post-cluster-create-job-{cluster_name}:
-████████image: apachepulsar/pulsar:{pulsar_version}
-████████restart: on-failure
-████████user: pulsar
-████████command: bash -c \"{create_resources_script} echo success\"
-████████environment:
-████████████- PULSAR_MEM=-Xms256m -Xmx256m -XX:MaxDirectMemorySize=256m -XX:+ExitOnOutOfMemoryError
-{depends_on_proxy_template}
-{depends_on_prev_cluster_template}
-████████deploy:
-████████████resources:
-████████████████limits:
-████████████████████memory: 512M
-████████networks:
-████████████- pulsar-net-{instance_name}
-"}
-    .trim()
-    .to_string()
-}
+    let mut create_resources_script = format!("{register_clusters}; {create_cluster_tenant}; {create_cluster_namespace}; {create_global_tenant}; {create_global_namespace}; {set_global_namespace_clusters};");
+
+    if instance_config.with_tiered_storage {
+        let set_offload_thresholds = [
+            format!("{cluster_name}-local/default"),
+            "global/default".to_string(),
+        ]
+        .iter()
+        .map(|namespace| {
+            format!("{pulsar_admin} namespaces set-offload-threshold --size {OFFLOAD_THRESHOLD} {namespace}")
+        })
+        .collect::<Vec<String>>()
+        .join("; ");
+
+        create_resources_script = format!("{create_resources_script} {set_offload_thresholds};");
+    }
+
+    if instance_config.resources.is_some() {
+        let apply_resources_script = apply_resources_script(&pulsar_admin, &instance_config);
+        create_resources_script = format!("{create_resources_script} {apply_resources_script}");
+    }
+
+    let mut service = Service {
+        image: format!("apachepulsar/pulsar:{pulsar_version}"),
+        restart: Some(RestartPolicy::OnFailure),
+        user: Some("pulsar".to_string()),
+        command: Service::bash_command(format!(
+            "set -e; bin/apply-config-from-env.py conf/client.conf; {create_resources_script} echo success"
+        )),
+        environment: IndexMap::from([(
+            "PULSAR_MEM".to_string(),
+            "-Xms256m -Xmx256m -XX:MaxDirectMemorySize=256m -XX:+ExitOnOutOfMemoryError"
+                .to_string(),
+        )]),
+        deploy: Some(Deploy::with_memory_limit("512M")),
+        networks: vec![network_name(&instance_name)],
+        ..Default::default()
+    };
+
+    if let Some(auth_config) = &instance_config.auth {
+        service
+            .environment
+            .extend(client_auth_environment(auth_config));
+        service.volumes.push(auth_volume());
+    }
 
-pub fn generate_dekaf(instance_name: String, cluster_index: u32) -> Result<DekafOutput> {
-    let depends_on_pulsar_proxy_template = format!("████████████pulsar-proxy-cluster-{cluster_index}:\n████████████████condition: service_healthy");
+    service.depends_on(
+        pulsar_proxy_service_name(&cluster_name),
+        DependsOnCondition::ServiceHealthy,
+    );
+
+    if cluster_index > 0 {
+        let prev_cluster_name = self::cluster_name(cluster_index - 1);
+        service.depends_on(
+            pulsar_proxy_service_name(&prev_cluster_name),
+            DependsOnCondition::ServiceHealthy,
+        );
+    }
 
-    let port = (cluster_index.to_string() + "8090").parse::<u32>().unwrap();
+    service
+}
+
+pub fn generate_dekaf(
+    instance_name: String,
+    instance_config: InstanceConfig,
+    cluster_index: u32,
+    host_ports: &mut HostPorts,
+) -> Result<DekafOutput> {
+    let cluster_name = cluster_name(cluster_index);
+    let pulsar_proxy_service_name = pulsar_proxy_service_name(&cluster_name);
+
+    let port = host_ports.allocate(
+        &format!("{cluster_name}.dekaf"),
+        legacy_port(cluster_index, 8090),
+    )?;
 
     let dekaf_host_url = format!("http://localhost:{port}");
 
-    let docker_compose_template = format! {"
-████# Dekaf UI for cluster-{cluster_index}
-████# More info: https://dekaf.io
-████dekaf-ui-cluster-{cluster_index}:
-████████image: tealtools/dekaf:latest
-████████restart: on-failure
-████████environment:
-████████████- DEKAF_PULSAR_NAME={instance_name}-cluster-{cluster_index}
-████████████- DEKAF_PULSAR_WEB_URL=http://pulsar-proxy-cluster-{cluster_index}:8080
-████████████- DEKAF_PULSAR_BROKER_URL=pulsar://pulsar-proxy-cluster-{cluster_index}:6650
-████████████- DEKAF_PUBLIC_BASE_URL={dekaf_host_url}
-████████████- JAVA_OPTS=-Xms128m -Xmx128m
-████████ports:
-████████████- {port}:8090
-████████healthcheck:
-████████████test: [\"CMD\", \"curl\", \"--fail\", \"http://127.0.0.1:8090/admin/v2/brokers/health\"]
-████████████interval: 10s
-████████████timeout: 5s
-████████████retries: 20
-████████depends_on:
-{depends_on_pulsar_proxy_template}
-████████deploy:
-████████████resources:
-████████████████limits:
-████████████████████memory: 256M
-████████networks:
-████████████- pulsar-net-{instance_name}
-"}
-    .trim()
-    .to_string();
+    let mut service = Service {
+        image: "tealtools/dekaf:latest".to_string(),
+        restart: Some(RestartPolicy::OnFailure),
+        environment: IndexMap::from([
+            (
+                "DEKAF_PULSAR_NAME".to_string(),
+                format!("{instance_name}-{cluster_name}"),
+            ),
+            (
+                "DEKAF_PULSAR_WEB_URL".to_string(),
+                format!("http://{pulsar_proxy_service_name}:8080"),
+            ),
+            (
+                "DEKAF_PULSAR_BROKER_URL".to_string(),
+                format!("pulsar://{pulsar_proxy_service_name}:6650"),
+            ),
+            ("DEKAF_PUBLIC_BASE_URL".to_string(), dekaf_host_url.clone()),
+            ("JAVA_OPTS".to_string(), "-Xms128m -Xmx128m".to_string()),
+        ]),
+        ports: vec![format!("{port}:8090")],
+        healthcheck: Some(Healthcheck {
+            test: curl_healthcheck_test("http://127.0.0.1:8090/admin/v2/brokers/health", None),
+            interval: "10s".to_string(),
+            timeout: "5s".to_string(),
+            retries: 20,
+        }),
+        deploy: Some(Deploy::with_memory_limit("256M")),
+        networks: vec![network_name(&instance_name)],
+        ..Default::default()
+    };
+
+    if instance_config.auth.is_some() {
+        service.env_file.push(dekaf_env_file());
+    }
+
+    service.depends_on(
+        pulsar_proxy_service_name,
+        DependsOnCondition::ServiceHealthy,
+    );
 
     let dekaf_output = DekafOutput {
         dekaf_host_url,
-        docker_compose_template,
+        dekaf_host_port: port,
+        service_name: dekaf_service_name(&cluster_name),
+        service,
     };
 
     Ok(dekaf_output)
 }
 
-pub fn generate_broker_template(
+pub fn generate_broker_service(
     instance_name: String,
     instance_config: InstanceConfig,
     cluster_name: String,
+    cluster_index: u32,
     broker_index: u32,
-) -> String {
-    let pulsar_version = instance_config.pulsar_version;
-    let managed_ledger_default_ensemble_size = min(instance_config.num_bookies, 2);
-    let managed_ledger_default_write_quorum = min(instance_config.num_bookies, 2);
-    let managed_ledger_default_ack_quorum = min(instance_config.num_bookies, 2);
-    let zookeepers_per_cluster = instance_config.num_zookeepers;
-    let depends_on_zookeeper_template = (0..zookeepers_per_cluster)
-        .map(|i| format!("████████████zookeeper-{i}:\n████████████████condition: service_healthy"))
-        .collect::<Vec<String>>()
-        .join("\n");
+) -> Service {
+    let pulsar_version = instance_config.pulsar_version.clone();
+    let managed_ledger_default_ensemble_size = managed_ledger_ensemble_size(&instance_config);
+    let managed_ledger_default_write_quorum = managed_ledger_ensemble_size(&instance_config);
+    let managed_ledger_default_ack_quorum = managed_ledger_ensemble_size(&instance_config);
+    let broker_resources = component_resources(&instance_config, Component::Broker);
+    let metadata_store_url = metadata_store_url(&instance_config);
+    let bookkeeper_metadata_service_uri =
+        bookkeeper_metadata_service_uri(&instance_config, &cluster_name);
+
+    // Offloaders are only shipped with the pulsar-all image
+    let pulsar_image = if instance_config.with_tiered_storage {
+        "apachepulsar/pulsar-all"
+    } else {
+        "apachepulsar/pulsar"
+    };
 
-    let depends_on_bookies_template = (0..instance_config.num_bookies)
-        .map(|i| format!("████████████bookie-{cluster_name}-{i}:\n████████████████condition: service_healthy"))
-        .collect::<Vec<String>>()
-        .join("\n");
+    let mut service = Service {
+        image: format!("{pulsar_image}:{pulsar_version}"),
+        user: Some("pulsar".to_string()),
+        restart: Some(RestartPolicy::OnFailure),
+        environment: IndexMap::from([
+            ("clusterName".to_string(), cluster_name.clone()),
+            ("metadataStoreUrl".to_string(), metadata_store_url),
+            (
+                "configurationMetadataStoreUrl".to_string(),
+                configuration_metadata_store_url(&instance_config, &cluster_name),
+            ),
+            (
+                "bookkeeperMetadataServiceUri".to_string(),
+                bookkeeper_metadata_service_uri,
+            ),
+            (
+                "managedLedgerDefaultEnsembleSize".to_string(),
+                managed_ledger_default_ensemble_size.to_string(),
+            ),
+            (
+                "managedLedgerDefaultWriteQuorum".to_string(),
+                managed_ledger_default_write_quorum.to_string(),
+            ),
+            (
+                "managedLedgerDefaultAckQuorum".to_string(),
+                managed_ledger_default_ack_quorum.to_string(),
+            ),
+            (
+                "managedLedgerCacheEvictionFrequency".to_string(),
+                "1000".to_string(),
+            ),
+            ("PULSAR_MEM".to_string(), broker_resources.pulsar_mem()),
+        ]),
+        command: Service::bash_command(
+            "bin/apply-config-from-env.py conf/broker.conf && exec bin/pulsar broker",
+        ),
+        healthcheck: Some(Healthcheck {
+            test: curl_healthcheck_test(
+                "http://127.0.0.1:8080/admin/v2/brokers/health",
+                instance_config.auth.as_ref(),
+            ),
+            interval: "10s".to_string(),
+            timeout: "5s".to_string(),
+            retries: 20,
+        }),
+        deploy: Some(broker_resources.deploy()),
+        networks: vec![network_name(&instance_name)],
+        ..Default::default()
+    };
 
-    let metadata_store_url = (0..zookeepers_per_cluster)
-        .map(|i| format!("zk:zookeeper-{i}:2181"))
-        .collect::<Vec<String>>()
-        .join(",");
+    for metadata_store_service_name in metadata_store_service_names(&instance_config) {
+        service.depends_on(
+            metadata_store_service_name,
+            DependsOnCondition::ServiceHealthy,
+        );
+    }
 
-    let bookkeeper_metadata_service_uri = format!(
-        "zk://{}",
-        (0..zookeepers_per_cluster)
-            .map(|i| format!("zookeeper-{i}:2181"))
-            .collect::<Vec<String>>()
-            .join(";")
-    ) + &format!("/bookkeeper-{cluster_name}");
-
-    format! {"
-████# Pulsar broker for cluster {cluster_name}
-████broker-{cluster_name}-{broker_index}:
-████████image: apachepulsar/pulsar:{pulsar_version}
-████████user: pulsar
-████████restart: on-failure
-████████environment:
-████████████- clusterName={cluster_name}
-████████████- metadataStoreUrl={metadata_store_url}
-████████████- configurationMetadataStoreUrl={metadata_store_url}
-████████████- bookkeeperMetadataServiceUri={bookkeeper_metadata_service_uri}
-████████████- managedLedgerDefaultEnsembleSize={managed_ledger_default_ensemble_size}
-████████████- managedLedgerDefaultWriteQuorum={managed_ledger_default_write_quorum}
-████████████- managedLedgerDefaultAckQuorum={managed_ledger_default_ack_quorum}
-████████████- managedLedgerCacheEvictionFrequency=1000
-████████████- PULSAR_MEM=-Xms256m -Xmx256m -XX:MaxDirectMemorySize=256m -XX:+ExitOnOutOfMemoryError
-████████command: bash -c \"bin/apply-config-from-env.py conf/broker.conf && exec bin/pulsar broker\"
-████████healthcheck:
-████████████test: [\"CMD\", \"curl\", \"--fail\", \"http://127.0.0.1:8080/admin/v2/brokers/health\"]
-████████████interval: 10s
-████████████timeout: 5s
-████████████retries: 20
-████████depends_on:
-{depends_on_zookeeper_template}
-{depends_on_bookies_template}
-████████deploy:
-████████████resources:
-████████████████limits:
-████████████████████memory: 512M
-████████networks:
-████████████- pulsar-net-{instance_name}
-"}
-    .trim()
-    .to_string()
+    for i in 0..instance_config.num_bookies {
+        service.depends_on(
+            bookie_service_name(&cluster_name, i),
+            DependsOnCondition::ServiceHealthy,
+        );
+    }
+
+    if instance_config.tls {
+        let service_name = broker_service_name(&cluster_name, broker_index);
+        service
+            .environment
+            .extend(broker_tls_environment(&service_name));
+        service.volumes.extend(certs_volumes(&service_name));
+    }
+
+    if let Some(auth_config) = &instance_config.auth {
+        service
+            .environment
+            .extend(broker_auth_environment(auth_config));
+        service.volumes.push(auth_volume());
+    }
+
+    if instance_config.functions_worker == FunctionsWorker::Broker {
+        service
+            .environment
+            .extend(broker_functions_environment(&cluster_name));
+        service.command = Service::bash_command(
+            "bin/gen-yml-from-env.py conf/functions_worker.yml && bin/apply-config-from-env.py conf/broker.conf && exec bin/pulsar broker",
+        );
+        service.volumes.push(functions_volume(&instance_config));
+    }
+
+    if instance_config.with_tiered_storage {
+        service
+            .environment
+            .extend(broker_offload_environment(&cluster_name));
+        service.depends_on(
+            minio_create_bucket_job_service_name(),
+            DependsOnCondition::ServiceCompletedSuccessfully,
+        );
+    }
+
+    apply_config_overrides(
+        &mut service.environment,
+        broker_config_overrides(&instance_config, cluster_index),
+    );
+
+    service
 }
 
-pub fn generate_bookie_template(
+pub fn generate_bookie_service(
     instance_name: String,
     instance_config: InstanceConfig,
     cluster_name: String,
+    cluster_index: u32,
     bookie_index: u32,
-) -> String {
-    let pulsar_version = instance_config.pulsar_version;
+) -> Service {
+    let pulsar_version = instance_config.pulsar_version.clone();
+
+    let metadata_service_uri = bookkeeper_metadata_service_uri(&instance_config, &cluster_name);
+
+    let bookie_resources = component_resources(&instance_config, Component::Bookie);
+
+    let mut bookie_env = IndexMap::from([
+        ("clusterName".to_string(), cluster_name.clone()),
+        ("metadataServiceUri".to_string(), metadata_service_uri),
+        ("useHostNameAsBookieID".to_string(), "true".to_string()),
+        (
+            "BOOKIE_MEM".to_string(),
+            bookie_resources.jvm_memory_options(),
+        ),
+    ]);
+    bookie_env.extend(bookie_storage_environment(&instance_config));
+
+    let mut service = Service {
+        image: format!("apachepulsar/pulsar:{pulsar_version}"),
+        user: Some("pulsar".to_string()),
+        hostname: Some(bookie_service_name(&cluster_name, bookie_index)),
+        restart: Some(RestartPolicy::OnFailure),
+        environment: bookie_env,
+        command: Service::bash_command("bin/apply-config-from-env.py conf/bookkeeper.conf; bin/apply-config-from-env.py conf/pulsar_env.sh; exec bin/pulsar bookie"),
+        volumes: vec![format!(
+            "bookie-data-{cluster_name}-{bookie_index}:/pulsar/data"
+        )],
+        healthcheck: Some(Healthcheck {
+            test: vec![
+                "CMD".to_string(),
+                "/pulsar/bin/bookkeeper".to_string(),
+                "shell".to_string(),
+                "bookiesanity".to_string(),
+            ],
+            interval: "10s".to_string(),
+            timeout: "30s".to_string(),
+            retries: 30,
+        }),
+        deploy: Some(bookie_resources.deploy()),
+        networks: vec![network_name(&instance_name)],
+        ..Default::default()
+    };
 
-    let zookeepers_per_cluster = instance_config.num_zookeepers;
+    service.depends_on(
+        pulsar_init_job_service_name(&cluster_name),
+        DependsOnCondition::ServiceCompletedSuccessfully,
+    );
 
-    let depends_on_zookeeper_template = (0..zookeepers_per_cluster)
-        .map(|i| format!("████████████zookeeper-{i}:\n████████████████condition: service_healthy"))
-        .collect::<Vec<String>>()
-        .join("\n");
+    for metadata_store_service_name in metadata_store_service_names(&instance_config) {
+        service.depends_on(
+            metadata_store_service_name,
+            DependsOnCondition::ServiceHealthy,
+        );
+    }
 
-    let metadata_service_uri = format!(
-        "zk+hierarchical://{}",
-        (0..zookeepers_per_cluster)
-            .map(|i| format!("zookeeper-{i}:2181"))
-            .collect::<Vec<String>>()
-            .join(";")
-    ) + &format!("/bookkeeper-{cluster_name}");
-
-    format! {"
-████# Bookie for cluster {cluster_name}
-████bookie-{cluster_name}-{bookie_index}:
-████████image: apachepulsar/pulsar:{pulsar_version}
-████████user: pulsar
-████████hostname: bookie-{cluster_name}-{bookie_index}
-████████restart: on-failure
-████████environment:
-████████████- clusterName={cluster_name}
-████████████- metadataServiceUri={metadata_service_uri}
-████████████- useHostNameAsBookieID=true
-████████████- BOOKIE_MEM=-Xms256m -Xmx256m -XX:MaxDirectMemorySize=256m
-████████████- dbStorage_writeCacheMaxSizeMb=16
-████████████- dbStorage_readAheadCacheMaxSizeMb=16
-████████████- dbStorage_rocksDB_writeBufferSizeMB=4
-████████████- dbStorage_rocksDB_sstSizeInMB=4
-████████████- dbStorage_rocksDB_blockSize=4096
-████████████- dbStorage_rocksDB_bloomFilterBitsPerKey=10
-████████████- dbStorage_rocksDB_numLevels=-1
-████████████- dbStorage_rocksDB_numFilesInLevel0=4
-████████████- dbStorage_rocksDB_maxSizeInLevel1MB=256
-████████depends_on:
-████████████pulsar-init-job-{cluster_name}:
-████████████████condition: service_completed_successfully
-{depends_on_zookeeper_template}
-████████command: bash -c \"bin/apply-config-from-env.py conf/bookkeeper.conf; bin/apply-config-from-env.py conf/pulsar_env.sh; exec bin/pulsar bookie\"
-████████volumes:
-████████████- bookie-data-{cluster_name}-{bookie_index}:/pulsar/data
-████████healthcheck:
-████████████test: [\"CMD\", \"/pulsar/bin/bookkeeper\", \"shell\", \"bookiesanity\"]
-████████████interval: 10s
-████████████timeout: 30s
-████████████retries: 30
-████████deploy:
-████████████resources:
-████████████████limits:
-████████████████████memory: 512M
-████████networks:
-████████████- pulsar-net-{instance_name}
-"}.trim().to_string()
+    if instance_config.tls {
+        let service_name = bookie_service_name(&cluster_name, bookie_index);
+        service
+            .environment
+            .extend(bookie_tls_environment(&service_name));
+        service.volumes.extend(certs_volumes(&service_name));
+    }
+
+    apply_config_overrides(
+        &mut service.environment,
+        bookie_config_overrides(&instance_config, cluster_index),
+    );
+
+    service
+}
+
+fn curl_healthcheck_test(url: &str, auth_config: Option<&AuthConfig>) -> Vec<String> {
+    match auth_config {
+        Some(auth_config) => vec![
+            "CMD-SHELL".to_string(),
+            format!(
+                "curl --fail {} {url}",
+                curl_auth_header(&auth_config.superuser_role)
+            ),
+        ],
+        None => ["CMD", "curl", "--fail", url]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect(),
+    }
 }
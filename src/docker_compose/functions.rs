@@ -0,0 +1,135 @@
+use indexmap::IndexMap;
+
+use super::auth::{auth_volume, functions_worker_auth_environment};
+use super::compose::{DependsOnCondition, Healthcheck, RestartPolicy, Service};
+use super::docker_compose::{broker_service_name, network_name};
+use super::metadata_store::configuration_metadata_store_url;
+use super::profiles::{component_resources, Component};
+use crate::instance_config::{FunctionsWorker, InstanceConfig};
+
+/// Directory with function and connector packages, relative to the instance directory.
+/// Used unless the instance config sets `functions_dir`.
+pub const DEFAULT_FUNCTIONS_DIR: &str = "functions";
+
+/// Where the functions directory is mounted in brokers, proxies and functions workers.
+pub const FUNCTIONS_MOUNT_PATH: &str = "/pulsar/user-functions";
+
+pub fn functions_worker_service_name(cluster_name: &str) -> String {
+    format!("functions-worker-{cluster_name}")
+}
+
+pub fn functions_volume(instance_config: &InstanceConfig) -> String {
+    let functions_dir = instance_config
+        .functions_dir
+        .clone()
+        .unwrap_or(format!("./{DEFAULT_FUNCTIONS_DIR}"));
+
+    format!("{functions_dir}:{FUNCTIONS_MOUNT_PATH}:ro")
+}
+
+/// functions_worker.yml settings, applied by `bin/gen-yml-from-env.py` from `PF_` variables.
+fn functions_worker_yml_environment(cluster_name: &str) -> IndexMap<String, String> {
+    IndexMap::from([
+        (
+            "PF_pulsarFunctionsCluster".to_string(),
+            cluster_name.to_string(),
+        ),
+        (
+            "PF_connectorsDirectory".to_string(),
+            FUNCTIONS_MOUNT_PATH.to_string(),
+        ),
+        (
+            "PF_functionsDirectory".to_string(),
+            FUNCTIONS_MOUNT_PATH.to_string(),
+        ),
+    ])
+}
+
+/// Enables the functions worker inside the broker.
+pub fn broker_functions_environment(cluster_name: &str) -> IndexMap<String, String> {
+    let mut environment =
+        IndexMap::from([("functionsWorkerEnabled".to_string(), "true".to_string())]);
+    environment.extend(functions_worker_yml_environment(cluster_name));
+    environment
+}
+
+/// Routes functions, sources and sinks admin API calls to the dedicated worker.
+pub fn proxy_functions_environment(cluster_name: &str) -> IndexMap<String, String> {
+    IndexMap::from([(
+        "functionWorkerWebServiceURL".to_string(),
+        format!(
+            "http://{}:6750",
+            functions_worker_service_name(cluster_name)
+        ),
+    )])
+}
+
+pub fn generate_functions_worker_service(
+    instance_name: String,
+    instance_config: InstanceConfig,
+    cluster_name: String,
+) -> Service {
+    let pulsar_version = instance_config.pulsar_version.clone();
+    let service_name = functions_worker_service_name(&cluster_name);
+    let broker_service_name = broker_service_name(&cluster_name, 0);
+    let resources = component_resources(&instance_config, Component::FunctionsWorker);
+
+    let mut environment = IndexMap::from([
+        ("PF_workerId".to_string(), service_name.clone()),
+        ("PF_workerHostname".to_string(), service_name.clone()),
+        ("PF_workerPort".to_string(), "6750".to_string()),
+        (
+            "PF_pulsarServiceUrl".to_string(),
+            format!("pulsar://{broker_service_name}:6650"),
+        ),
+        (
+            "PF_pulsarWebServiceUrl".to_string(),
+            format!("http://{broker_service_name}:8080"),
+        ),
+        (
+            "PF_configurationMetadataStoreUrl".to_string(),
+            configuration_metadata_store_url(&instance_config, &cluster_name),
+        ),
+        ("PULSAR_MEM".to_string(), resources.pulsar_mem()),
+    ]);
+    environment.extend(functions_worker_yml_environment(&cluster_name));
+
+    let mut service = Service {
+        image: format!("apachepulsar/pulsar:{pulsar_version}"),
+        user: Some("pulsar".to_string()),
+        hostname: Some(service_name),
+        restart: Some(RestartPolicy::OnFailure),
+        command: Service::bash_command(
+            "bin/gen-yml-from-env.py conf/functions_worker.yml && bin/apply-config-from-env.py conf/pulsar_env.sh && exec bin/pulsar functions-worker",
+        ),
+        environment,
+        healthcheck: Some(Healthcheck {
+            test: ["CMD", "curl", "--fail", "http://127.0.0.1:6750/admin/v2/worker/cluster"]
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect(),
+            interval: "10s".to_string(),
+            timeout: "5s".to_string(),
+            retries: 20,
+        }),
+        volumes: vec![functions_volume(&instance_config)],
+        deploy: Some(resources.deploy()),
+        networks: vec![network_name(&instance_name)],
+        ..Default::default()
+    };
+
+    if let Some(auth_config) = &instance_config.auth {
+        service
+            .environment
+            .extend(functions_worker_auth_environment(auth_config));
+        service.volumes.push(auth_volume());
+    }
+
+    service.depends_on(broker_service_name, DependsOnCondition::ServiceHealthy);
+
+    service
+}
+
+pub fn is_functions_worker_enabled(instance_config: &InstanceConfig) -> bool {
+    instance_config.functions_worker != FunctionsWorker::Disabled
+}
@@ -0,0 +1,248 @@
+use indexmap::IndexMap;
+
+use super::compose::{Deploy, Healthcheck, RestartPolicy, Service};
+use super::config_overrides::{apply_config_overrides, zookeeper_config_overrides};
+use super::docker_compose::network_name;
+use super::profiles::{component_resources, Component};
+use crate::instance_config::{ConfigurationStore, InstanceConfig, MetadataStore};
+
+const OXIA_IMAGE: &str = "streamnative/oxia:0.11.9";
+const ETCD_IMAGE: &str = "quay.io/coreos/etcd:v3.5.13";
+
+pub fn zookeeper_service_name(zookeeper_index: u32) -> String {
+    format!("zookeeper-{zookeeper_index}")
+}
+
+pub fn oxia_service_name() -> String {
+    "oxia".to_string()
+}
+
+pub fn etcd_service_name() -> String {
+    "etcd".to_string()
+}
+
+/// Services that have to be healthy before anything else that talks to the metadata store starts.
+pub fn metadata_store_service_names(instance_config: &InstanceConfig) -> Vec<String> {
+    match instance_config.metadata_store {
+        MetadataStore::Zookeeper => (0..instance_config.num_zookeepers)
+            .map(zookeeper_service_name)
+            .collect(),
+        MetadataStore::Oxia => vec![oxia_service_name()],
+        MetadataStore::Etcd => vec![etcd_service_name()],
+    }
+}
+
+/// Value for `metadataStoreUrl`
+pub fn metadata_store_url(instance_config: &InstanceConfig) -> String {
+    match instance_config.metadata_store {
+        MetadataStore::Zookeeper => (0..instance_config.num_zookeepers)
+            .map(|i| format!("zk:{}:2181", zookeeper_service_name(i)))
+            .collect::<Vec<String>>()
+            .join(","),
+        MetadataStore::Oxia => format!("oxia://{}:6648", oxia_service_name()),
+        MetadataStore::Etcd => format!("etcd:http://{}:2379", etcd_service_name()),
+    }
+}
+
+/// Value for `configurationMetadataStoreUrl`.
+/// Per-cluster configuration stores live under their own ZooKeeper chroot.
+pub fn configuration_metadata_store_url(
+    instance_config: &InstanceConfig,
+    cluster_name: &str,
+) -> String {
+    match instance_config.configuration_store {
+        ConfigurationStore::Shared => metadata_store_url(instance_config),
+        ConfigurationStore::PerCluster => format!(
+            "{}/configuration-{cluster_name}",
+            metadata_store_url(instance_config)
+        ),
+    }
+}
+
+/// Configuration metadata store URL passed to `pulsar initialize-cluster-metadata`
+pub fn cluster_configuration_metadata_store_url(
+    instance_config: &InstanceConfig,
+    cluster_name: &str,
+) -> String {
+    match instance_config.configuration_store {
+        ConfigurationStore::Shared => cluster_metadata_store_url(instance_config, cluster_name),
+        ConfigurationStore::PerCluster => {
+            configuration_metadata_store_url(instance_config, cluster_name)
+        }
+    }
+}
+
+/// Metadata store URL passed to `pulsar initialize-cluster-metadata`
+pub fn cluster_metadata_store_url(instance_config: &InstanceConfig, cluster_name: &str) -> String {
+    match instance_config.metadata_store {
+        MetadataStore::Zookeeper => format!("zk:{}:2181/{cluster_name}", zookeeper_service_name(0)),
+        MetadataStore::Oxia | MetadataStore::Etcd => metadata_store_url(instance_config),
+    }
+}
+
+/// Value for bookies `metadataServiceUri` and brokers `bookkeeperMetadataServiceUri`.
+/// The path of an Oxia URL is a namespace and etcd URLs have no chroot,
+/// so with these stores the BookKeeper metadata lives at the root of the metadata store.
+pub fn bookkeeper_metadata_service_uri(
+    instance_config: &InstanceConfig,
+    cluster_name: &str,
+) -> String {
+    match instance_config.metadata_store {
+        MetadataStore::Zookeeper => format!(
+            "zk://{}/bookkeeper-{cluster_name}",
+            (0..instance_config.num_zookeepers)
+                .map(|i| format!("{}:2181", zookeeper_service_name(i)))
+                .collect::<Vec<String>>()
+                .join(";")
+        ),
+        MetadataStore::Oxia | MetadataStore::Etcd => {
+            format!("metadata-store:{}", metadata_store_url(instance_config))
+        }
+    }
+}
+
+pub fn metadata_store_volumes(instance_config: &InstanceConfig) -> Vec<String> {
+    match instance_config.metadata_store {
+        MetadataStore::Zookeeper => (0..instance_config.num_zookeepers)
+            .map(|i| format!("zookeeper-data-{i}"))
+            .collect(),
+        MetadataStore::Oxia => vec!["oxia-data".to_string()],
+        MetadataStore::Etcd => vec!["etcd-data".to_string()],
+    }
+}
+
+pub fn generate_metadata_store_services(
+    instance_name: String,
+    instance_config: InstanceConfig,
+) -> IndexMap<String, Service> {
+    match instance_config.metadata_store {
+        MetadataStore::Zookeeper => (0..instance_config.num_zookeepers)
+            .map(|zookeeper_index| {
+                (
+                    zookeeper_service_name(zookeeper_index),
+                    generate_zookeeper_service(
+                        instance_name.clone(),
+                        instance_config.clone(),
+                        zookeeper_index,
+                    ),
+                )
+            })
+            .collect(),
+        MetadataStore::Oxia => {
+            IndexMap::from([(oxia_service_name(), generate_oxia_service(instance_name))])
+        }
+        MetadataStore::Etcd => {
+            IndexMap::from([(etcd_service_name(), generate_etcd_service(instance_name))])
+        }
+    }
+}
+
+pub fn generate_zookeeper_service(
+    instance_name: String,
+    instance_config: InstanceConfig,
+    zookeeper_index: u32,
+) -> Service {
+    let pulsar_version = instance_config.pulsar_version.clone();
+
+    let zookeepers_per_cluster = instance_config.num_zookeepers;
+    let zookeeper_servers = (0..zookeepers_per_cluster)
+        .map(|i| format!("server.{i}={}:2888:3888", zookeeper_service_name(i)))
+        .collect::<Vec<String>>();
+
+    let append_zookeeper_servers = zookeeper_servers
+        .iter()
+        .map(|server| format!("echo \"{}\" >> /pulsar/conf/zookeeper.conf", server))
+        .collect::<Vec<String>>()
+        .join("; ");
+
+    let resources = component_resources(&instance_config, Component::Zookeeper);
+
+    let create_my_id_if_not_exists = format!("if [ ! -f /pulsar/data/zookeeper/myid ]; then mkdir -p /pulsar/data/zookeeper && echo {zookeeper_index} > /pulsar/data/zookeeper/myid; fi");
+
+    let mut service = Service {
+        image: format!("apachepulsar/pulsar:{pulsar_version}"),
+        user: Some("pulsar".to_string()),
+        hostname: Some(zookeeper_service_name(zookeeper_index)),
+        restart: Some(RestartPolicy::OnFailure),
+        command: Service::bash_command(format!("bin/apply-config-from-env.py conf/zookeeper.conf && bin/apply-config-from-env.py conf/pulsar_env.sh && {append_zookeeper_servers} && {create_my_id_if_not_exists} && exec bin/pulsar zookeeper")),
+        environment: IndexMap::from([("PULSAR_MEM".to_string(), resources.pulsar_mem())]),
+        healthcheck: Some(Healthcheck {
+            test: vec!["CMD".to_string(), "bin/pulsar-zookeeper-ruok.sh".to_string()],
+            interval: "10s".to_string(),
+            timeout: "5s".to_string(),
+            retries: 10,
+        }),
+        volumes: vec![format!("zookeeper-data-{zookeeper_index}:/pulsar/data")],
+        deploy: Some(resources.deploy()),
+        networks: vec![network_name(&instance_name)],
+        ..Default::default()
+    };
+
+    apply_config_overrides(
+        &mut service.environment,
+        zookeeper_config_overrides(&instance_config),
+    );
+
+    service
+}
+
+pub fn generate_oxia_service(instance_name: String) -> Service {
+    Service {
+        image: OXIA_IMAGE.to_string(),
+        hostname: Some(oxia_service_name()),
+        restart: Some(RestartPolicy::OnFailure),
+        command: vec![
+            "oxia".to_string(),
+            "standalone".to_string(),
+            "--data-dir=/data/db".to_string(),
+            "--wal-dir=/data/wal".to_string(),
+        ],
+        healthcheck: Some(Healthcheck {
+            test: vec![
+                "CMD".to_string(),
+                "oxia".to_string(),
+                "health".to_string(),
+                "--port=6649".to_string(),
+            ],
+            interval: "10s".to_string(),
+            timeout: "5s".to_string(),
+            retries: 10,
+        }),
+        volumes: vec!["oxia-data:/data".to_string()],
+        deploy: Some(Deploy::with_memory_limit("256M")),
+        networks: vec![network_name(&instance_name)],
+        ..Default::default()
+    }
+}
+
+pub fn generate_etcd_service(instance_name: String) -> Service {
+    let etcd_service_name = etcd_service_name();
+
+    Service {
+        image: ETCD_IMAGE.to_string(),
+        hostname: Some(etcd_service_name.clone()),
+        restart: Some(RestartPolicy::OnFailure),
+        command: vec![
+            "etcd".to_string(),
+            format!("--name={etcd_service_name}"),
+            "--data-dir=/etcd-data".to_string(),
+            "--listen-client-urls=http://0.0.0.0:2379".to_string(),
+            format!("--advertise-client-urls=http://{etcd_service_name}:2379"),
+        ],
+        healthcheck: Some(Healthcheck {
+            test: vec![
+                "CMD".to_string(),
+                "etcdctl".to_string(),
+                "endpoint".to_string(),
+                "health".to_string(),
+            ],
+            interval: "10s".to_string(),
+            timeout: "5s".to_string(),
+            retries: 10,
+        }),
+        volumes: vec!["etcd-data:/etcd-data".to_string()],
+        deploy: Some(Deploy::with_memory_limit("256M")),
+        networks: vec![network_name(&instance_name)],
+        ..Default::default()
+    }
+}
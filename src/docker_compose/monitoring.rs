@@ -0,0 +1,266 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use super::clusters::cluster_instance_config;
+use super::compose::{
+    Config, DependsOnCondition, Deploy, Healthcheck, RestartPolicy, Service, ServiceConfig,
+};
+use super::docker_compose::{
+    bookie_service_name, broker_service_name, cluster_name, network_name, pulsar_proxy_service_name,
+};
+use super::metadata_store::{etcd_service_name, oxia_service_name, zookeeper_service_name};
+use crate::host_ports::{legacy_port, HostPorts};
+use crate::instance_config::{InstanceConfig, MetadataStore};
+
+const PROMETHEUS_IMAGE: &str = "prom/prometheus:v2.51.2";
+const GRAFANA_IMAGE: &str = "streamnative/apache-pulsar-grafana-dashboard:0.0.16";
+const PROMETHEUS_CONFIG_NAME: &str = "prometheus-config";
+
+pub fn prometheus_service_name() -> String {
+    "prometheus".to_string()
+}
+
+pub fn grafana_service_name(cluster_name: &str) -> String {
+    format!("grafana-{cluster_name}")
+}
+
+pub fn monitoring_volumes(instance_config: &InstanceConfig) -> Vec<String> {
+    if instance_config.with_monitoring {
+        vec!["prometheus-data".to_string()]
+    } else {
+        vec![]
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PrometheusConfig {
+    pub global: PrometheusGlobalConfig,
+    pub scrape_configs: Vec<ScrapeConfig>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PrometheusGlobalConfig {
+    pub scrape_interval: String,
+}
+
+/// Job names match the ones the Pulsar dashboards query.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScrapeConfig {
+    pub job_name: String,
+
+    /// Keeps the `cluster` label reported by Pulsar components
+    pub honor_labels: bool,
+
+    pub static_configs: Vec<StaticConfig>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StaticConfig {
+    pub targets: Vec<String>,
+    pub labels: IndexMap<String, String>,
+}
+
+fn static_config(cluster_name: Option<String>, targets: Vec<String>) -> StaticConfig {
+    StaticConfig {
+        targets,
+        labels: cluster_name
+            .map(|cluster_name| IndexMap::from([("cluster".to_string(), cluster_name)]))
+            .unwrap_or_default(),
+    }
+}
+
+pub fn generate_prometheus_config(instance_config: &InstanceConfig) -> PrometheusConfig {
+    let clusters = (0..instance_config.num_clusters)
+        .map(|cluster_index| {
+            (
+                cluster_name(cluster_index),
+                cluster_instance_config(instance_config, cluster_index),
+            )
+        })
+        .collect::<Vec<(String, InstanceConfig)>>();
+
+    let per_cluster = |service_names: &dyn Fn(&str, &InstanceConfig) -> Vec<String>, port: u16| {
+        clusters
+            .iter()
+            .map(|(cluster_name, cluster_instance_config)| {
+                static_config(
+                    Some(cluster_name.clone()),
+                    service_names(cluster_name, cluster_instance_config)
+                        .iter()
+                        .map(|service_name| format!("{service_name}:{port}"))
+                        .collect(),
+                )
+            })
+            .collect::<Vec<StaticConfig>>()
+    };
+
+    let brokers = per_cluster(
+        &|cluster_name, cluster_instance_config| {
+            (0..cluster_instance_config.num_brokers)
+                .map(|i| broker_service_name(cluster_name, i))
+                .collect()
+        },
+        8080,
+    );
+    let bookies = per_cluster(
+        &|cluster_name, cluster_instance_config| {
+            (0..cluster_instance_config.num_bookies)
+                .map(|i| bookie_service_name(cluster_name, i))
+                .collect()
+        },
+        8000,
+    );
+    let proxies = per_cluster(
+        &|cluster_name, _| vec![pulsar_proxy_service_name(cluster_name)],
+        8080,
+    );
+
+    let (metadata_store_job_name, metadata_store_targets) = match instance_config.metadata_store {
+        MetadataStore::Zookeeper => (
+            "zookeeper",
+            (0..instance_config.num_zookeepers)
+                .map(|i| format!("{}:8000", zookeeper_service_name(i)))
+                .collect(),
+        ),
+        MetadataStore::Oxia => ("oxia", vec![format!("{}:8080", oxia_service_name())]),
+        MetadataStore::Etcd => ("etcd", vec![format!("{}:2379", etcd_service_name())]),
+    };
+
+    let scrape_configs = [
+        ("broker", brokers),
+        ("bookie", bookies),
+        ("proxy", proxies),
+        (
+            metadata_store_job_name,
+            vec![static_config(None, metadata_store_targets)],
+        ),
+    ]
+    .into_iter()
+    .map(|(job_name, static_configs)| ScrapeConfig {
+        job_name: job_name.to_string(),
+        honor_labels: true,
+        static_configs,
+    })
+    .collect();
+
+    PrometheusConfig {
+        global: PrometheusGlobalConfig {
+            scrape_interval: "15s".to_string(),
+        },
+        scrape_configs,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MonitoringOutput {
+    /// Host port of each cluster's Grafana by cluster index
+    pub grafana_host_ports: Vec<u16>,
+    pub prometheus_host_url: String,
+    pub prometheus_host_port: u16,
+    #[serde(skip)]
+    pub services: IndexMap<String, Service>,
+    #[serde(skip)]
+    pub configs: IndexMap<String, Config>,
+}
+
+pub fn generate_monitoring(
+    instance_name: String,
+    instance_config: InstanceConfig,
+    host_ports: &mut HostPorts,
+) -> Result<MonitoringOutput> {
+    let prometheus_host_port = host_ports.allocate("prometheus", Some(9090))?;
+
+    let prometheus_config = serde_yaml::to_string(&generate_prometheus_config(&instance_config))?;
+
+    let mut prometheus = Service {
+        image: PROMETHEUS_IMAGE.to_string(),
+        restart: Some(RestartPolicy::OnFailure),
+        ports: vec![format!("{prometheus_host_port}:9090")],
+        volumes: vec!["prometheus-data:/prometheus".to_string()],
+        configs: vec![ServiceConfig {
+            source: PROMETHEUS_CONFIG_NAME.to_string(),
+            target: "/etc/prometheus/prometheus.yml".to_string(),
+        }],
+        healthcheck: Some(Healthcheck {
+            test: [
+                "CMD",
+                "wget",
+                "--quiet",
+                "--spider",
+                "http://127.0.0.1:9090/-/ready",
+            ]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect(),
+            interval: "10s".to_string(),
+            timeout: "5s".to_string(),
+            retries: 10,
+        }),
+        deploy: Some(Deploy::with_memory_limit("256M")),
+        networks: vec![network_name(&instance_name)],
+        ..Default::default()
+    };
+
+    // Nothing to scrape until the proxies are up
+    for cluster_index in 0..instance_config.num_clusters {
+        prometheus.depends_on(
+            pulsar_proxy_service_name(&cluster_name(cluster_index)),
+            DependsOnCondition::ServiceHealthy,
+        );
+    }
+
+    let mut services = IndexMap::from([(prometheus_service_name(), prometheus)]);
+    let mut grafana_host_ports = Vec::new();
+
+    // The dashboards are rendered for the single cluster in PULSAR_CLUSTER, so each cluster gets its own Grafana
+    for cluster_index in 0..instance_config.num_clusters {
+        let cluster_name = cluster_name(cluster_index);
+        let grafana_host_port = host_ports.allocate(
+            &format!("{cluster_name}.grafana"),
+            legacy_port(cluster_index, 3000),
+        )?;
+
+        let mut grafana = Service {
+            image: GRAFANA_IMAGE.to_string(),
+            restart: Some(RestartPolicy::OnFailure),
+            ports: vec![format!("{grafana_host_port}:3000")],
+            environment: IndexMap::from([
+                (
+                    "PULSAR_PROMETHEUS_URL".to_string(),
+                    format!("http://{}:9090", prometheus_service_name()),
+                ),
+                ("PULSAR_CLUSTER".to_string(), cluster_name.clone()),
+                ("GF_AUTH_ANONYMOUS_ENABLED".to_string(), "true".to_string()),
+                (
+                    "GF_AUTH_ANONYMOUS_ORG_ROLE".to_string(),
+                    "Admin".to_string(),
+                ),
+            ]),
+            deploy: Some(Deploy::with_memory_limit("256M")),
+            networks: vec![network_name(&instance_name)],
+            ..Default::default()
+        };
+
+        grafana.depends_on(
+            prometheus_service_name(),
+            DependsOnCondition::ServiceHealthy,
+        );
+
+        services.insert(grafana_service_name(&cluster_name), grafana);
+        grafana_host_ports.push(grafana_host_port);
+    }
+
+    Ok(MonitoringOutput {
+        grafana_host_ports,
+        prometheus_host_url: format!("http://localhost:{prometheus_host_port}"),
+        prometheus_host_port,
+        services,
+        configs: IndexMap::from([(
+            PROMETHEUS_CONFIG_NAME.to_string(),
+            Config {
+                content: prometheus_config,
+            },
+        )]),
+    })
+}
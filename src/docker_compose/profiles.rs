@@ -0,0 +1,167 @@
+use indexmap::IndexMap;
+
+use super::compose::{Deploy, ResourceLimits, Resources};
+use crate::instance_config::{ComponentResources, InstanceConfig, ResourceProfile};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Component {
+    Zookeeper,
+    Broker,
+    Bookie,
+    Proxy,
+    FunctionsWorker,
+}
+
+impl Component {
+    pub const ALL: [Component; 5] = [
+        Component::Zookeeper,
+        Component::Broker,
+        Component::Bookie,
+        Component::Proxy,
+        Component::FunctionsWorker,
+    ];
+
+    /// Key of the component in the `component_resources` section
+    pub fn name(&self) -> &'static str {
+        match self {
+            Component::Zookeeper => "zookeeper",
+            Component::Broker => "broker",
+            Component::Bookie => "bookie",
+            Component::Proxy => "proxy",
+            Component::FunctionsWorker => "functions_worker",
+        }
+    }
+}
+
+/// Resources of a component after applying overrides to the profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JvmResources {
+    pub heap: String,
+    pub direct_memory: String,
+    pub memory: String,
+    pub cpus: Option<String>,
+}
+
+impl JvmResources {
+    fn new(heap: &str, direct_memory: &str, memory: &str, cpus: Option<&str>) -> Self {
+        JvmResources {
+            heap: heap.to_string(),
+            direct_memory: direct_memory.to_string(),
+            memory: memory.to_string(),
+            cpus: cpus.map(|cpus| cpus.to_string()),
+        }
+    }
+
+    /// Value for `BOOKIE_MEM`
+    pub fn jvm_memory_options(&self) -> String {
+        format!(
+            "-Xms{heap} -Xmx{heap} -XX:MaxDirectMemorySize={}",
+            self.direct_memory,
+            heap = self.heap
+        )
+    }
+
+    /// Value for `PULSAR_MEM`
+    pub fn pulsar_mem(&self) -> String {
+        format!("{} -XX:+ExitOnOutOfMemoryError", self.jvm_memory_options())
+    }
+
+    pub fn deploy(&self) -> Deploy {
+        Deploy {
+            resources: Resources {
+                limits: ResourceLimits {
+                    memory: Some(self.memory.clone()),
+                    cpus: self.cpus.clone(),
+                },
+            },
+        }
+    }
+}
+
+/// Laptop values are the ones puls used before profiles were introduced.
+fn profile_resources(profile: ResourceProfile, component: Component) -> JvmResources {
+    match (profile, component) {
+        (ResourceProfile::Tiny, Component::Zookeeper | Component::Proxy) => {
+            JvmResources::new("64m", "64m", "192M", None)
+        }
+        (ResourceProfile::Tiny, _) => JvmResources::new("192m", "128m", "384M", None),
+
+        (ResourceProfile::Laptop, Component::Zookeeper | Component::Proxy) => {
+            JvmResources::new("128m", "128m", "256M", None)
+        }
+        (ResourceProfile::Laptop, _) => JvmResources::new("256m", "256m", "512M", None),
+
+        (ResourceProfile::Perf, Component::Zookeeper) => {
+            JvmResources::new("512m", "256m", "1G", Some("1"))
+        }
+        (ResourceProfile::Perf, Component::Proxy | Component::FunctionsWorker) => {
+            JvmResources::new("512m", "512m", "1536M", Some("1"))
+        }
+        (ResourceProfile::Perf, Component::Broker) => {
+            JvmResources::new("1g", "1g", "2560M", Some("2"))
+        }
+        (ResourceProfile::Perf, Component::Bookie) => {
+            JvmResources::new("1g", "1g", "3G", Some("2"))
+        }
+    }
+}
+
+pub fn component_resource_overrides(
+    instance_config: &InstanceConfig,
+    component: Component,
+) -> Option<&ComponentResources> {
+    let overrides = instance_config.component_resources.as_ref()?;
+    match component {
+        Component::Zookeeper => overrides.zookeeper.as_ref(),
+        Component::Broker => overrides.broker.as_ref(),
+        Component::Bookie => overrides.bookie.as_ref(),
+        Component::Proxy => overrides.proxy.as_ref(),
+        Component::FunctionsWorker => overrides.functions_worker.as_ref(),
+    }
+}
+
+pub fn component_resources(instance_config: &InstanceConfig, component: Component) -> JvmResources {
+    let mut resources = profile_resources(instance_config.resource_profile, component);
+
+    if let Some(overrides) = component_resource_overrides(instance_config, component) {
+        if let Some(heap) = &overrides.heap {
+            resources.heap = heap.clone();
+        }
+        if let Some(direct_memory) = &overrides.direct_memory {
+            resources.direct_memory = direct_memory.clone();
+        }
+        if let Some(memory) = &overrides.memory {
+            resources.memory = memory.clone();
+        }
+        if let Some(cpus) = &overrides.cpus {
+            resources.cpus = Some(cpus.clone());
+        }
+    }
+
+    resources
+}
+
+/// DbLedgerStorage caches live in direct memory, so they grow with the profile.
+pub fn bookie_storage_environment(instance_config: &InstanceConfig) -> IndexMap<String, String> {
+    let (cache_size_mb, write_buffer_size_mb, sst_size_mb) = match instance_config.resource_profile
+    {
+        ResourceProfile::Tiny => ("8", "4", "4"),
+        ResourceProfile::Laptop => ("16", "4", "4"),
+        ResourceProfile::Perf => ("256", "64", "64"),
+    };
+
+    [
+        ("dbStorage_writeCacheMaxSizeMb", cache_size_mb),
+        ("dbStorage_readAheadCacheMaxSizeMb", cache_size_mb),
+        ("dbStorage_rocksDB_writeBufferSizeMB", write_buffer_size_mb),
+        ("dbStorage_rocksDB_sstSizeInMB", sst_size_mb),
+        ("dbStorage_rocksDB_blockSize", "4096"),
+        ("dbStorage_rocksDB_bloomFilterBitsPerKey", "10"),
+        ("dbStorage_rocksDB_numLevels", "-1"),
+        ("dbStorage_rocksDB_numFilesInLevel0", "4"),
+        ("dbStorage_rocksDB_maxSizeInLevel1MB", "256"),
+    ]
+    .iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect()
+}
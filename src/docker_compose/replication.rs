@@ -0,0 +1,42 @@
+use anyhow::{bail, Result};
+
+use super::docker_compose::cluster_name;
+use crate::instance_config::{
+    ConfigurationStore, InstanceConfig, MetadataStore, ReplicationTopology,
+};
+
+/// Namespace policies live in the configuration store, so with a shared store every cluster
+/// sees the same replication clusters and only a full mesh can be described.
+pub fn validate_replication(instance_config: &InstanceConfig) -> Result<()> {
+    if instance_config.configuration_store == ConfigurationStore::PerCluster
+        && instance_config.metadata_store != MetadataStore::Zookeeper
+    {
+        bail!(
+            "Per-cluster configuration stores are only supported with the zookeeper metadata store"
+        );
+    }
+
+    if instance_config.replication_topology != ReplicationTopology::FullMesh
+        && instance_config.configuration_store == ConfigurationStore::Shared
+        && instance_config.num_clusters > 1
+    {
+        bail!("One-way and hub-and-spoke replication need per-cluster configuration stores, use --configuration-store per-cluster");
+    }
+
+    Ok(())
+}
+
+/// Clusters the `global/default` namespace of the cluster is replicated to, the cluster included.
+pub fn replication_clusters(instance_config: &InstanceConfig, cluster_index: u32) -> Vec<String> {
+    let all_cluster_names = (0..instance_config.num_clusters).map(cluster_name);
+
+    if cluster_index == 0 {
+        return all_cluster_names.collect();
+    }
+
+    match instance_config.replication_topology {
+        ReplicationTopology::FullMesh => all_cluster_names.collect(),
+        ReplicationTopology::OneWay => vec![cluster_name(cluster_index)],
+        ReplicationTopology::HubAndSpoke => vec![cluster_name(0), cluster_name(cluster_index)],
+    }
+}
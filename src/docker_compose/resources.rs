@@ -0,0 +1,130 @@
+use serde_json::json;
+
+use super::docker_compose::cluster_name;
+use crate::instance_config::{InstanceConfig, NamespaceConfig, SchemaConfig, TopicConfig};
+
+/// Runs `create` only if `exists` fails, so jobs that run on every start don't fail
+/// on resources created by an earlier run or by the job of another cluster.
+pub fn create_if_missing(exists: &str, create: &str) -> String {
+    format!("({exists} > /dev/null 2>&1 || {create})")
+}
+
+/// Script that creates the tenants, namespaces and topics from the `resources` section.
+/// Every step is safe to repeat: existing resources are skipped or updated and policies
+/// are set again, so the script runs in the post-cluster-create job of each cluster on
+/// every start.
+pub fn apply_resources_script(pulsar_admin: &str, instance_config: &InstanceConfig) -> String {
+    let Some(resources) = &instance_config.resources else {
+        return String::new();
+    };
+
+    let all_cluster_names = (0..instance_config.num_clusters)
+        .map(cluster_name)
+        .collect::<Vec<String>>();
+
+    let mut commands: Vec<String> = Vec::new();
+
+    for tenant in &resources.tenants {
+        let allowed_clusters = if tenant.allowed_clusters.is_empty() {
+            all_cluster_names.join(",")
+        } else {
+            tenant.allowed_clusters.join(",")
+        };
+
+        commands.push(format!(
+            "({pulsar_admin} tenants create --allowed-clusters {allowed_clusters} {tenant} || {pulsar_admin} tenants update --allowed-clusters {allowed_clusters} {tenant})",
+            tenant = tenant.name
+        ));
+
+        for namespace in &tenant.namespaces {
+            let namespace_name = format!("{}/{}", tenant.name, namespace.name);
+            commands.extend(namespace_commands(pulsar_admin, &namespace_name, namespace));
+        }
+    }
+
+    commands
+        .iter()
+        .map(|command| format!("{command};"))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn namespace_commands(
+    pulsar_admin: &str,
+    namespace_name: &str,
+    namespace: &NamespaceConfig,
+) -> Vec<String> {
+    let mut commands = vec![create_if_missing(
+        &format!("{pulsar_admin} namespaces policies {namespace_name}"),
+        &format!("{pulsar_admin} namespaces create {namespace_name}"),
+    )];
+
+    if !namespace.replication_clusters.is_empty() {
+        commands.push(format!(
+            "{pulsar_admin} namespaces set-clusters --clusters {} {namespace_name}",
+            namespace.replication_clusters.join(",")
+        ));
+    }
+
+    if let Some(retention) = &namespace.retention {
+        commands.push(format!(
+            "{pulsar_admin} namespaces set-retention --time {} --size {} {namespace_name}",
+            retention.time, retention.size
+        ));
+    }
+
+    if let Some(message_ttl) = &namespace.message_ttl {
+        commands.push(format!(
+            "{pulsar_admin} namespaces set-message-ttl --messageTTL {message_ttl} {namespace_name}"
+        ));
+    }
+
+    for topic in &namespace.topics {
+        commands.extend(topic_commands(pulsar_admin, namespace_name, topic));
+    }
+
+    commands
+}
+
+fn topic_commands(pulsar_admin: &str, namespace_name: &str, topic: &TopicConfig) -> Vec<String> {
+    let topic_name = format!("persistent://{namespace_name}/{}", topic.name);
+
+    let mut commands = vec![if topic.partitions > 0 {
+        format!(
+            "({pulsar_admin} topics create-partitioned-topic --partitions {partitions} {topic_name} || {pulsar_admin} topics update-partitioned-topic --partitions {partitions} {topic_name})",
+            partitions = topic.partitions
+        )
+    } else {
+        create_if_missing(
+            &format!("{pulsar_admin} topics stats {topic_name}"),
+            &format!("{pulsar_admin} topics create {topic_name}"),
+        )
+    }];
+
+    if let Some(schema) = &topic.schema {
+        commands.push(schema_upload_command(pulsar_admin, &topic_name, schema));
+    }
+
+    commands
+}
+
+/// Uploading the same schema again doesn't create a new schema version.
+fn schema_upload_command(pulsar_admin: &str, topic_name: &str, schema: &SchemaConfig) -> String {
+    let schema_file = format!("/tmp/schema-{}.json", topic_name.replace([':', '/'], "_"));
+    let schema_json = json!({
+        "type": schema.schema_type,
+        "schema": schema.definition,
+        "properties": {},
+    })
+    .to_string();
+
+    format!(
+        "echo {} > {schema_file} && {pulsar_admin} schemas upload --filename {schema_file} {topic_name}",
+        shell_quote(&schema_json)
+    )
+}
+
+/// Quotes the value for bash. `$` is doubled so docker compose doesn't interpolate it.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''").replace('$', "$$"))
+}
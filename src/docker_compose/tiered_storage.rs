@@ -0,0 +1,156 @@
+use indexmap::IndexMap;
+
+use super::compose::{DependsOnCondition, Deploy, Healthcheck, RestartPolicy, Service};
+use super::docker_compose::{cluster_name, network_name};
+use crate::instance_config::InstanceConfig;
+
+const MINIO_IMAGE: &str = "minio/minio:RELEASE.2024-04-18T19-09-19Z";
+const MINIO_CLIENT_IMAGE: &str = "minio/mc:RELEASE.2024-04-18T16-45-29Z";
+const MINIO_ACCESS_KEY: &str = "minioadmin";
+const MINIO_SECRET_KEY: &str = "minioadmin";
+const MINIO_REGION: &str = "us-east-1";
+
+/// Offload threshold set on namespaces created by puls, kept low so that offload
+/// kicks in quickly on a local instance.
+pub const OFFLOAD_THRESHOLD: &str = "10M";
+
+pub fn minio_service_name() -> String {
+    "minio".to_string()
+}
+
+pub fn minio_create_bucket_job_service_name() -> String {
+    "minio-create-bucket-job".to_string()
+}
+
+pub fn minio_endpoint() -> String {
+    format!("http://{}:9000", minio_service_name())
+}
+
+pub fn offload_bucket_name(cluster_name: &str) -> String {
+    format!("offload-{cluster_name}")
+}
+
+pub fn tiered_storage_volumes(instance_config: &InstanceConfig) -> Vec<String> {
+    if instance_config.with_tiered_storage {
+        vec!["minio-data".to_string()]
+    } else {
+        vec![]
+    }
+}
+
+/// Broker settings for offloading ledgers of the given cluster to its MinIO bucket.
+pub fn broker_offload_environment(cluster_name: &str) -> IndexMap<String, String> {
+    IndexMap::from([
+        (
+            "managedLedgerOffloadDriver".to_string(),
+            "aws-s3".to_string(),
+        ),
+        (
+            "s3ManagedLedgerOffloadBucket".to_string(),
+            offload_bucket_name(cluster_name),
+        ),
+        (
+            "s3ManagedLedgerOffloadRegion".to_string(),
+            MINIO_REGION.to_string(),
+        ),
+        (
+            "s3ManagedLedgerOffloadServiceEndpoint".to_string(),
+            minio_endpoint(),
+        ),
+        (
+            "AWS_ACCESS_KEY_ID".to_string(),
+            MINIO_ACCESS_KEY.to_string(),
+        ),
+        (
+            "AWS_SECRET_ACCESS_KEY".to_string(),
+            MINIO_SECRET_KEY.to_string(),
+        ),
+    ])
+}
+
+pub fn generate_tiered_storage_services(
+    instance_name: String,
+    instance_config: InstanceConfig,
+) -> IndexMap<String, Service> {
+    if !instance_config.with_tiered_storage {
+        return IndexMap::new();
+    }
+
+    IndexMap::from([
+        (
+            minio_service_name(),
+            generate_minio_service(instance_name.clone()),
+        ),
+        (
+            minio_create_bucket_job_service_name(),
+            generate_minio_create_bucket_job_service(instance_name, instance_config),
+        ),
+    ])
+}
+
+pub fn generate_minio_service(instance_name: String) -> Service {
+    Service {
+        image: MINIO_IMAGE.to_string(),
+        hostname: Some(minio_service_name()),
+        restart: Some(RestartPolicy::OnFailure),
+        command: vec![
+            "server".to_string(),
+            "/data".to_string(),
+            "--console-address".to_string(),
+            ":9001".to_string(),
+        ],
+        environment: IndexMap::from([
+            ("MINIO_ROOT_USER".to_string(), MINIO_ACCESS_KEY.to_string()),
+            (
+                "MINIO_ROOT_PASSWORD".to_string(),
+                MINIO_SECRET_KEY.to_string(),
+            ),
+            ("MINIO_REGION".to_string(), MINIO_REGION.to_string()),
+        ]),
+        healthcheck: Some(Healthcheck {
+            test: vec![
+                "CMD".to_string(),
+                "mc".to_string(),
+                "ready".to_string(),
+                "local".to_string(),
+            ],
+            interval: "10s".to_string(),
+            timeout: "5s".to_string(),
+            retries: 10,
+        }),
+        volumes: vec!["minio-data:/data".to_string()],
+        deploy: Some(Deploy::with_memory_limit("256M")),
+        networks: vec![network_name(&instance_name)],
+        ..Default::default()
+    }
+}
+
+/// Creates an offload bucket per cluster. Brokers don't start until it completes.
+pub fn generate_minio_create_bucket_job_service(
+    instance_name: String,
+    instance_config: InstanceConfig,
+) -> Service {
+    let minio_endpoint = minio_endpoint();
+    let set_alias =
+        format!("mc alias set minio {minio_endpoint} {MINIO_ACCESS_KEY} {MINIO_SECRET_KEY}");
+    let create_buckets = (0..instance_config.num_clusters)
+        .map(|i| {
+            let bucket = offload_bucket_name(&cluster_name(i));
+            format!("mc mb --ignore-existing --region {MINIO_REGION} minio/{bucket}")
+        })
+        .collect::<Vec<String>>()
+        .join(" && ");
+
+    let mut service = Service {
+        image: MINIO_CLIENT_IMAGE.to_string(),
+        restart: Some(RestartPolicy::OnFailure),
+        entrypoint: vec!["/bin/sh".to_string(), "-c".to_string()],
+        command: vec![format!("{set_alias} && {create_buckets}")],
+        networks: vec![network_name(&instance_name)],
+        ..Default::default()
+    };
+
+    service.depends_on(minio_service_name(), DependsOnCondition::ServiceHealthy);
+
+    service
+}
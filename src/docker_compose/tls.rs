@@ -0,0 +1,132 @@
+use indexmap::IndexMap;
+
+use super::clusters::cluster_instance_config;
+use super::docker_compose::{
+    bookie_service_name, broker_service_name, cluster_name, pulsar_proxy_service_name,
+};
+use crate::certs::{ca_cert_file_name, cert_file_name, key_file_name};
+use crate::instance_config::InstanceConfig;
+
+/// Directory with generated certificates, relative to the instance directory.
+pub const CERTS_DIR: &str = "certs";
+
+const CERTS_MOUNT_PATH: &str = "/pulsar/certs";
+
+/// Mounts the CA certificate and the service's own certificate and key.
+/// The CA key stays on the host, so containers can't issue certificates.
+pub fn certs_volumes(service_name: &str) -> Vec<String> {
+    [
+        ca_cert_file_name(),
+        cert_file_name(service_name),
+        key_file_name(service_name),
+    ]
+    .iter()
+    .map(|file_name| format!("./{CERTS_DIR}/{file_name}:{CERTS_MOUNT_PATH}/{file_name}:ro"))
+    .collect()
+}
+
+/// Services that need a certificate along with the host names it should be valid for.
+/// Proxies are also reachable from the host, so their certificates include localhost.
+pub fn tls_certificates(instance_config: &InstanceConfig) -> Vec<(String, Vec<String>)> {
+    let mut certificates = Vec::new();
+
+    for cluster_index in 0..instance_config.num_clusters {
+        let cluster_name = cluster_name(cluster_index);
+        let cluster_instance_config = cluster_instance_config(instance_config, cluster_index);
+
+        for i in 0..cluster_instance_config.num_brokers {
+            let service_name = broker_service_name(&cluster_name, i);
+            certificates.push((service_name.clone(), vec![service_name]));
+        }
+
+        for i in 0..cluster_instance_config.num_bookies {
+            let service_name = bookie_service_name(&cluster_name, i);
+            certificates.push((service_name.clone(), vec![service_name]));
+        }
+
+        let service_name = pulsar_proxy_service_name(&cluster_name);
+        certificates.push((
+            service_name.clone(),
+            vec![
+                service_name,
+                "localhost".to_string(),
+                "127.0.0.1".to_string(),
+            ],
+        ));
+    }
+
+    certificates
+}
+
+fn mounted_ca_cert_file() -> String {
+    format!("{CERTS_MOUNT_PATH}/{}", ca_cert_file_name())
+}
+
+fn mounted_cert_file(service_name: &str) -> String {
+    format!("{CERTS_MOUNT_PATH}/{}", cert_file_name(service_name))
+}
+
+fn mounted_key_file(service_name: &str) -> String {
+    format!("{CERTS_MOUNT_PATH}/{}", key_file_name(service_name))
+}
+
+fn to_environment(env: &[(&str, String)]) -> IndexMap<String, String> {
+    env.iter()
+        .map(|(k, v)| (k.to_string(), v.clone()))
+        .collect()
+}
+
+/// Enables TLS ports on the broker and TLS from the broker to bookies.
+pub fn broker_tls_environment(service_name: &str) -> IndexMap<String, String> {
+    to_environment(&[
+        ("brokerServicePortTls", "6651".to_string()),
+        ("webServicePortTls", "8443".to_string()),
+        ("tlsCertificateFilePath", mounted_cert_file(service_name)),
+        ("tlsKeyFilePath", mounted_key_file(service_name)),
+        ("tlsTrustCertsFilePath", mounted_ca_cert_file()),
+        ("brokerClientTlsEnabled", "true".to_string()),
+        ("brokerClientTrustCertsFilePath", mounted_ca_cert_file()),
+        ("bookkeeperTLSClientAuthentication", "true".to_string()),
+        ("bookkeeperTLSKeyFileType", "PEM".to_string()),
+        ("bookkeeperTLSKeyFilePath", mounted_key_file(service_name)),
+        (
+            "bookkeeperTLSCertificateFilePath",
+            mounted_cert_file(service_name),
+        ),
+        ("bookkeeperTLSTrustCertTypes", "PEM".to_string()),
+        ("bookkeeperTLSTrustCertsFilePath", mounted_ca_cert_file()),
+    ])
+}
+
+/// Enables TLS ports on the proxy and TLS from the proxy to brokers.
+pub fn proxy_tls_environment(service_name: &str) -> IndexMap<String, String> {
+    to_environment(&[
+        ("servicePortTls", "6651".to_string()),
+        ("webServicePortTls", "8443".to_string()),
+        ("tlsCertificateFilePath", mounted_cert_file(service_name)),
+        ("tlsKeyFilePath", mounted_key_file(service_name)),
+        ("tlsTrustCertsFilePath", mounted_ca_cert_file()),
+        ("tlsEnabledWithBroker", "true".to_string()),
+        ("brokerClientTrustCertsFilePath", mounted_ca_cert_file()),
+    ])
+}
+
+/// Makes the bookie accept TLS connections from brokers. These settings are commented out
+/// in the default bookkeeper.conf, so they are passed with the `PULSAR_PREFIX_` prefix.
+pub fn bookie_tls_environment(service_name: &str) -> IndexMap<String, String> {
+    to_environment(&[
+        (
+            "PULSAR_PREFIX_tlsProviderFactoryClass",
+            "org.apache.bookkeeper.tls.TLSContextFactory".to_string(),
+        ),
+        ("PULSAR_PREFIX_tlsClientAuthentication", "true".to_string()),
+        ("PULSAR_PREFIX_tlsKeyStoreType", "PEM".to_string()),
+        ("PULSAR_PREFIX_tlsKeyStore", mounted_key_file(service_name)),
+        (
+            "PULSAR_PREFIX_tlsCertificatePath",
+            mounted_cert_file(service_name),
+        ),
+        ("PULSAR_PREFIX_tlsTrustStoreType", "PEM".to_string()),
+        ("PULSAR_PREFIX_tlsTrustStore", mounted_ca_cert_file()),
+    ])
+}
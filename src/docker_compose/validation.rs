@@ -0,0 +1,392 @@
+use anyhow::{anyhow, bail, Result};
+use regex::Regex;
+
+use super::auth::auth_roles;
+use super::clusters::{cluster_config, cluster_instance_config};
+use super::compose::ComposeFile;
+use super::config_overrides::{
+    all_config_overrides, managed_ledger_quorum, MANAGED_LEDGER_QUORUM_KEYS,
+};
+use super::docker_compose::{
+    cluster_name, post_cluster_create_job_service_name, pulsar_init_job_service_name,
+};
+use super::profiles::{component_resources, Component};
+use super::replication::validate_replication;
+use crate::host_ports::{legacy_port, DEFAULT_DYNAMIC_BASE_PORT};
+use crate::instance_config::{ConfigOverrides, InstanceConfig, MetadataStore};
+use crate::tokens::check_role;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    /// The instance can't start with this config
+    Error,
+    /// The instance starts, but likely not the way the user expects
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopologyIssue {
+    pub severity: Severity,
+    /// Instance config field the issue is about
+    pub field: String,
+    pub message: String,
+}
+
+fn error(field: &str, message: String) -> TopologyIssue {
+    TopologyIssue {
+        severity: Severity::Error,
+        field: field.to_string(),
+        message,
+    }
+}
+
+fn warning(field: &str, message: String) -> TopologyIssue {
+    TopologyIssue {
+        severity: Severity::Warning,
+        field: field.to_string(),
+        message,
+    }
+}
+
+/// Host ports the instance publishes, see `HostPorts::allocate` calls.
+pub fn num_host_ports(instance_config: &InstanceConfig) -> u32 {
+    let clusters = (0..instance_config.num_clusters)
+        .map(|cluster_index| {
+            if cluster_instance_config(instance_config, cluster_index).with_dekaf {
+                3
+            } else {
+                2
+            }
+        })
+        .sum::<u32>();
+    // Prometheus and a Grafana per cluster
+    let monitoring = if instance_config.with_monitoring {
+        1 + instance_config.num_clusters
+    } else {
+        0
+    };
+    clusters + monitoring
+}
+
+pub fn check_topology(instance_config: &InstanceConfig) -> Vec<TopologyIssue> {
+    let mut issues = Vec::new();
+
+    for (field, value) in [
+        ("num_clusters", instance_config.num_clusters),
+        ("num_brokers", instance_config.num_brokers),
+        ("num_bookies", instance_config.num_bookies),
+    ] {
+        if value == 0 {
+            issues.push(error(field, "must be at least 1".to_string()));
+        }
+    }
+
+    for (cluster_index, cluster_config) in instance_config.clusters.iter().enumerate() {
+        if cluster_index as u32 >= instance_config.num_clusters {
+            issues.push(warning(
+                &format!("clusters[{cluster_index}]"),
+                format!(
+                    "there is no {}, the entry is ignored, increase num_clusters to add it",
+                    cluster_name(cluster_index as u32)
+                ),
+            ));
+            continue;
+        }
+
+        for (field, value) in [
+            ("num_brokers", cluster_config.num_brokers),
+            ("num_bookies", cluster_config.num_bookies),
+        ] {
+            if value == Some(0) {
+                issues.push(error(
+                    &format!("clusters[{cluster_index}].{field}"),
+                    "must be at least 1".to_string(),
+                ));
+            }
+        }
+    }
+
+    if instance_config.metadata_store == MetadataStore::Zookeeper {
+        let num_zookeepers = instance_config.num_zookeepers;
+        if num_zookeepers == 0 {
+            issues.push(error("num_zookeepers", "must be at least 1".to_string()));
+        } else if num_zookeepers.is_multiple_of(2) {
+            issues.push(warning(
+                "num_zookeepers",
+                format!("{num_zookeepers} ZooKeeper nodes tolerate as many failures as {}, use an odd number", num_zookeepers - 1),
+            ));
+        }
+    }
+
+    if let Err(err) = validate_replication(instance_config) {
+        issues.push(error("replication_topology", err.to_string()));
+    }
+
+    issues.extend(check_config_overrides(instance_config));
+
+    for component in Component::ALL {
+        issues.extend(check_component_resources(instance_config, component, ""));
+    }
+
+    // Clusters with their own resources, instance-wide issues aren't repeated for each of them
+    for cluster_index in 0..instance_config.num_clusters {
+        if !cluster_config(instance_config, cluster_index).is_some_and(|cluster_config| {
+            cluster_config.resource_profile.is_some()
+                || cluster_config.component_resources.is_some()
+        }) {
+            continue;
+        }
+
+        let cluster_instance_config = cluster_instance_config(instance_config, cluster_index);
+        let prefix = format!("clusters[{cluster_index}].");
+        for component in Component::ALL {
+            for issue in check_component_resources(&cluster_instance_config, component, &prefix) {
+                let is_instance_wide = issues
+                    .iter()
+                    .any(|instance_issue| instance_issue.message == issue.message);
+                if !is_instance_wide {
+                    issues.push(issue);
+                }
+            }
+        }
+    }
+
+    if let Some(auth_config) = &instance_config.auth {
+        for role in auth_roles(auth_config) {
+            if let Err(err) = check_role(&role) {
+                issues.push(error("auth.roles", err.to_string()));
+            }
+        }
+    }
+
+    match instance_config.base_port {
+        Some(base_port) => {
+            let last_port = u32::from(base_port) + num_host_ports(instance_config) - 1;
+            if last_port > u32::from(u16::MAX) {
+                issues.push(error(
+                    "base_port",
+                    format!("the instance needs {} host ports, they don't fit between {base_port} and {}", num_host_ports(instance_config), u16::MAX),
+                ));
+            }
+        }
+        None => {
+            let first_unfit_cluster_index = (0..instance_config.num_clusters)
+                .find(|cluster_index| legacy_port(*cluster_index, 8080).is_none());
+            if let Some(cluster_index) = first_unfit_cluster_index {
+                issues.push(warning(
+                    "num_clusters",
+                    format!(
+                        "host ports of {} and later clusters don't fit the <cluster index><port> scheme, they are picked starting from {DEFAULT_DYNAMIC_BASE_PORT}",
+                        cluster_name(cluster_index)
+                    ),
+                ));
+            }
+        }
+    }
+
+    issues
+}
+
+/// `prefix` is prepended to field paths, e.g. `clusters[1].`
+fn check_component_resources(
+    instance_config: &InstanceConfig,
+    component: Component,
+    prefix: &str,
+) -> Vec<TopologyIssue> {
+    let mut issues = Vec::new();
+    let field = format!("{prefix}component_resources.{}", component.name());
+
+    let resources = component_resources(instance_config, component);
+    let sizes = [
+        ("heap", &resources.heap, true),
+        ("direct_memory", &resources.direct_memory, true),
+        ("memory", &resources.memory, false),
+    ]
+    .map(|(name, value, is_jvm_option)| {
+        let size = if is_jvm_option {
+            parse_jvm_memory(value)
+        } else {
+            parse_memory(value)
+        };
+        if size.is_err() {
+            issues.push(error(
+                &format!("{field}.{name}"),
+                format!("invalid size {value}, use e.g. 512m or 1g"),
+            ));
+        }
+        size.ok()
+    });
+
+    if let [Some(heap), Some(direct_memory), Some(memory)] = sizes {
+        if heap + direct_memory > memory {
+            issues.push(warning(
+                &field,
+                format!(
+                    "heap {} and direct memory {} don't fit into the {} container memory limit, the container may be killed",
+                    resources.heap, resources.direct_memory, resources.memory
+                ),
+            ));
+        }
+    }
+
+    if let Some(cpus) = &resources.cpus {
+        if !cpus.parse::<f64>().is_ok_and(|cpus| cpus > 0.0) {
+            issues.push(error(
+                &format!("{field}.cpus"),
+                format!("invalid CPU limit {cpus}, use e.g. 1.5"),
+            ));
+        }
+    }
+
+    issues
+}
+
+fn check_config_overrides(instance_config: &InstanceConfig) -> Vec<TopologyIssue> {
+    let mut issues = Vec::new();
+
+    for (field, overrides) in all_config_overrides(instance_config) {
+        for key in overrides.keys() {
+            if key.is_empty() || key.contains(|c: char| c.is_whitespace() || c == '=') {
+                issues.push(error(&field, format!("invalid setting name \"{key}\"")));
+            }
+        }
+    }
+
+    for cluster_index in 0..instance_config.num_clusters {
+        let cluster_instance_config = cluster_instance_config(instance_config, cluster_index);
+        let num_bookies = cluster_instance_config.num_bookies;
+        // Without bookies the quorums are 0, num_bookies is reported instead
+        if num_bookies == 0 {
+            continue;
+        }
+
+        let cluster_config = cluster_config(instance_config, cluster_index);
+        let is_quorum_overridden = |overrides: &ConfigOverrides| {
+            MANAGED_LEDGER_QUORUM_KEYS
+                .iter()
+                .any(|key| overrides.contains_key(*key))
+        };
+        // Default quorums always fit the bookies, only overrides can break them.
+        // Point to the field the override comes from
+        let field = if cluster_config
+            .is_some_and(|cluster_config| is_quorum_overridden(&cluster_config.broker_config))
+        {
+            format!("clusters[{cluster_index}].broker_config")
+        } else if is_quorum_overridden(&instance_config.broker_config) {
+            "broker_config".to_string()
+        } else {
+            continue;
+        };
+
+        let [ensemble_size, write_quorum, ack_quorum] =
+            match managed_ledger_quorum(instance_config, cluster_index) {
+                Ok(quorum) => quorum,
+                Err(err) => {
+                    issues.push(error(&field, err.to_string()));
+                    continue;
+                }
+            };
+
+        if ack_quorum > write_quorum || write_quorum > ensemble_size || ack_quorum == 0 {
+            issues.push(error(
+                &field,
+                format!("ledger quorums must satisfy 0 < ack quorum <= write quorum <= ensemble size, got {ack_quorum}, {write_quorum} and {ensemble_size}"),
+            ));
+        } else if ensemble_size > num_bookies {
+            issues.push(warning(
+                &field,
+                format!(
+                    "ledger ensemble size {ensemble_size} of {} is greater than {num_bookies} bookies, writes will fail",
+                    cluster_name(cluster_index),
+                ),
+            ));
+        }
+    }
+
+    issues
+}
+
+/// Fails on configs the instance can't start with. Returns warnings about risky ones.
+pub fn validate_topology(instance_config: &InstanceConfig) -> Result<Vec<String>> {
+    let issues = check_topology(instance_config);
+
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .map(|issue| format!("{}: {}", issue.field, issue.message))
+        .collect::<Vec<String>>();
+    if !errors.is_empty() {
+        bail!("Invalid instance config. {}", errors.join(". "));
+    }
+
+    Ok(issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Warning)
+        .map(|issue| format!("{}: {}", issue.field, issue.message))
+        .collect())
+}
+
+/// Parses compose memory limits, e.g. `512M`, `1g`, `256mb` or a number of bytes.
+pub fn parse_memory(memory: &str) -> Result<u64> {
+    let memory = memory.trim().to_lowercase();
+    let number_end = memory
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(memory.len());
+    let (number, unit) = memory.split_at(number_end);
+
+    let multiplier: u64 = match unit.trim_end_matches('b') {
+        "" => 1,
+        "k" => 1024,
+        "m" => 1024 * 1024,
+        "g" => 1024 * 1024 * 1024,
+        _ => return Err(anyhow!("Unknown memory unit in {memory}")),
+    };
+    let number = number
+        .parse::<f64>()
+        .map_err(|_| anyhow!("Invalid memory value {memory}"))?;
+
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// Init and post-create jobs exit once they're done, so they never run alongside
+/// all the other services.
+fn is_one_shot_job(service_name: &str) -> bool {
+    [
+        pulsar_init_job_service_name(""),
+        post_cluster_create_job_service_name(""),
+    ]
+    .iter()
+    .any(|job_name_prefix| service_name.starts_with(job_name_prefix))
+}
+
+/// Parses JVM memory sizes, e.g. `-Xmx512m`. Unlike compose, the JVM takes whole numbers only.
+pub fn parse_jvm_memory(memory: &str) -> Result<u64> {
+    let jvm_memory_regex = Regex::new(r"^\d+[kKmMgG]?$").unwrap();
+    if !jvm_memory_regex.is_match(memory) {
+        return Err(anyhow!("Invalid JVM memory size {memory}"));
+    }
+    parse_memory(memory)
+}
+
+/// Sum of memory limits of all long-running services. Services without a limit aren't counted.
+pub fn estimate_memory(docker_compose: &ComposeFile) -> Result<u64> {
+    docker_compose
+        .services
+        .iter()
+        .filter(|(service_name, _)| !is_one_shot_job(service_name))
+        .filter_map(|(_, service)| service.deploy.as_ref())
+        .filter_map(|deploy| deploy.resources.limits.memory.as_deref())
+        .map(parse_memory)
+        .sum()
+}
+
+pub fn format_memory(bytes: u64) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+    const GIB: f64 = 1024.0 * MIB;
+
+    if bytes as f64 >= GIB {
+        format!("{:.1} GiB", bytes as f64 / GIB)
+    } else {
+        format!("{:.0} MiB", bytes as f64 / MIB)
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::net::TcpListener;
+
+/// Ports are allocated from this base when the preferred port isn't available
+/// and the instance config doesn't pin a base port.
+pub const DEFAULT_DYNAMIC_BASE_PORT: u16 = 20000;
+
+/// Host ports assigned to an instance, persisted in the instance directory as `ports.yml`.
+/// Once a port is recorded it is reused on every start, so URLs stay stable.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct HostPorts {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_port: Option<u16>,
+
+    #[serde(default)]
+    pub ports: IndexMap<String, u16>,
+
+    /// Ports recorded by other instances. Never handed out by this allocator.
+    #[serde(skip)]
+    reserved: HashSet<u16>,
+}
+
+impl HostPorts {
+    pub fn new(base_port: Option<u16>) -> Self {
+        HostPorts {
+            base_port,
+            ..Default::default()
+        }
+    }
+
+    /// Drops recorded ports if the pinned base port changed since they were allocated.
+    pub fn with_base_port(self, base_port: Option<u16>) -> Self {
+        if self.base_port == base_port {
+            self
+        } else {
+            HostPorts {
+                reserved: self.reserved,
+                ..HostPorts::new(base_port)
+            }
+        }
+    }
+
+    pub fn reserve(&mut self, ports: impl IntoIterator<Item = u16>) {
+        self.reserved.extend(ports);
+    }
+
+    pub fn get(&self, key: &str) -> Option<u16> {
+        self.ports.get(key).copied()
+    }
+
+    /// Returns the port recorded for the key or allocates a new one.
+    ///
+    /// With a pinned base port, the first free port starting from it is used.
+    /// Otherwise the preferred port is used if it's free on the host, falling back to
+    /// the first free port starting from [DEFAULT_DYNAMIC_BASE_PORT].
+    pub fn allocate(&mut self, key: &str, preferred_port: Option<u16>) -> Result<u16> {
+        if let Some(port) = self.get(key) {
+            return Ok(port);
+        }
+
+        let port = match self.base_port {
+            Some(base_port) => self.next_port(base_port)?,
+            None => match preferred_port.filter(|port| self.is_available(*port)) {
+                Some(port) => port,
+                None => self.next_port(DEFAULT_DYNAMIC_BASE_PORT)?,
+            },
+        };
+
+        self.ports.insert(key.to_string(), port);
+        Ok(port)
+    }
+
+    /// Recorded ports that are currently bound by some other process on the host.
+    pub fn busy_ports(&self) -> Vec<(String, u16)> {
+        self.ports
+            .iter()
+            .filter(|(_, port)| !is_host_port_free(**port))
+            .map(|(key, port)| (key.clone(), *port))
+            .collect()
+    }
+
+    fn is_available(&self, port: u16) -> bool {
+        !self.reserved.contains(&port)
+            && !self.ports.values().any(|p| *p == port)
+            && is_host_port_free(port)
+    }
+
+    fn next_port(&self, from_port: u16) -> Result<u16> {
+        (from_port..=u16::MAX)
+            .find(|port| {
+                !self.reserved.contains(port)
+                    && !self.ports.values().any(|p| p == port)
+                    && is_host_port_free(*port)
+            })
+            .ok_or(anyhow!("No free host ports left starting from {from_port}"))
+    }
+}
+
+/// The port scheme used before ports were allocated, e.g. 8080, 18080, 28080 for clusters 0, 1, 2.
+/// Returns None if the port doesn't fit into the valid range.
+pub fn legacy_port(cluster_index: u32, port: u16) -> Option<u16> {
+    format!("{cluster_index}{port}").parse::<u16>().ok()
+}
+
+pub fn is_host_port_free(port: u16) -> bool {
+    TcpListener::bind(("0.0.0.0", port)).is_ok()
+}
@@ -1,15 +1,294 @@
-use clap::Parser;
-use serde::{Deserialize, Serialize};
+use clap::{Parser, ValueEnum};
+use indexmap::IndexMap;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_yaml::Value;
+
+use crate::config_schema::CONFIG_VERSION;
 
 const DEFAULT_PULSAR_VERSION: &str = "3.2.2";
 const DEFAULT_NUM_CLUSTERS: &str = "1";
 const DEFAULT_NUM_BROKERS: &str = "1";
 const DEFAULT_NUM_BOOKIES: &str = "1";
 const DEFAULT_NUM_ZOOKEEPERS: &str = "1";
+const DEFAULT_SUPERUSER_ROLE: &str = "admin";
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MetadataStore {
+    #[default]
+    Zookeeper,
+    Oxia,
+    Etcd,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FunctionsWorker {
+    #[default]
+    Disabled,
+    /// Run the functions worker inside brokers
+    Broker,
+    /// Run a dedicated functions worker service per cluster
+    Dedicated,
+}
+
+/// Where clusters keep tenants, namespaces and other instance-wide policies.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConfigurationStore {
+    /// All clusters share one configuration store, so namespace policies are global
+    #[default]
+    Shared,
+    /// Every cluster has its own configuration store, so namespace policies are local
+    PerCluster,
+}
+
+/// Which clusters replicate the `global/default` namespace to which.
+/// The first cluster is the source of one-way replication and the hub of hub-and-spoke.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReplicationTopology {
+    /// Every cluster replicates to every other cluster
+    #[default]
+    FullMesh,
+    /// The first cluster replicates to the other clusters, nothing is replicated back
+    OneWay,
+    /// The first cluster replicates to the other clusters, they replicate back to it only
+    HubAndSpoke,
+}
+
+/// Heap, direct memory and container limits of Pulsar components.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceProfile {
+    /// Smallest footprint that still starts, e.g. for CI runners
+    Tiny,
+    /// Fits several instances on a developer machine
+    #[default]
+    Laptop,
+    /// Production-like heaps and caches for soak and performance tests
+    Perf,
+}
+
+/// Overrides of the resource profile for a component. Unset fields come from the profile.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ComponentResources {
+    /// JVM heap, e.g. 512m
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heap: Option<String>,
+
+    /// JVM direct memory, e.g. 512m
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direct_memory: Option<String>,
+
+    /// Container memory limit, e.g. 1G
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<String>,
+
+    /// Container CPU limit, e.g. 1.5
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ComponentResourcesConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zookeeper: Option<ComponentResources>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub broker: Option<ComponentResources>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bookie: Option<ComponentResources>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ComponentResources>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub functions_worker: Option<ComponentResources>,
+}
+
+/// Settings passed to a component via its environment, e.g. `allowAutoTopicCreation: false`.
+/// They are applied after the ones puls generates, so they take precedence.
+pub type ConfigOverrides = IndexMap<String, String>;
+
+/// Accepts any scalar value, so booleans and numbers don't have to be quoted.
+fn deserialize_config_overrides<'de, D>(deserializer: D) -> Result<ConfigOverrides, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    IndexMap::<String, Value>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::String(value) => value,
+                Value::Bool(value) => value.to_string(),
+                Value::Number(value) => value.to_string(),
+                Value::Null => String::new(),
+                _ => {
+                    return Err(de::Error::custom(format!(
+                        "value of {key} must be a string, number or boolean"
+                    )))
+                }
+            };
+            Ok((key, value))
+        })
+        .collect()
+}
+
+/// Settings of a single cluster. Unset fields take the instance-wide values,
+/// config overrides are applied on top of the instance-wide ones.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ClusterConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pulsar_version: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_brokers: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_bookies: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub with_dekaf: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub functions_worker: Option<FunctionsWorker>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_profile: Option<ResourceProfile>,
+
+    /// Merged field by field into the instance-wide component resources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component_resources: Option<ComponentResourcesConfig>,
+
+    #[serde(
+        default,
+        skip_serializing_if = "IndexMap::is_empty",
+        deserialize_with = "deserialize_config_overrides"
+    )]
+    pub broker_config: ConfigOverrides,
+
+    #[serde(
+        default,
+        skip_serializing_if = "IndexMap::is_empty",
+        deserialize_with = "deserialize_config_overrides"
+    )]
+    pub bookie_config: ConfigOverrides,
+
+    #[serde(
+        default,
+        skip_serializing_if = "IndexMap::is_empty",
+        deserialize_with = "deserialize_config_overrides"
+    )]
+    pub proxy_config: ConfigOverrides,
+}
+
+/// Token authentication and authorization. Tokens are stored in the instance directory.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AuthConfig {
+    /// Role used by puls itself, e.g. by `puls exec` and Dekaf
+    #[serde(default = "default_superuser_role")]
+    pub superuser_role: String,
+
+    /// Additional roles to generate tokens for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+}
+
+fn default_superuser_role() -> String {
+    DEFAULT_SUPERUSER_ROLE.to_string()
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            superuser_role: default_superuser_role(),
+            roles: Vec::new(),
+        }
+    }
+}
+
+/// Tenants, namespaces and topics created on every `puls start` if they don't exist yet.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ResourcesConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tenants: Vec<TenantConfig>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct TenantConfig {
+    pub name: String,
+
+    /// Defaults to all clusters of the instance
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_clusters: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub namespaces: Vec<NamespaceConfig>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct NamespaceConfig {
+    pub name: String,
+
+    /// Defaults to the cluster the namespace is created in
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replication_clusters: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<RetentionConfig>,
+
+    /// Message TTL, e.g. 30m, 1h, 7d
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_ttl: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub topics: Vec<TopicConfig>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct RetentionConfig {
+    /// Retention time, e.g. 1h, 7d. -1 for infinite retention
+    pub time: String,
+
+    /// Retention size, e.g. 100M, 10G. -1 for infinite retention
+    pub size: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct TopicConfig {
+    /// Local topic name, e.g. `orders`
+    pub name: String,
+
+    /// Number of partitions. Non-partitioned topic is created if 0
+    #[serde(default)]
+    pub partitions: u32,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<SchemaConfig>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct SchemaConfig {
+    /// Schema type, e.g. AVRO, JSON, PROTOBUF, STRING
+    #[serde(rename = "type")]
+    pub schema_type: String,
+
+    /// Schema definition, e.g. Avro schema JSON. Not needed for primitive types
+    #[serde(default)]
+    pub definition: String,
+}
 
+/// Fields missing from config files take their default values, so project configs can be partial.
 #[derive(Parser, Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[command(version, about, long_about = None)]
+#[serde(default)]
 pub struct InstanceConfig {
+    /// Layout version of the config file. Older files are migrated on read
+    #[arg(skip = CONFIG_VERSION)]
+    pub version: u32,
+
     #[arg(long, default_value = DEFAULT_PULSAR_VERSION)]
     pub pulsar_version: String,
 
@@ -22,23 +301,148 @@ pub struct InstanceConfig {
     #[arg(long, default_value = DEFAULT_NUM_BOOKIES)]
     pub num_bookies: u32,
 
+    /// Number of ZooKeeper nodes. Only used with the zookeeper metadata store
     #[arg(long, default_value = DEFAULT_NUM_ZOOKEEPERS)]
     pub num_zookeepers: u32,
 
+    /// Metadata store used by brokers and bookies
+    #[arg(long, value_enum, default_value_t = MetadataStore::Zookeeper)]
+    #[serde(default)]
+    pub metadata_store: MetadataStore,
+
+    /// Configuration metadata store layout. One-way and hub-and-spoke replication need per-cluster
+    #[arg(long, value_enum, default_value_t = ConfigurationStore::Shared)]
+    #[serde(default)]
+    pub configuration_store: ConfigurationStore,
+
+    /// Geo-replication topology of the `global/default` namespace
+    #[arg(long, value_enum, default_value_t = ReplicationTopology::FullMesh)]
+    #[serde(default)]
+    pub replication_topology: ReplicationTopology,
+
     /// Enable Pulsar management UI for the instance
     #[arg(long, default_value_t = false)]
     pub with_dekaf: bool,
+
+    /// Offload ledgers to a bundled MinIO service
+    #[arg(long, default_value_t = false)]
+    #[serde(default)]
+    pub with_tiered_storage: bool,
+
+    /// Add Prometheus and Grafana with Pulsar dashboards
+    #[arg(long, default_value_t = false)]
+    #[serde(default)]
+    pub with_monitoring: bool,
+
+    /// Enable TLS for proxies, brokers and bookies with certificates signed by a generated CA
+    #[arg(long, default_value_t = false)]
+    #[serde(default)]
+    pub tls: bool,
+
+    /// Enable authentication and authorization with JWT tokens. Set with `--with-auth`
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthConfig>,
+
+    /// Pulsar Functions worker mode
+    #[arg(long, value_enum, default_value_t = FunctionsWorker::Disabled)]
+    #[serde(default)]
+    pub functions_worker: FunctionsWorker,
+
+    /// Host directory with function and connector JARs and NARs. Defaults to `functions` in the instance directory
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub functions_dir: Option<String>,
+
+    /// Tenants, namespaces and topics to create. Set with `puls edit`
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<ResourcesConfig>,
+
+    /// Heap and container limits of brokers, bookies, proxies, ZooKeeper and functions workers
+    #[arg(long, value_enum, default_value_t = ResourceProfile::Laptop)]
+    pub resource_profile: ResourceProfile,
+
+    /// Per-component overrides of the resource profile. Set with `puls edit`
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component_resources: Option<ComponentResourcesConfig>,
+
+    /// broker.conf settings of all brokers. Set with `puls edit`
+    #[arg(skip)]
+    #[serde(
+        default,
+        skip_serializing_if = "IndexMap::is_empty",
+        deserialize_with = "deserialize_config_overrides"
+    )]
+    pub broker_config: ConfigOverrides,
+
+    /// bookkeeper.conf settings of all bookies. Set with `puls edit`
+    #[arg(skip)]
+    #[serde(
+        default,
+        skip_serializing_if = "IndexMap::is_empty",
+        deserialize_with = "deserialize_config_overrides"
+    )]
+    pub bookie_config: ConfigOverrides,
+
+    /// proxy.conf settings of all proxies. Set with `puls edit`
+    #[arg(skip)]
+    #[serde(
+        default,
+        skip_serializing_if = "IndexMap::is_empty",
+        deserialize_with = "deserialize_config_overrides"
+    )]
+    pub proxy_config: ConfigOverrides,
+
+    /// zookeeper.conf settings. ZooKeeper is shared by all clusters, so it has no per-cluster overrides
+    #[arg(skip)]
+    #[serde(
+        default,
+        skip_serializing_if = "IndexMap::is_empty",
+        deserialize_with = "deserialize_config_overrides"
+    )]
+    pub zookeeper_config: ConfigOverrides,
+
+    /// Per-cluster settings, the first entry is cluster-0. Set with `puls edit`
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clusters: Vec<ClusterConfig>,
+
+    /// Allocate host ports from the free ports starting at this port instead of the default ones
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_port: Option<u16>,
 }
 
 impl Default for InstanceConfig {
     fn default() -> Self {
         InstanceConfig {
+            version: CONFIG_VERSION,
             pulsar_version: DEFAULT_PULSAR_VERSION.to_string(),
             num_clusters: DEFAULT_NUM_CLUSTERS.parse().unwrap(),
             num_brokers: DEFAULT_NUM_BROKERS.parse().unwrap(),
             num_bookies: DEFAULT_NUM_BOOKIES.parse().unwrap(),
             num_zookeepers: DEFAULT_NUM_ZOOKEEPERS.parse().unwrap(),
+            metadata_store: MetadataStore::default(),
+            configuration_store: ConfigurationStore::default(),
+            replication_topology: ReplicationTopology::default(),
             with_dekaf: true,
+            with_tiered_storage: false,
+            with_monitoring: false,
+            tls: false,
+            auth: None,
+            functions_worker: FunctionsWorker::default(),
+            functions_dir: None,
+            resources: None,
+            resource_profile: ResourceProfile::default(),
+            component_resources: None,
+            broker_config: IndexMap::new(),
+            bookie_config: IndexMap::new(),
+            proxy_config: IndexMap::new(),
+            zookeeper_config: IndexMap::new(),
+            clusters: Vec::new(),
+            base_port: None,
         }
     }
 }
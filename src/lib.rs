@@ -1 +1,15 @@
+pub mod bundle;
+pub mod certs;
+pub mod config_schema;
+pub mod docker_compose;
+pub mod host_ports;
 pub mod instance_config;
+pub mod output;
+pub mod project;
+pub mod pulsar_client;
+pub mod scale;
+pub mod snapshots;
+pub mod status;
+pub mod tokens;
+pub mod upgrade;
+pub mod wait;
@@ -1,21 +1,71 @@
-mod docker_compose;
-mod instance_config;
-
 use anyhow::{anyhow, Error, Result};
-use clap::{Parser, Subcommand};
+use backon::{BackoffBuilder, ExponentialBuilder};
+use clap::{Parser, Subcommand, ValueEnum};
 use dirs::home_dir;
-use docker_compose::docker_compose::{generate_instance, InstanceOutput, PrintInfo};
-use instance_config::InstanceConfig;
-use regex::Regex;
+use indexmap::IndexMap;
+use puls::bundle::{export_bundle, parse_bundle, write_bundle_files};
+use puls::certs::{ca_cert_file_name, write_certificates};
+use puls::config_schema::{parse_config, validate_config, CONFIG_VERSION};
+use puls::docker_compose::auth::{auth_roles, dekaf_env, AUTH_DIR, DEKAF_ENV_FILE};
+use puls::docker_compose::docker_compose::{
+    cluster_name, generate_instance, project_name, pulsar_proxy_service_name, InstanceOutput,
+    PrintInfo,
+};
+use puls::docker_compose::functions::{
+    is_functions_worker_enabled, DEFAULT_FUNCTIONS_DIR, FUNCTIONS_MOUNT_PATH,
+};
+use puls::docker_compose::tls::{tls_certificates, CERTS_DIR};
+use puls::docker_compose::validation::{format_memory, validate_topology};
+use puls::host_ports::HostPorts;
+use puls::instance_config::{AuthConfig, InstanceConfig};
+use puls::output::OutputFormat;
+use puls::project::{
+    check_instance_name, find_project_config_file, read_project_config, ProjectConfig,
+    PROJECT_CONFIG_FILE_NAME,
+};
+use puls::pulsar_client::{
+    produce_script, produce_script_input, ConsumeOptions, ProduceOptions, SchemaType,
+};
+use puls::scale::{changed_services, check_scale_down, removed_bookies, set_scale};
+use puls::snapshots::{
+    check_snapshot_files, docker_volume_name, list_snapshots, read_manifest, snapshot_dir,
+    volume_archive_file_name, write_manifest, SnapshotManifest, RESTORE_BACKUP_CONFIG_FILE_NAME,
+    SNAPSHOT_CONFIG_FILE_NAME,
+};
+use puls::status::{check_command_args, status_checks, CheckMethod, StatusCheck};
+use puls::tokens::{
+    create_token, read_or_create_secret_key, read_token, token_file_name, write_tokens,
+};
+use puls::upgrade::{downgraded_clusters, set_pulsar_version, upgrade_plan};
+use puls::wait::{parse_duration, wait_checks, WaitTarget};
+use serde::{Deserialize, Serialize};
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
 use std::process;
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Prints progress messages to stdout,
+/// or to stderr if stdout is reserved for JSON or YAML output.
+macro_rules! status {
+    ($output:expr, $($arg:tt)*) => {
+        if $output.is_text() {
+            println!($($arg)*)
+        } else {
+            eprintln!($($arg)*)
+        }
+    };
+}
 
 #[derive(Parser)]
 #[command(version, about, long_about = None, arg_required_else_help(true))]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Output format for describe, ls, ps, start, status and wait commands
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
 }
 
 #[derive(Parser, Clone, Debug)]
@@ -26,6 +76,14 @@ pub struct CreateCommandArgs {
     #[clap(flatten)]
     pub instance_config: InstanceConfig,
 
+    /// Enable authentication and authorization with JWT tokens
+    #[arg(long, default_value_t = false)]
+    pub with_auth: bool,
+
+    /// Generate a token for this role on start. Can be repeated
+    #[arg(long = "auth-role", requires = "with_auth")]
+    pub auth_roles: Vec<String>,
+
     #[arg(long, default_value_t = false)]
     pub overwrite: bool,
 }
@@ -65,6 +123,10 @@ pub struct StartCommandArgs {
     /// Disable opening the browser after starting the instance
     #[arg(long, default_value_t = false)]
     pub no_open_browser: bool,
+
+    /// Start even if the estimated memory usage exceeds the memory available to Docker
+    #[arg(long, default_value_t = false)]
+    pub no_memory_check: bool,
 }
 
 #[derive(Parser, Clone, Debug)]
@@ -73,6 +135,79 @@ pub struct PsCommandArgs {
     pub instance_name: Option<String>,
 }
 
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None)]
+pub struct StatusCommandArgs {
+    pub instance_name: Option<String>,
+
+    /// Check the instance again every `--interval` seconds until interrupted
+    #[arg(short, long, default_value_t = false)]
+    pub watch: bool,
+
+    /// Seconds between checks in the watch mode
+    #[arg(long, default_value_t = 5)]
+    pub interval: u64,
+}
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None)]
+pub struct WaitCommandArgs {
+    pub instance_name: Option<String>,
+
+    /// Give up after this long, e.g. 300s or 5m
+    #[arg(long, default_value = "300s", value_parser = parse_duration)]
+    pub timeout: Duration,
+
+    /// What to wait for: ready, topics-created or cluster-N
+    #[arg(long = "for", default_value = "ready")]
+    pub target: WaitTarget,
+}
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None, arg_required_else_help(true))]
+pub struct ScaleCommandArgs {
+    pub instance_name: Option<String>,
+
+    /// Number of brokers per cluster
+    #[arg(long)]
+    pub brokers: Option<u32>,
+
+    /// Number of bookies per cluster. Removed bookies are decommissioned first, so no data is lost
+    #[arg(long)]
+    pub bookies: Option<u32>,
+
+    /// Scale only this cluster
+    #[arg(long)]
+    pub cluster: Option<u32>,
+
+    /// Give up waiting for new services to become healthy after this long, e.g. 300s or 5m
+    #[arg(long, default_value = "300s", value_parser = parse_duration)]
+    pub timeout: Duration,
+}
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None, arg_required_else_help(true))]
+pub struct UpgradeCommandArgs {
+    pub instance_name: Option<String>,
+
+    /// Pulsar version to upgrade to, e.g. 3.3.1
+    #[arg(long)]
+    pub to: String,
+
+    /// Upgrade only this cluster, e.g. to test geo-replication between Pulsar versions
+    #[arg(long)]
+    pub cluster: Option<u32>,
+
+    /// Give up waiting for each upgraded service to become healthy after this long, e.g. 300s or 5m
+    #[arg(long, default_value = "300s", value_parser = parse_duration)]
+    pub timeout: Duration,
+
+    /// Switch to an older Pulsar version. Pulsar doesn't support downgrades, data written by
+    /// the newer version may not be readable
+    #[arg(long, default_value_t = false)]
+    pub allow_downgrade: bool,
+}
+
 #[derive(Parser, Clone, Debug)]
 #[command(version, about, long_about = None)]
 pub struct StopCommandArgs {
@@ -101,6 +236,47 @@ pub struct PurgeCommandArgs {
     pub instance_name: String,
 }
 
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None, arg_required_else_help(true))]
+pub struct SnapshotCommandArgs {
+    pub instance_name: String,
+
+    pub snapshot_name: String,
+
+    /// Replace the snapshot if it already exists
+    #[arg(long, default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None, arg_required_else_help(true))]
+pub struct RestoreCommandArgs {
+    pub instance_name: String,
+
+    pub snapshot_name: String,
+}
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None, arg_required_else_help(true))]
+pub struct ExportCommandArgs {
+    pub instance_name: String,
+}
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None, arg_required_else_help(true))]
+pub struct ImportCommandArgs {
+    /// Bundle file created by `puls export`, or `-` to read it from stdin
+    pub bundle_file: PathBuf,
+
+    /// Instance name to use instead of the one stored in the bundle
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Replace the instance config, certificates and tokens if the instance already exists
+    #[arg(long, default_value_t = false)]
+    pub overwrite: bool,
+}
+
 #[derive(Parser, Clone, Debug)]
 #[command(version, about, long_about = None)]
 pub struct LsCommandArgs {}
@@ -152,11 +328,162 @@ pub struct ExecCommandArgs {
     cluster: Option<u32>,
 }
 
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None, arg_required_else_help(true))]
+pub struct ProduceCommandArgs {
+    #[clap(flatten)]
+    pub options: ProduceOptions,
+
+    /// Pulsar instance name
+    #[arg(long)]
+    instance: Option<String>,
+
+    /// Cluster index, e.g. 0, 1, 2
+    #[arg(long)]
+    cluster: Option<u32>,
+}
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None, arg_required_else_help(true))]
+pub struct ConsumeCommandArgs {
+    #[clap(flatten)]
+    pub options: ConsumeOptions,
+
+    /// Pulsar instance name
+    #[arg(long)]
+    instance: Option<String>,
+
+    /// Cluster index, e.g. 0, 1, 2
+    #[arg(long)]
+    cluster: Option<u32>,
+}
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None)]
+pub struct TokenCreateCommandArgs {
+    pub instance_name: Option<String>,
+
+    /// Role to put into the token subject
+    #[arg(long)]
+    pub role: String,
+}
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None)]
+pub struct ConfigValidateCommandArgs {
+    /// Defaults to the project config in the current directory, then to the default instance
+    pub instance_name: Option<String>,
+
+    /// Validate the config file at this path instead of an instance config
+    #[arg(long, conflicts_with = "instance_name")]
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None)]
+pub struct ConfigSyncCommandArgs {}
+
+#[derive(Subcommand, Clone, Debug)]
+enum ConfigCommands {
+    /// Report unknown keys and invalid values in the instance config
+    #[command()]
+    Validate(ConfigValidateCommandArgs),
+
+    /// Apply the project config in the current directory to its instance config
+    #[command()]
+    Sync(ConfigSyncCommandArgs),
+}
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None, arg_required_else_help(true))]
+pub struct ConfigCommandArgs {
+    #[command(subcommand)]
+    command: ConfigCommands,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+enum TokenCommands {
+    /// Create a token for the role, signed with the instance secret key
+    #[command()]
+    Create(TokenCreateCommandArgs),
+}
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None, arg_required_else_help(true))]
+pub struct TokenCommandArgs {
+    #[command(subcommand)]
+    command: TokenCommands,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum FunctionKind {
+    #[default]
+    Function,
+    Source,
+    Sink,
+}
+
+impl FunctionKind {
+    /// `pulsar-admin` command and the flag that takes the package path
+    fn admin_command(&self) -> (&str, &str) {
+        match self {
+            FunctionKind::Function => ("functions", "--jar"),
+            FunctionKind::Source => ("sources", "--archive"),
+            FunctionKind::Sink => ("sinks", "--archive"),
+        }
+    }
+}
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None, arg_required_else_help(true))]
+pub struct FunctionsDeployCommandArgs {
+    /// Path to the function JAR or the connector NAR
+    package: PathBuf,
+
+    /// Pulsar instance name
+    #[arg(long)]
+    instance: Option<String>,
+
+    /// Cluster index, e.g. 0, 1, 2
+    #[arg(long)]
+    cluster: Option<u32>,
+
+    /// Deploy the package as a function, a source or a sink
+    #[arg(long, value_enum, default_value_t = FunctionKind::Function)]
+    kind: FunctionKind,
+
+    /// Arguments for `pulsar-admin functions create`, e.g. `--classname Foo --inputs my-topic`
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+enum FunctionsCommands {
+    /// Copy the package to the functions directory and create a function, source or sink from it
+    #[command()]
+    Deploy(FunctionsDeployCommandArgs),
+}
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None, arg_required_else_help(true))]
+pub struct FunctionsCommandArgs {
+    #[command(subcommand)]
+    command: FunctionsCommands,
+}
+
 #[derive(Subcommand)]
 enum Commands {
+    /// Manage instance config files
+    #[command()]
+    Config(ConfigCommandArgs),
+
+    /// Consume messages from a topic with `pulsar-client`
+    #[command()]
+    Consume(ConsumeCommandArgs),
+
     /// Create a new Pulsar instance
     #[command()]
-    Create(CreateCommandArgs),
+    Create(Box<CreateCommandArgs>),
 
     /// Delete specified Pulsar instance
     #[command()]
@@ -174,6 +501,18 @@ enum Commands {
     #[command()]
     Exec(ExecCommandArgs),
 
+    /// Print Pulsar instance config, certificates and tokens as a bundle, e.g. `puls export my-instance > bundle.yaml`
+    #[command()]
+    Export(ExportCommandArgs),
+
+    /// Deploy Pulsar Functions and IO connectors
+    #[command()]
+    Functions(FunctionsCommandArgs),
+
+    /// Create a Pulsar instance from a bundle created by `puls export`
+    #[command()]
+    Import(ImportCommandArgs),
+
     /// List all Pulsar instances
     #[command()]
     Ls(LsCommandArgs),
@@ -182,6 +521,10 @@ enum Commands {
     #[command()]
     Logs(LogsCommandArgs),
 
+    /// Produce messages to a topic with `pulsar-client`
+    #[command()]
+    Produce(ProduceCommandArgs),
+
     /// List containers and services associated with the specified Pulsar instance
     #[command()]
     Ps(PsCommandArgs),
@@ -190,6 +533,19 @@ enum Commands {
     #[command()]
     Purge(PurgeCommandArgs),
 
+    /// Replace Pulsar instance data and config with a snapshot
+    #[command()]
+    Restore(RestoreCommandArgs),
+
+    /// Change the number of brokers and bookies of a running Pulsar instance.
+    /// Services whose config depends on the counts, like brokers' ledger quorums, are recreated
+    #[command()]
+    Scale(ScaleCommandArgs),
+
+    /// Stop Pulsar instance and archive its data volumes and config into a named snapshot
+    #[command()]
+    Snapshot(SnapshotCommandArgs),
+
     /// Start Pulsar instance
     #[command()]
     Start(StartCommandArgs),
@@ -198,6 +554,10 @@ enum Commands {
     #[command()]
     Stats(StatsCommandArgs),
 
+    /// Check health of every component of the specified Pulsar instance
+    #[command()]
+    Status(StatusCommandArgs),
+
     /// Stop specified Pulsar instance
     #[command()]
     Stop(StopCommandArgs),
@@ -206,6 +566,19 @@ enum Commands {
     #[command()]
     Template(TemplateCommandArgs),
 
+    /// Manage JWT tokens of the specified Pulsar instance
+    #[command()]
+    Token(TokenCommandArgs),
+
+    /// Upgrade Pulsar instance to a new Pulsar version, restarting services one by one.
+    /// Each cluster has a single proxy, so clients reconnect while it restarts
+    #[command()]
+    Upgrade(UpgradeCommandArgs),
+
+    /// Wait until the specified Pulsar instance is ready to use, e.g. in CI pipelines
+    #[command()]
+    Wait(WaitCommandArgs),
+
     /// Get default Pulsar instance name
     #[command()]
     GetDefaultInstance(GetDefaultInstanceCommandArgs),
@@ -240,6 +613,8 @@ fn get_default_instance_name() -> Result<String> {
         create_cmd(CreateCommandArgs {
             instance_name: "default".to_string(),
             instance_config: InstanceConfig::default(),
+            with_auth: false,
+            auth_roles: Vec::new(),
             overwrite: false,
         })?;
         std::fs::write(default_instance_name_file.clone(), "default")?;
@@ -249,6 +624,72 @@ fn get_default_instance_name() -> Result<String> {
     Ok(default_instance_name.trim().to_string())
 }
 
+/// Project config in the current directory or its closest parent.
+/// Instance and snapshot directories contain `puls.yml` too, they don't count as projects.
+fn get_project_config_file() -> Result<Option<PathBuf>> {
+    let project_config_file = find_project_config_file(&std::env::current_dir()?);
+    match project_config_file {
+        Some(file) if !file.starts_with(get_config_dir()?) => Ok(Some(file)),
+        _ => Ok(None),
+    }
+}
+
+fn read_project_config_file(project_config_file: &Path) -> Result<ProjectConfig> {
+    let project_config = read_project_config(project_config_file).map_err(|err| {
+        anyhow!(
+            "Failed to read project config at {}. {err}",
+            project_config_file.display()
+        )
+    })?;
+    validate_topology(&project_config.instance_config)?;
+    Ok(project_config)
+}
+
+/// Returns the instance defined by the project config, if any, and creates it on first use.
+/// Existing instance configs are only updated by `puls config sync`, so that changes made by
+/// `puls upgrade` or `puls scale` aren't reverted behind the user's back. The rest of instance
+/// files like ports, certificates and tokens live in the instance directory as usual.
+fn get_project_instance_name() -> Result<Option<String>> {
+    let Some(project_config_file) = get_project_config_file()? else {
+        return Ok(None);
+    };
+
+    let project_config = read_project_config_file(&project_config_file)?;
+    let instance_name = project_config.instance_name;
+    let instance_config = project_config.instance_config;
+
+    if !is_instance_exists(instance_name.clone())? {
+        // Commands print JSON or YAML to stdout, the resolver only reports to stderr
+        eprintln!(
+            "Creating Pulsar instance {instance_name} defined in {}",
+            project_config_file.display()
+        );
+        let instance_config_file =
+            create_instance_config_file(instance_name.clone(), instance_config, false)?;
+        eprintln!(
+            "Created a new Pulsar instance config file at {}",
+            instance_config_file.display()
+        );
+    } else if read_instance_config(instance_name.clone()).ok().as_ref() != Some(&instance_config) {
+        eprintln!(
+            "Pulsar instance {instance_name} config differs from {}. Run `puls config sync` to apply it",
+            project_config_file.display()
+        );
+    }
+
+    Ok(Some(instance_name))
+}
+
+fn resolve_instance_name(instance_name: Option<String>) -> Result<String> {
+    match instance_name {
+        Some(instance_name) => Ok(instance_name),
+        None => match get_project_instance_name()? {
+            Some(instance_name) => Ok(instance_name),
+            None => get_default_instance_name(),
+        },
+    }
+}
+
 fn set_default_instance_name(instance_name: String) -> Result<()> {
     let default_instance_name_file = get_default_instance_name_file()?;
     std::fs::write(default_instance_name_file.clone(), instance_name)?;
@@ -276,43 +717,64 @@ fn set_default_cluster_index(cluster_index: u32) -> Result<()> {
     Ok(())
 }
 
-fn exec_cmd(instance_name: String, cluster_index: u32, command: Vec<String>) -> Result<()> {
-    let docker_compose_file = get_instance_docker_compose_file(instance_name.clone())?;
+/// Returns `docker compose exec` command that runs in the proxy container of the cluster.
+fn pulsar_proxy_exec_cmd(instance_name: String, cluster_index: u32) -> Result<Command> {
+    let instance_config = read_instance_config(instance_name.clone())?;
+    if cluster_index >= instance_config.num_clusters {
+        return Err(anyhow!(
+            "Pulsar instance \"{instance_name}\" has no cluster with index {cluster_index}"
+        ));
+    }
 
     // TODO - run in a separate container. Good enough for proof-of-concept.
     // To implement it, we should determine the docker network name first.
-    let pulsar_proxy_service_name = format!("pulsar-proxy-cluster-{cluster_index}");
-
-    let command_as_str = command.join(" ");
-    let bash_args = [
-        "bash",
-        "-c",
-        format!("export PATH=$PATH:/pulsar/bin; {command_as_str}").as_str(),
-    ]
-    .iter()
-    .map(|arg| arg.to_string())
-    .collect::<Vec<String>>();
-
-    let mut docker_args: Vec<String> = [
-        "compose",
-        "-f",
-        docker_compose_file.to_str().unwrap(),
-        "exec",
-        "--user",
-        "pulsar",
-        &pulsar_proxy_service_name,
-    ]
-    .iter()
-    .map(|arg| arg.to_string())
-    .collect();
-
-    docker_args.extend(bash_args);
-
-    Command::new("docker").args(docker_args).spawn()?.wait()?;
+    let pulsar_proxy_service_name = pulsar_proxy_service_name(&cluster_name(cluster_index));
+
+    let mut command = docker_compose_cmd(instance_name)?;
+    command
+        .arg("exec")
+        .arg("--user")
+        .arg("pulsar")
+        .arg(pulsar_proxy_service_name);
+
+    Ok(command)
+}
+
+fn exec_cmd(instance_name: String, cluster_index: u32, command: Vec<String>) -> Result<()> {
+    // The command is passed as positional parameters, so its arguments reach the
+    // program unchanged instead of being split and expanded by the shell
+    let exit_status = pulsar_proxy_exec_cmd(instance_name, cluster_index)?
+        .arg("bash")
+        .arg("-c")
+        .arg("export PATH=$PATH:/pulsar/bin; exec \"$@\"")
+        .arg("bash")
+        .args(&command)
+        .spawn()?
+        .wait()?;
+
+    if !exit_status.success() {
+        return Err(anyhow!("Command failed: {}", command.join(" ")));
+    }
 
     Ok(())
 }
 
+/// Returns `docker compose` command bound to the instance's compose file and project,
+/// so containers, volumes and networks of different instances never clash.
+fn docker_compose_cmd(instance_name: String) -> Result<Command> {
+    let docker_compose_file = get_instance_docker_compose_file(instance_name.clone())?;
+
+    let mut command = Command::new("docker");
+    command
+        .arg("compose")
+        .arg("--project-name")
+        .arg(project_name(&instance_name))
+        .arg("-f")
+        .arg(docker_compose_file);
+
+    Ok(command)
+}
+
 fn get_config_dir() -> Result<PathBuf> {
     let config_dir = Path::new(&home_dir().unwrap()).join(".config").join("puls");
     if !config_dir.exists() {
@@ -380,10 +842,48 @@ fn read_instance_config(instance_name: String) -> Result<InstanceConfig> {
             Error::msg(err_msg)
         })?;
 
-    let instance_config = serde_yaml::from_str::<InstanceConfig>(&instance_config_yaml)?;
+    // Older configs are migrated in memory only, the file keeps its comments
+    // until a command changes the config
+    let (instance_config, _) = parse_config(&instance_config_yaml).map_err(|err| {
+        anyhow!(
+            "Invalid instance config file at {}. {err}",
+            instance_config_file.display()
+        )
+    })?;
+
     Ok(instance_config)
 }
 
+/// Replaces the instance config file. A file written by an older version of puls is kept
+/// next to it, because the new one is migrated to [CONFIG_VERSION].
+fn save_instance_config(instance_name: String, instance_config: &InstanceConfig) -> Result<()> {
+    let instance_config_file = get_instance_config_file(instance_name)?;
+
+    let migrated_from_version = std::fs::read_to_string(&instance_config_file)
+        .ok()
+        .and_then(|instance_config_yaml| parse_config(&instance_config_yaml).ok())
+        .and_then(|(_, migrated_from_version)| migrated_from_version);
+    if let Some(from_version) = migrated_from_version {
+        let backup_file =
+            instance_config_file.with_file_name(format!("puls.yml.v{from_version}.bak"));
+        std::fs::copy(&instance_config_file, &backup_file)?;
+
+        // Stderr keeps JSON and YAML output of the command intact
+        eprintln!(
+            "Migrated instance config at {} from version {from_version} to {CONFIG_VERSION}. Backup saved to {}",
+            instance_config_file.display(),
+            backup_file.display()
+        );
+    }
+
+    std::fs::write(
+        &instance_config_file,
+        serde_yaml::to_string(instance_config)?,
+    )?;
+
+    Ok(())
+}
+
 fn get_instance_docker_compose_file(instance_name: String) -> Result<PathBuf> {
     let instance_dir = get_instance_dir(instance_name)?;
     let instance_docker_compose_file = instance_dir.join("docker-compose.yml");
@@ -391,18 +891,155 @@ fn get_instance_docker_compose_file(instance_name: String) -> Result<PathBuf> {
     Ok(instance_docker_compose_file)
 }
 
-fn write_instance_config(
+fn get_instance_functions_dir(
     instance_name: String,
-    instance_config: InstanceConfig,
-    is_overwrite: bool,
-) -> Result<()> {
-    let config_yaml = serde_yaml::to_string(&instance_config)?;
-
-    let instance_name = instance_name.clone();
-    let instance_name_regex = Regex::new(r"^[a-zA-Z0-9_-]+$").unwrap();
-    if !instance_name_regex.is_match(&instance_name) {
-        let err_msg = "Invalid instance name provided. Only alphanumeric characters, dashes, and underscores are allowed.".to_string();
-        return Err(Error::msg(err_msg));
+    instance_config: &InstanceConfig,
+) -> Result<PathBuf> {
+    match &instance_config.functions_dir {
+        Some(functions_dir) => Ok(PathBuf::from(functions_dir)),
+        None => Ok(get_instance_dir(instance_name)?.join(DEFAULT_FUNCTIONS_DIR)),
+    }
+}
+
+fn get_instance_ports_file(instance_name: String) -> Result<PathBuf> {
+    let instance_dir = get_instance_dir(instance_name)?;
+    let instance_ports_file = instance_dir.join("ports.yml");
+
+    Ok(instance_ports_file)
+}
+
+fn read_instance_ports(instance_name: String) -> Result<HostPorts> {
+    let instance_ports_file = get_instance_ports_file(instance_name)?;
+    if !instance_ports_file.exists() {
+        return Ok(HostPorts::default());
+    }
+
+    let instance_ports_yaml = std::fs::read_to_string(instance_ports_file)?;
+    let instance_ports = serde_yaml::from_str::<HostPorts>(&instance_ports_yaml)?;
+    Ok(instance_ports)
+}
+
+fn write_instance_ports(instance_name: String, host_ports: &HostPorts) -> Result<()> {
+    let instance_ports_file = get_instance_ports_file(instance_name)?;
+    std::fs::write(instance_ports_file, serde_yaml::to_string(host_ports)?)?;
+    Ok(())
+}
+
+/// Host ports recorded for the instance. Ports recorded by other instances are reserved.
+fn instance_host_ports(
+    instance_name: String,
+    instance_config: &InstanceConfig,
+) -> Result<HostPorts> {
+    let mut host_ports =
+        read_instance_ports(instance_name.clone())?.with_base_port(instance_config.base_port);
+
+    for other_instance_name in list_instance_names()? {
+        if other_instance_name == instance_name {
+            continue;
+        }
+
+        let other_host_ports = read_instance_ports(other_instance_name)?;
+        host_ports.reserve(other_host_ports.ports.values().copied());
+    }
+
+    Ok(host_ports)
+}
+
+/// Generates the instance using the host ports recorded for it, allocating new ones if needed.
+/// Ports recorded by other instances are never reused.
+fn generate_instance_with_ports(
+    instance_name: String,
+    instance_config: InstanceConfig,
+) -> Result<InstanceOutput> {
+    let mut host_ports = instance_host_ports(instance_name.clone(), &instance_config)?;
+    let instance_output =
+        generate_instance(instance_name.clone(), instance_config, &mut host_ports)?;
+    write_instance_ports(instance_name, &host_ports)?;
+
+    Ok(instance_output)
+}
+
+/// Like [generate_instance_with_ports], but ports that aren't recorded yet are only
+/// allocated for the output and not persisted, so read-only commands don't claim any.
+fn preview_instance_with_ports(
+    instance_name: String,
+    instance_config: InstanceConfig,
+) -> Result<InstanceOutput> {
+    let mut host_ports = instance_host_ports(instance_name.clone(), &instance_config)?;
+    generate_instance(instance_name, instance_config, &mut host_ports)
+}
+
+/// Headless hosts have no browser, the URLs are printed by `puls describe` anyway.
+fn open_browser(url: &str, output: OutputFormat) {
+    if let Err(err) = webbrowser::open(url) {
+        status!(
+            output,
+            "Warning: unable to open {url} in the browser. {err}"
+        );
+    }
+}
+
+fn is_instance_running(instance_name: String) -> Result<bool> {
+    let output = docker_compose_cmd(instance_name)?
+        .arg("ps")
+        .arg("--quiet")
+        .output()?;
+
+    Ok(!String::from_utf8(output.stdout)?.trim().is_empty())
+}
+
+/// Images of the running containers of the instance by service name.
+fn running_service_images(instance_name: String) -> Result<IndexMap<String, String>> {
+    let output = docker_compose_cmd(instance_name)?
+        .arg("ps")
+        .arg("--format")
+        .arg("{{.Service}} {{.Image}}")
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Failed to list containers of the instance"));
+    }
+
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .filter_map(|line| line.trim().split_once(' '))
+        .map(|(service_name, image)| (service_name.to_string(), image.to_string()))
+        .collect())
+}
+
+fn write_instance_config(
+    instance_name: String,
+    instance_config: InstanceConfig,
+    is_overwrite: bool,
+) -> Result<()> {
+    let instance_config_file =
+        create_instance_config_file(instance_name, instance_config, is_overwrite)?;
+
+    println!(
+        "Created a new Pulsar instance config file at {}",
+        instance_config_file.display()
+    );
+
+    Ok(())
+}
+
+/// Same as [write_instance_config] without printing, returns the path of the config file.
+fn create_instance_config_file(
+    instance_name: String,
+    instance_config: InstanceConfig,
+    is_overwrite: bool,
+) -> Result<PathBuf> {
+    let config_yaml = serde_yaml::to_string(&instance_config)?;
+
+    let instance_name = instance_name.clone();
+    check_instance_name(&instance_name)?;
+
+    let clashing_instance_name = list_instance_names()?.into_iter().find(|other| {
+        *other != instance_name && project_name(other) == project_name(&instance_name)
+    });
+    if let Some(other) = clashing_instance_name {
+        let err_msg = format!("Pulsar instance name \"{instance_name}\" clashes with existing instance \"{other}\". Instance names are case-insensitive.");
+        return Err(Error::msg(err_msg));
     }
 
     let is_already_exists = is_instance_exists(instance_name.clone())?;
@@ -418,14 +1055,10 @@ fn write_instance_config(
 
     std::fs::write(instance_config_file.clone(), config_yaml)?;
 
-    println!(
-        "Created a new Pulsar instance config file at {}",
-        instance_config_file.display()
-    );
-
-    Ok(())
+    Ok(instance_config_file)
 }
 
+#[allow(dead_code)]
 fn list_instance_configs(_args: LsCommandArgs) -> Result<Vec<InstanceConfig>> {
     let instances_dir = get_instances_dir()?;
     let instances = std::fs::read_dir(instances_dir)
@@ -457,46 +1090,361 @@ fn list_instance_configs(_args: LsCommandArgs) -> Result<Vec<InstanceConfig>> {
 
 fn create_cmd(args: CreateCommandArgs) -> Result<()> {
     println!("Creating a new Pulsar instance {}", args.instance_name);
-    write_instance_config(args.instance_name, args.instance_config, args.overwrite)
+
+    let mut instance_config = args.instance_config;
+
+    // Compose resolves relative paths against the instance directory
+    if let Some(functions_dir) = &instance_config.functions_dir {
+        let functions_dir = std::path::absolute(functions_dir)?;
+        instance_config.functions_dir = Some(functions_dir.display().to_string());
+    }
+
+    if args.with_auth {
+        instance_config.auth = Some(AuthConfig {
+            roles: args.auth_roles,
+            ..Default::default()
+        });
+    }
+
+    for warning in validate_topology(&instance_config)? {
+        println!("Warning: {warning}");
+    }
+
+    write_instance_config(args.instance_name, instance_config, args.overwrite)
+}
+
+fn functions_deploy_cmd(args: FunctionsDeployCommandArgs) -> Result<()> {
+    let instance_name = resolve_instance_name(args.instance)?;
+    let cluster_index = args.cluster.unwrap_or(get_default_cluster_index()?);
+    let instance_config = read_instance_config(instance_name.clone())?;
+    if !is_functions_worker_enabled(&instance_config) {
+        return Err(anyhow!(
+            "Functions worker is disabled for Pulsar instance \"{instance_name}\". Recreate it with `puls create --functions-worker broker --overwrite {instance_name}`"
+        ));
+    }
+
+    let package_file_name = args
+        .package
+        .file_name()
+        .ok_or(anyhow!("Invalid package path: {}", args.package.display()))?
+        .to_string_lossy()
+        .to_string();
+
+    let functions_dir = get_instance_functions_dir(instance_name.clone(), &instance_config)?;
+    std::fs::create_dir_all(&functions_dir)?;
+
+    let package_file = functions_dir.join(&package_file_name);
+    if std::fs::canonicalize(&args.package)?
+        != std::fs::canonicalize(&functions_dir)?.join(&package_file_name)
+    {
+        println!(
+            "Copying {} to {}",
+            args.package.display(),
+            package_file.display()
+        );
+        std::fs::copy(&args.package, &package_file)?;
+    }
+
+    let (admin_command, package_flag) = args.kind.admin_command();
+    let mut command = vec![
+        "pulsar-admin".to_string(),
+        admin_command.to_string(),
+        "create".to_string(),
+        package_flag.to_string(),
+        format!("{FUNCTIONS_MOUNT_PATH}/{package_file_name}"),
+    ];
+    command.extend(args.args);
+
+    exec_cmd(instance_name, cluster_index, command)
+}
+
+/// Messages from `--message` flags, or lines of the file or stdin.
+fn read_messages(options: &ProduceOptions) -> Result<Vec<String>> {
+    if !options.messages.is_empty() {
+        return Ok(options.messages.clone());
+    }
+
+    let text = match options.file.as_deref() {
+        Some("-") | None => {
+            if options.file.is_none() && std::io::stdin().is_terminal() {
+                return Err(anyhow!(
+                    "No messages to produce. Pass them with --message, --file or stdin"
+                ));
+            }
+            std::io::read_to_string(std::io::stdin())?
+        }
+        Some(file) => std::fs::read_to_string(file)
+            .map_err(|err| anyhow!("Failed to read messages from {file}. {err}"))?,
+    };
+
+    Ok(text
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect())
+}
+
+fn produce_cmd(args: ProduceCommandArgs) -> Result<()> {
+    let instance_name = resolve_instance_name(args.instance)?;
+    let cluster_index = args.cluster.unwrap_or(get_default_cluster_index()?);
+    let options = args.options;
+
+    let schema_definition = match &options.schema_file {
+        Some(schema_file) => Some(std::fs::read_to_string(schema_file)?.trim().to_string()),
+        None if matches!(options.schema_type, SchemaType::Json | SchemaType::Avro) => {
+            return Err(anyhow!(
+                "--schema-file with the schema definition is required for JSON and Avro schemas"
+            ));
+        }
+        None => None,
+    };
+
+    let input = produce_script_input(&read_messages(&options)?)?;
+
+    // Messages are streamed through stdin, so their size isn't limited by the command line.
+    // Arguments reach pulsar-client as positional parameters, without shell expansion
+    let mut child = pulsar_proxy_exec_cmd(instance_name, cluster_index)?
+        .arg("-T")
+        .arg("bash")
+        .arg("-c")
+        .arg(produce_script())
+        .arg("bash")
+        .args(options.pulsar_client_args(schema_definition.as_deref()))
+        .stdin(Stdio::piped())
+        .spawn()?;
+    let write_result = child
+        .stdin
+        .take()
+        .ok_or(anyhow!("Failed to open stdin of pulsar-client"))?
+        .write_all(&input);
+    let exit_status = child.wait()?;
+
+    if !exit_status.success() {
+        return Err(anyhow!("Failed to produce messages to {}", options.topic));
+    }
+    write_result?;
+
+    Ok(())
+}
+
+fn consume_cmd(args: ConsumeCommandArgs) -> Result<()> {
+    let instance_name = resolve_instance_name(args.instance)?;
+    let cluster_index = args.cluster.unwrap_or(get_default_cluster_index()?);
+
+    let exit_status = pulsar_proxy_exec_cmd(instance_name, cluster_index)?
+        .arg("-T")
+        .arg("bin/pulsar-client")
+        .args(args.options.pulsar_client_args())
+        .stdin(Stdio::null())
+        .spawn()?
+        .wait()?;
+
+    if !exit_status.success() {
+        return Err(anyhow!(
+            "Failed to consume messages from {}",
+            args.options.topic
+        ));
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct TokenOutput {
+    instance_name: String,
+    role: String,
+    token: String,
+}
+
+fn token_create_cmd(args: TokenCreateCommandArgs, output: OutputFormat) -> Result<()> {
+    let instance_name = resolve_instance_name(args.instance_name)?;
+    let instance_config = read_instance_config(instance_name.clone())?;
+    if instance_config.auth.is_none() {
+        return Err(anyhow!(
+            "Authentication is not enabled for Pulsar instance \"{instance_name}\". Recreate it with `puls create --with-auth --overwrite {instance_name}`"
+        ));
+    }
+
+    let auth_dir = get_instance_dir(instance_name.clone())?.join(AUTH_DIR);
+    let secret_key = read_or_create_secret_key(&auth_dir)?;
+    let token = create_token(&secret_key, &args.role)?;
+
+    if output.is_text() {
+        println!("{token}");
+    } else {
+        output.print(&TokenOutput {
+            instance_name,
+            role: args.role,
+            token,
+        })?;
+    }
+
+    Ok(())
+}
+
+fn config_sync_cmd(_args: ConfigSyncCommandArgs) -> Result<()> {
+    let project_config_file = get_project_config_file()?.ok_or(anyhow!(
+        "No {PROJECT_CONFIG_FILE_NAME} found in the current directory or its parents"
+    ))?;
+
+    let project_config = read_project_config_file(&project_config_file)?;
+    let instance_name = project_config.instance_name;
+    let instance_config = project_config.instance_config;
+
+    if !is_instance_exists(instance_name.clone())? {
+        return write_instance_config(instance_name, instance_config, false);
+    }
+
+    if read_instance_config(instance_name.clone()).ok().as_ref() == Some(&instance_config) {
+        println!("Pulsar instance {instance_name} config is up to date");
+        return Ok(());
+    }
+
+    save_instance_config(instance_name.clone(), &instance_config)?;
+    println!(
+        "Updated Pulsar instance {instance_name} config from {}",
+        project_config_file.display()
+    );
+    if is_instance_running(instance_name.clone())? {
+        println!("Restart it with `puls stop {instance_name} && puls start {instance_name}` to apply the changes");
+    }
+
+    Ok(())
+}
+
+fn config_validate_cmd(args: ConfigValidateCommandArgs) -> Result<()> {
+    let project_config_file = if args.instance_name.is_none() {
+        get_project_config_file()?
+    } else {
+        None
+    };
+
+    let config_file = match (args.file, project_config_file) {
+        (Some(file), _) => file,
+        (None, Some(project_config_file)) => project_config_file,
+        (None, None) => {
+            let instance_name = resolve_instance_name(args.instance_name)?;
+            if !is_instance_exists(instance_name.clone())? {
+                return Err(anyhow!(
+                    "Pulsar instance with such name does not exist: {instance_name}"
+                ));
+            }
+            get_instance_config_file(instance_name)?
+        }
+    };
+
+    let config_yaml = std::fs::read_to_string(&config_file).map_err(|err| {
+        anyhow!(
+            "Failed to read config file at {}. {err}",
+            config_file.display()
+        )
+    })?;
+
+    let is_project_config = config_file.file_name() == Some(PROJECT_CONFIG_FILE_NAME.as_ref())
+        && !config_file.starts_with(get_config_dir()?);
+    let extra_keys: &[&str] = if is_project_config {
+        &["instance_name"]
+    } else {
+        &[]
+    };
+
+    let issues = validate_config(&config_yaml, extra_keys);
+    for issue in &issues {
+        println!("{}: {issue}", config_file.display());
+    }
+
+    let num_errors = issues.iter().filter(|issue| !issue.is_warning).count();
+    if num_errors > 0 {
+        return Err(anyhow!(
+            "Found {num_errors} error(s) in {}",
+            config_file.display()
+        ));
+    }
+
+    println!("{} is valid", config_file.display());
+    Ok(())
 }
 
 fn template_cmd(args: TemplateCommandArgs) -> Result<()> {
-    let instance_name = args.instance_name.unwrap_or(get_default_instance_name()?);
+    let instance_name = resolve_instance_name(args.instance_name)?;
     let instance_config = read_instance_config(instance_name.clone())?;
-    let instance_output = generate_instance(instance_name, instance_config)?;
+    let instance_output = preview_instance_with_ports(instance_name, instance_config)?;
     println!("{}", instance_output.docker_compose_template);
     Ok(())
 }
 
 fn stats_cmd(args: StatsCommandArgs) -> Result<()> {
-    let instance_name = args.instance_name.unwrap_or(get_default_instance_name()?);
-
-    let docker_compose_file = get_instance_docker_compose_file(instance_name.clone())?;
+    let instance_name = resolve_instance_name(args.instance_name)?;
 
-    let cmd_args = vec![
-        "compose",
-        "-f",
-        docker_compose_file.to_str().unwrap(),
-        "stats",
-    ];
-
-    Command::new("docker").args(cmd_args).spawn()?.wait()?;
+    docker_compose_cmd(instance_name)?
+        .arg("stats")
+        .spawn()?
+        .wait()?;
 
     Ok(())
 }
 
-fn ls_cmd(args: LsCommandArgs) -> Result<()> {
+#[derive(Debug, Clone, Serialize)]
+struct InstanceSummary {
+    instance_name: String,
+    is_default: bool,
+    instance_config: InstanceConfig,
+}
+
+#[allow(unused_variables)]
+fn ls_cmd(args: LsCommandArgs, output: OutputFormat) -> Result<()> {
     let instance_names = list_instance_names()?;
 
-    for instance in instance_names {
-        println!("{instance}");
+    if output.is_text() {
+        for instance in instance_names {
+            println!("{instance}");
+        }
+        return Ok(());
     }
 
-    Ok(())
+    let default_instance_name_file = get_default_instance_name_file()?;
+    let default_instance_name = std::fs::read_to_string(default_instance_name_file)
+        .map(|name| name.trim().to_string())
+        .ok();
+
+    let mut instances: Vec<InstanceSummary> = Vec::new();
+    for instance_name in instance_names {
+        match read_instance_config(instance_name.clone()) {
+            Ok(instance_config) => instances.push(InstanceSummary {
+                is_default: default_instance_name.as_ref() == Some(&instance_name),
+                instance_name,
+                instance_config,
+            }),
+            Err(err) => {
+                eprintln!(
+                    "Failed to read instance config for {}. {}",
+                    instance_name, err
+                );
+            }
+        }
+    }
+
+    output.print(&instances)
 }
 
 fn edit_cmd(args: EditCommandArgs) -> Result<()> {
-    let instance_name = args.instance_name.unwrap_or(get_default_instance_name()?);
+    // The instance config is overwritten from the project config, so edit the source
+    if args.instance_name.is_none() {
+        if let Some(project_config_file) = get_project_config_file()? {
+            println!("Edit project config using default $EDITOR");
+            println!("Project config file: {}", project_config_file.display());
+
+            let text_editor = std::env::var("EDITOR").unwrap_or("nano".to_string());
+            Command::new(text_editor)
+                .arg(project_config_file)
+                .spawn()?
+                .wait()?;
+
+            return Ok(());
+        }
+    }
+
+    let instance_name = resolve_instance_name(args.instance_name)?;
 
     let is_exists = is_instance_exists(instance_name.clone())?;
     if !is_exists {
@@ -525,47 +1473,117 @@ fn edit_cmd(args: EditCommandArgs) -> Result<()> {
 }
 
 fn logs_cmd(args: LogsCommandArgs) -> Result<()> {
-    let instance_name = args.instance_name.unwrap_or(get_default_instance_name()?);
-    let docker_compose_file = get_instance_docker_compose_file(instance_name.clone())?;
+    let instance_name = resolve_instance_name(args.instance_name)?;
 
-    let mut cmd_args = vec![
-        "compose",
-        "-f",
-        docker_compose_file.to_str().unwrap(),
-        "logs",
-    ];
+    let mut command = docker_compose_cmd(instance_name)?;
+    command.arg("logs");
 
     if args.follow {
-        cmd_args.push("--follow");
+        command.arg("--follow");
     }
 
-    Command::new("docker").args(cmd_args).spawn()?.wait()?;
+    command.spawn()?.wait()?;
 
     Ok(())
 }
 
-fn describe_cmd(args: DescribeCommandArgs) -> Result<()> {
-    let instance_name = args.instance_name.unwrap_or(get_default_instance_name()?);
+fn describe_cmd(args: DescribeCommandArgs, output: OutputFormat) -> Result<()> {
+    let instance_name = resolve_instance_name(args.instance_name)?;
     let instance_config = read_instance_config(instance_name.clone())?;
-    let instance_output = generate_instance(instance_name.clone(), instance_config)?;
+    let instance_output = preview_instance_with_ports(instance_name.clone(), instance_config)?;
 
-    instance_output.print_info();
+    if output.is_text() {
+        instance_output.print_info();
+        return Ok(());
+    }
 
-    Ok(())
+    output.print(&instance_output)
 }
 
-fn ps_cmd(args: PsCommandArgs) -> Result<()> {
-    fn ps_instance(instance_name: String) -> Result<()> {
-        let docker_compose_file = get_instance_docker_compose_file(instance_name.clone())?;
+/// Container state as reported by `docker compose ps --format json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ContainerState {
+    #[serde(rename(deserialize = "Name"))]
+    name: String,
 
-        println!("Pulsar instance \"{}\"", instance_name);
+    #[serde(rename(deserialize = "Service"))]
+    service: String,
+
+    #[serde(rename(deserialize = "State"))]
+    state: String,
+
+    #[serde(rename(deserialize = "Health"), default)]
+    health: String,
+
+    #[serde(rename(deserialize = "Status"), default)]
+    status: String,
+
+    #[serde(rename(deserialize = "ExitCode"), default)]
+    exit_code: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct InstanceContainers {
+    instance_name: String,
+    containers: Vec<ContainerState>,
+}
+
+fn list_instance_containers(instance_name: String) -> Result<Vec<ContainerState>> {
+    let output = docker_compose_cmd(instance_name.clone())?
+        .arg("ps")
+        .arg("--all")
+        .arg("--format")
+        .arg("json")
+        .stderr(Stdio::inherit())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to list containers of Pulsar instance \"{instance_name}\""
+        ));
+    }
 
-        let cmd_args = vec!["compose", "-f", docker_compose_file.to_str().unwrap(), "ps"];
+    let stdout = String::from_utf8(output.stdout)?;
+    let stdout = stdout.trim();
 
-        Command::new("docker").args(cmd_args).spawn()?.wait()?;
+    // Older docker compose versions print a JSON array, newer ones print an object per line
+    if stdout.starts_with('[') {
+        return Ok(serde_json::from_str(stdout)?);
+    }
+
+    stdout
+        .lines()
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+fn ps_cmd(args: PsCommandArgs, output: OutputFormat) -> Result<()> {
+    fn ps_instance(instance_name: String) -> Result<()> {
+        println!("Pulsar instance \"{}\"", instance_name);
+
+        docker_compose_cmd(instance_name)?.arg("ps").spawn()?.wait()?;
         Ok(())
     }
 
+    if !output.is_text() {
+        let instance_names = match args.instance_name {
+            Some(name) => vec![name],
+            None => list_instance_names()?,
+        };
+
+        let instances = instance_names
+            .into_iter()
+            .map(|instance_name| {
+                Ok(InstanceContainers {
+                    containers: list_instance_containers(instance_name.clone())?,
+                    instance_name,
+                })
+            })
+            .collect::<Result<Vec<InstanceContainers>>>()?;
+
+        return output.print(&instances);
+    }
+
     match args.instance_name {
         Some(name) => ps_instance(name),
         None => {
@@ -579,35 +1597,325 @@ fn ps_cmd(args: PsCommandArgs) -> Result<()> {
     }
 }
 
-fn start_cmd(args: StartCommandArgs) -> Result<InstanceOutput> {
-    let instance_name = args.instance_name.unwrap_or(get_default_instance_name()?);
+#[derive(Debug, Clone, Serialize)]
+struct ComponentStatus {
+    cluster_name: Option<String>,
+    component: String,
+    service_name: String,
+    state: String,
+    healthy: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct InstanceStatus {
+    instance_name: String,
+    healthy: bool,
+    components: Vec<ComponentStatus>,
+}
+
+impl PrintInfo for InstanceStatus {
+    fn print_info(&self) {
+        println!("Pulsar instance \"{}\"", self.instance_name);
+
+        let service_name_width = self
+            .components
+            .iter()
+            .map(|component| component.service_name.len())
+            .max()
+            .unwrap_or(0);
+
+        let mut cluster_name: Option<Option<String>> = None;
+        for component in &self.components {
+            if cluster_name.as_ref() != Some(&component.cluster_name) {
+                cluster_name = Some(component.cluster_name.clone());
+
+                println!();
+                match &component.cluster_name {
+                    Some(name) => println!("Cluster {name}"),
+                    None => println!("Instance-wide services"),
+                }
+                println!(
+                    "  {:<service_name_width$}  {:<10}  {:<10}  HEALTH",
+                    "SERVICE", "COMPONENT", "STATE"
+                );
+            }
+
+            println!(
+                "  {:<service_name_width$}  {:<10}  {:<10}  {}",
+                component.service_name,
+                component.component,
+                component.state,
+                if component.healthy {
+                    "healthy"
+                } else {
+                    "unhealthy"
+                }
+            );
+        }
+    }
+}
+
+fn run_status_check(
+    instance_name: &str,
+    containers: &[ContainerState],
+    check: StatusCheck,
+) -> Result<ComponentStatus> {
+    let container = containers
+        .iter()
+        .find(|container| container.service == check.service_name);
+    let state = container
+        .map(|container| container.state.clone())
+        .unwrap_or("missing".to_string());
+
+    let can_run_check = match check.method {
+        CheckMethod::Exec | CheckMethod::Command => state == "running",
+        CheckMethod::Run => state != "missing",
+        CheckMethod::Completed => false,
+    };
+
+    let healthy = match check_command_args(&check) {
+        None => container
+            .is_some_and(|container| container.state == "exited" && container.exit_code == 0),
+        Some(args) => {
+            can_run_check
+                && docker_compose_cmd(instance_name.to_string())?
+                    .args(args)
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status()?
+                    .success()
+        }
+    };
+
+    Ok(ComponentStatus {
+        cluster_name: check.cluster_name,
+        component: check.component,
+        service_name: check.service_name,
+        state,
+        healthy,
+    })
+}
+
+/// Runs the checks against the instance containers in parallel.
+/// Bookie sanity checks take a few seconds each.
+fn run_status_checks(instance_name: String, checks: Vec<StatusCheck>) -> Result<InstanceStatus> {
+    let containers = list_instance_containers(instance_name.clone())?;
+
+    let components = std::thread::scope(|scope| {
+        checks
+            .into_iter()
+            .map(|check| scope.spawn(|| run_status_check(&instance_name, &containers, check)))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Result<Vec<ComponentStatus>>>()
+    })?;
+
+    Ok(InstanceStatus {
+        healthy: components.iter().all(|component| component.healthy),
+        instance_name,
+        components,
+    })
+}
+
+fn status_cmd(args: StatusCommandArgs, output: OutputFormat) -> Result<()> {
+    let instance_name = resolve_instance_name(args.instance_name)?;
+    if !is_instance_exists(instance_name.clone())? {
+        return Err(anyhow!(
+            "Pulsar instance with such name does not exist: {instance_name}"
+        ));
+    }
+
+    loop {
+        let instance_config = read_instance_config(instance_name.clone())?;
+        let instance_status =
+            run_status_checks(instance_name.clone(), status_checks(&instance_config))?;
+
+        if output.is_text() {
+            if args.watch {
+                // Clear the terminal and move the cursor to the top left corner
+                print!("\x1B[2J\x1B[1;1H");
+            }
+            instance_status.print_info();
+        } else {
+            output.print(&instance_status)?;
+        }
+
+        if !args.watch {
+            if !instance_status.healthy {
+                return Err(anyhow!("Pulsar instance \"{instance_name}\" is unhealthy"));
+            }
+            return Ok(());
+        }
+
+        std::thread::sleep(std::time::Duration::from_secs(args.interval));
+    }
+}
+
+fn wait_cmd(args: WaitCommandArgs, output: OutputFormat) -> Result<()> {
+    let instance_name = resolve_instance_name(args.instance_name)?;
+    let instance_config = read_instance_config(instance_name.clone())?;
+    // Fails early on an unknown cluster
+    wait_checks(&instance_config, args.target, args.timeout)?;
+
+    status!(
+        output,
+        "Waiting up to {} seconds for Pulsar instance \"{instance_name}\" to be {}",
+        args.timeout.as_secs(),
+        args.target
+    );
+
+    let started_at = std::time::Instant::now();
+    let remaining = || args.timeout.saturating_sub(started_at.elapsed());
+    let check_target = || {
+        let checks = wait_checks(&instance_config, args.target, remaining())?;
+        let instance_status = run_status_checks(instance_name.clone(), checks)?;
+        if instance_status.healthy {
+            return Ok(instance_status);
+        }
+
+        let pending = instance_status
+            .components
+            .iter()
+            .filter(|component| !component.healthy)
+            .map(|component| format!("{} ({})", component.component, component.service_name))
+            .collect::<Vec<String>>()
+            .join(", ");
+        Err(anyhow!("Not ready yet: {pending}"))
+    };
+
+    let max_delay = Duration::from_secs(10);
+    let mut delays = ExponentialBuilder::default()
+        .with_min_delay(Duration::from_secs(1))
+        .with_max_delay(max_delay)
+        .with_max_times(usize::MAX)
+        .build();
+
+    // Sleeps are clamped to the time left, so the wait never outlasts the timeout
+    let instance_status = loop {
+        let err = match check_target() {
+            Ok(instance_status) => break instance_status,
+            Err(err) => err,
+        };
+
+        if remaining().is_zero() {
+            return Err(anyhow!(
+                "Timed out waiting for Pulsar instance \"{instance_name}\" to be {}. {err}",
+                args.target
+            ));
+        }
+
+        let delay = delays.next().unwrap_or(max_delay).min(remaining());
+        status!(
+            output,
+            "{err}. Checking again in {} seconds",
+            delay.as_secs()
+        );
+        std::thread::sleep(delay);
+    };
+
+    status!(
+        output,
+        "Pulsar instance \"{instance_name}\" is {} after {} seconds",
+        args.target,
+        started_at.elapsed().as_secs()
+    );
+
+    output.print(&instance_status)
+}
+
+fn start_cmd(args: StartCommandArgs, output: OutputFormat) -> Result<InstanceOutput> {
+    let instance_name = resolve_instance_name(args.instance_name)?;
 
     let is_exists = is_instance_exists(instance_name.clone())?;
     if !is_exists {
-        println!("Pulsar instance with such name does not exist: {instance_name}");
+        status!(
+            output,
+            "Pulsar instance with such name does not exist: {instance_name}"
+        );
         return Err(anyhow!("Run `puls create <instance_name>` first"));
     }
 
     let instance_config = read_instance_config(instance_name.clone())?;
 
-    let instance_output = generate_instance(instance_name.clone(), instance_config)?;
+    let certs_dir = get_instance_dir(instance_name.clone())?.join(CERTS_DIR);
+    if instance_config.tls {
+        write_certificates(&certs_dir, &tls_certificates(&instance_config))?;
+    }
+
+    if is_functions_worker_enabled(&instance_config) {
+        // Otherwise docker creates the bind-mounted directory owned by root
+        std::fs::create_dir_all(get_instance_functions_dir(
+            instance_name.clone(),
+            &instance_config,
+        )?)?;
+    }
+
+    let auth_dir = get_instance_dir(instance_name.clone())?.join(AUTH_DIR);
+    if let Some(auth_config) = &instance_config.auth {
+        write_tokens(&auth_dir, &auth_roles(auth_config))?;
+        let superuser_token = read_token(&auth_dir, &auth_config.superuser_role)?;
+        std::fs::write(auth_dir.join(DEKAF_ENV_FILE), dekaf_env(&superuser_token))?;
+    }
+
+    let instance_output = generate_instance_with_ports(instance_name.clone(), instance_config)?;
     let docker_compose_file = get_instance_docker_compose_file(instance_name.clone())?;
     std::fs::write(
         docker_compose_file.clone(),
         instance_output.docker_compose_template.clone(),
     )?;
 
+    for warning in &instance_output.warnings {
+        status!(output, "Warning: {warning}");
+    }
+
+    if !args.no_memory_check {
+        if let Some(docker_memory) = docker_total_memory() {
+            if instance_output.estimated_memory > docker_memory {
+                status!(
+                    output,
+                    "Pulsar instance \"{instance_name}\" needs about {} of memory, but only {} is available to Docker",
+                    format_memory(instance_output.estimated_memory),
+                    format_memory(docker_memory)
+                );
+                status!(output, "Reduce the number of clusters, brokers or bookies, give Docker more memory or pass --no-memory-check to start anyway");
+                return Err(anyhow!(
+                    "Unable to start Pulsar instance \"{instance_name}\""
+                ));
+            }
+        }
+    }
+
+    if !is_instance_running(instance_name.clone())? {
+        let busy_ports = read_instance_ports(instance_name.clone())?.busy_ports();
+        if !busy_ports.is_empty() {
+            for (key, port) in busy_ports {
+                status!(output, "Host port {port} ({key}) is already in use");
+            }
+            status!(output, "Stop the process that uses these ports or pin another port range by setting `base_port` with `puls edit {instance_name}`");
+            return Err(anyhow!(
+                "Unable to start Pulsar instance \"{instance_name}\""
+            ));
+        }
+    }
+
     let instance_config = read_instance_config(instance_name.clone())?;
-    println!(
+    status!(
+        output,
         "Starting Pulsar instance \"{}\" with configuration:",
         instance_name
     );
-    println!("\n{}", serde_yaml::to_string(&instance_config)?.trim());
-    println!("---");
+    status!(
+        output,
+        "\n{}",
+        serde_yaml::to_string(&instance_config)?.trim()
+    );
+    status!(output, "---");
 
     let ctrlc_instance_name = instance_name.clone();
     ctrlc::set_handler(move || {
-        println!(
+        status!(
+            output,
             "Received process termination signal. Stopping Pulsar instance: {}",
             ctrlc_instance_name
         );
@@ -620,91 +1928,391 @@ fn start_cmd(args: StartCommandArgs) -> Result<InstanceOutput> {
         match stop_cmd_result {
             Ok(_) => {}
             Err(err) => {
-                println!(
+                status!(
+                    output,
                     "Failed to stop Pulsar instance: {}. {}",
-                    ctrlc_instance_name, err
+                    ctrlc_instance_name,
+                    err
                 );
                 process::exit(1)
             }
         }
     })?;
 
-    let mut docker_compose_args: Vec<&str> = vec![
-        "compose",
-        "-f",
-        docker_compose_file.to_str().unwrap(),
-        "up",
-        "--remove-orphans",
-    ];
+    let mut docker_compose_args: Vec<&str> = vec!["up", "--remove-orphans"];
+
+    if args.pull {
+        docker_compose_args.push("--pull");
+        docker_compose_args.push("always");
+    }
+
+    if !args.follow {
+        docker_compose_args.push("--wait");
+        docker_compose_args.push("--detach");
+    }
+
+    let started_at = std::time::Instant::now();
+    let mut docker_compose_up = docker_compose_cmd(instance_name.clone())?;
+    docker_compose_up.args(docker_compose_args.clone());
+    if !output.is_text() {
+        // Keep stdout clean for the JSON or YAML output printed after the start
+        docker_compose_up.stdout(Stdio::from(std::io::stderr()));
+    }
+
+    let exit_status = docker_compose_up.spawn()?.wait()?;
+
+    let completed_at = std::time::Instant::now();
+    let seconds_elapsed = completed_at.duration_since(started_at).as_secs();
+    let event_name = if exit_status.success() {
+        "started"
+    } else {
+        "failed"
+    };
+    status!(output, "");
+    status!(
+        output,
+        "Pulsar instance \"{instance_name}\" {event_name} in {seconds_elapsed} seconds"
+    );
+
+    if !exit_status.success() {
+        if !args.no_kill {
+            stop_cmd(StopCommandArgs {
+                instance_name: Some(instance_name.clone()),
+                all: false,
+            })?;
+        }
+
+        status!(output, "");
+        status!(output, "- If you see that some container in the \"Error\" state, check the logs using `docker logs <container_name>`");
+        status!(
+            output,
+            "- You can check all containers logs using `puls logs {instance_name}`"
+        );
+        status!(output, "- Alternatively you can try to purge the instance data using `puls purge {instance_name}` and start it again by running `puls start {instance_name}`");
+        status!(output, "");
+        status!(
+            output,
+            "If you think that something is wrong with puls, you can submit an issue here:"
+        );
+        status!(output, "https://github.com/tealtools/puls/issues");
+        status!(output, "");
+        let err_msg = "Have a good day!".to_string();
+        return Err(Error::msg(err_msg));
+    }
+
+    if instance_output.instance_config.tls {
+        status!(
+            output,
+            "TLS is enabled. Configure clients to trust the CA certificate: {}",
+            certs_dir.join(ca_cert_file_name()).display()
+        );
+    }
+
+    if let Some(auth_config) = &instance_output.instance_config.auth {
+        status!(
+            output,
+            "Authentication is enabled. Superuser token: {}",
+            auth_dir
+                .join(token_file_name(&auth_config.superuser_role))
+                .display()
+        );
+    }
+
+    Ok(instance_output)
+}
+
+fn scale_cmd(args: ScaleCommandArgs) -> Result<()> {
+    let is_project_instance = args.instance_name.is_none() && get_project_config_file()?.is_some();
+    let instance_name = resolve_instance_name(args.instance_name)?;
+    if !is_instance_exists(instance_name.clone())? {
+        return Err(anyhow!(
+            "Pulsar instance with such name does not exist: {instance_name}"
+        ));
+    }
+
+    let instance_config = read_instance_config(instance_name.clone())?;
+    let mut scaled_instance_config = instance_config.clone();
+    set_scale(
+        &mut scaled_instance_config,
+        args.brokers,
+        args.bookies,
+        args.cluster,
+    )?;
+    validate_topology(&scaled_instance_config)?;
+
+    if scaled_instance_config == instance_config {
+        println!("Pulsar instance {instance_name} already has this number of brokers and bookies");
+        return Ok(());
+    }
+
+    let removed_bookies = removed_bookies(&instance_config, &scaled_instance_config);
+    let is_running = is_instance_running(instance_name.clone())?;
+    if !removed_bookies.is_empty() {
+        if !is_running {
+            return Err(anyhow!(
+                "Bookies can only be removed from a running instance, so their ledgers are re-replicated first. Start it with `puls start {instance_name}`"
+            ));
+        }
+        check_scale_down(&instance_config, &scaled_instance_config)?;
+    }
+
+    // Compose file of the running services, to find the ones the new config changes
+    let docker_compose =
+        preview_instance_with_ports(instance_name.clone(), instance_config)?.docker_compose;
+
+    // Decommission with the current compose file, it still has the removed bookies
+    let project_name = project_name(&instance_name);
+    for removed_bookie in &removed_bookies {
+        println!(
+            "Decommissioning {}. Its ledgers are re-replicated to the remaining bookies, it can take a while",
+            removed_bookie.service_name
+        );
+
+        docker_compose_cmd(instance_name.clone())?
+            .arg("stop")
+            .arg(&removed_bookie.service_name)
+            .status()?;
+
+        let exit_status = docker_compose_cmd(instance_name.clone())?
+            .arg("exec")
+            .arg("-T")
+            .arg(removed_bookie.remaining_bookie_service_name())
+            .args(removed_bookie.decommission_command())
+            .status()?;
+        if !exit_status.success() {
+            docker_compose_cmd(instance_name.clone())?
+                .arg("start")
+                .arg(&removed_bookie.service_name)
+                .status()?;
+            return Err(anyhow!(
+                "Failed to decommission {}, it was started again",
+                removed_bookie.service_name
+            ));
+        }
+
+        docker_compose_cmd(instance_name.clone())?
+            .arg("rm")
+            .arg("--force")
+            .arg(&removed_bookie.service_name)
+            .status()?;
+
+        // The cookie is gone from the metadata store, a bookie reusing the data would fail to start
+        Command::new("docker")
+            .arg("volume")
+            .arg("rm")
+            .arg(docker_volume_name(
+                &project_name,
+                &removed_bookie.volume_name,
+            ))
+            .stdout(Stdio::null())
+            .status()?;
+    }
+
+    println!("Updating the instance config");
+    save_instance_config(instance_name.clone(), &scaled_instance_config)?;
+    if is_project_instance {
+        println!("Update the broker and bookie counts in {PROJECT_CONFIG_FILE_NAME} as well, otherwise `puls config sync` reverts them");
+    }
+
+    if scaled_instance_config.tls {
+        let certs_dir = get_instance_dir(instance_name.clone())?.join(CERTS_DIR);
+        write_certificates(&certs_dir, &tls_certificates(&scaled_instance_config))?;
+    }
+
+    let instance_output =
+        generate_instance_with_ports(instance_name.clone(), scaled_instance_config)?;
+    std::fs::write(
+        get_instance_docker_compose_file(instance_name.clone())?,
+        instance_output.docker_compose_template.clone(),
+    )?;
+
+    if !is_running {
+        println!("Pulsar instance {instance_name} isn't running, the new services start with `puls start {instance_name}`");
+        return Ok(());
+    }
+
+    // Running services are kept as they are, removed brokers are orphans now
+    println!("Starting new services");
+    let exit_status = docker_compose_cmd(instance_name.clone())?
+        .arg("up")
+        .arg("--detach")
+        .arg("--no-recreate")
+        .arg("--remove-orphans")
+        .arg("--wait")
+        .arg("--wait-timeout")
+        .arg(args.timeout.as_secs().to_string())
+        .status()?;
+    if !exit_status.success() {
+        return Err(anyhow!(
+            "New services didn't become healthy. Check their logs with `puls logs {instance_name}`"
+        ));
+    }
+
+    // One by one, like `puls upgrade`, so the rest of the cluster keeps serving clients
+    let changed_services = changed_services(&docker_compose, &instance_output.docker_compose);
+    for (i, service_name) in changed_services.iter().enumerate() {
+        println!(
+            "[{}/{}] Recreating {service_name} to apply the new config",
+            i + 1,
+            changed_services.len()
+        );
+
+        let exit_status = docker_compose_cmd(instance_name.clone())?
+            .arg("up")
+            .arg("--no-deps")
+            .arg("--detach")
+            .arg("--force-recreate")
+            .arg("--wait")
+            .arg("--wait-timeout")
+            .arg(args.timeout.as_secs().to_string())
+            .arg(service_name)
+            .status()?;
+        if !exit_status.success() {
+            return Err(anyhow!(
+                "{service_name} didn't become healthy after it was recreated. Check its logs with `docker logs`. Restart the instance to apply the new config to the rest of the services"
+            ));
+        }
+    }
+
+    println!("Pulsar instance {instance_name} successfully scaled");
+
+    Ok(())
+}
+
+fn upgrade_cmd(args: UpgradeCommandArgs) -> Result<()> {
+    let is_project_instance = args.instance_name.is_none() && get_project_config_file()?.is_some();
+    let instance_name = resolve_instance_name(args.instance_name)?;
+    if !is_instance_exists(instance_name.clone())? {
+        return Err(anyhow!(
+            "Pulsar instance with such name does not exist: {instance_name}"
+        ));
+    }
+
+    let instance_config = read_instance_config(instance_name.clone())?;
+    let mut upgraded_instance_config = instance_config.clone();
+    set_pulsar_version(&mut upgraded_instance_config, &args.to, args.cluster)?;
+    validate_topology(&upgraded_instance_config)?;
+
+    let downgraded_clusters = downgraded_clusters(&instance_config, &args.to, args.cluster)
+        .into_iter()
+        .map(|(cluster_name, pulsar_version)| format!("{cluster_name} runs {pulsar_version}"))
+        .collect::<Vec<String>>();
+    if !downgraded_clusters.is_empty() {
+        if !args.allow_downgrade {
+            return Err(anyhow!(
+                "Pulsar {} is older than the current version: {}. Pulsar doesn't support downgrades, pass --allow-downgrade to switch anyway",
+                args.to,
+                downgraded_clusters.join(", ")
+            ));
+        }
+        println!(
+            "Warning: downgrading to Pulsar {}: {}. Data written by the newer version may not be readable",
+            args.to,
+            downgraded_clusters.join(", ")
+        );
+    }
+
+    let is_running = is_instance_running(instance_name.clone())?;
+    let running_images = if is_running {
+        running_service_images(instance_name.clone())?
+    } else {
+        IndexMap::new()
+    };
+
+    let instance_output =
+        generate_instance_with_ports(instance_name.clone(), upgraded_instance_config.clone())?;
+    let steps = upgrade_plan(
+        &upgraded_instance_config,
+        &instance_output.docker_compose,
+        &running_images,
+    );
+
+    if upgraded_instance_config == instance_config && steps.is_empty() {
+        println!(
+            "Pulsar instance {instance_name} already runs Pulsar {}",
+            args.to
+        );
+        return Ok(());
+    }
 
-    if args.pull {
-        docker_compose_args.push("--pull");
-        docker_compose_args.push("always");
+    if upgraded_instance_config != instance_config {
+        println!(
+            "Setting Pulsar version to {} in the instance config",
+            args.to
+        );
+        save_instance_config(instance_name.clone(), &upgraded_instance_config)?;
+        if is_project_instance {
+            println!("Update pulsar_version in {PROJECT_CONFIG_FILE_NAME} as well, otherwise `puls config sync` reverts the upgrade");
+        }
     }
+    std::fs::write(
+        get_instance_docker_compose_file(instance_name.clone())?,
+        instance_output.docker_compose_template.clone(),
+    )?;
 
-    if !args.follow {
-        docker_compose_args.push("--wait");
-        docker_compose_args.push("--detach");
+    if !is_running {
+        println!(
+            "Pulsar instance {instance_name} isn't running, it will run Pulsar {} after `puls start {instance_name}`",
+            args.to
+        );
+        return Ok(());
     }
 
-    let started_at = std::time::Instant::now();
-    let exit_status = Command::new("docker")
-        .args(docker_compose_args.clone())
-        .spawn()?
-        .wait()?;
+    for (i, step) in steps.iter().enumerate() {
+        println!(
+            "[{}/{}] Upgrading {} {} to {}",
+            i + 1,
+            steps.len(),
+            step.component,
+            step.service_name,
+            step.image
+        );
+        if step.drops_client_connections() {
+            println!(
+                "{} is the only proxy of its cluster, its clients disconnect and reconnect once it's healthy",
+                step.service_name
+            );
+        }
 
-    let completed_at = std::time::Instant::now();
-    let seconds_elapsed = completed_at.duration_since(started_at).as_secs();
-    let event_name = if exit_status.success() {
-        "started"
-    } else {
-        "failed"
-    };
-    println!();
-    println!("Pulsar instance \"{instance_name}\" {event_name} in {seconds_elapsed} seconds");
+        // Dependencies are already running, only this service is recreated
+        let exit_status = docker_compose_cmd(instance_name.clone())?
+            .arg("up")
+            .arg("--no-deps")
+            .arg("--detach")
+            .arg("--wait")
+            .arg("--wait-timeout")
+            .arg(args.timeout.as_secs().to_string())
+            .arg(&step.service_name)
+            .spawn()?
+            .wait()?;
 
-    if !exit_status.success() {
-        if !args.no_kill {
-            stop_cmd(StopCommandArgs {
-                instance_name: Some(instance_name.clone()),
-                all: false,
-            })?;
+        if !exit_status.success() {
+            return Err(anyhow!(
+                "{} didn't become healthy after the upgrade. Check its logs with `docker logs`. Services after it still run the previous version, run `puls upgrade` again to continue",
+                step.service_name
+            ));
         }
-
-        println!();
-        println!("- If you see that some container in the \"Error\" state, check the logs using `docker logs <container_name>`");
-        println!("- You can check all containers logs using `puls logs {instance_name}`");
-        println!("- Alternatively you can try to purge the instance data using `puls purge {instance_name}` and start it again by running `puls start {instance_name}`");
-        println!();
-        println!("If you think that something is wrong with puls, you can submit an issue here:");
-        println!("https://github.com/tealtools/puls/issues");
-        println!();
-        let err_msg = "Have a good day!".to_string();
-        return Err(Error::msg(err_msg));
     }
 
-    Ok(instance_output)
+    println!(
+        "Pulsar instance {instance_name} successfully upgraded to Pulsar {}",
+        args.to
+    );
+
+    Ok(())
 }
 
 fn stop_cmd(args: StopCommandArgs) -> Result<()> {
     fn stop_instance(instance_name: String) -> Result<()> {
         println!("Stopping Pulsar instance: {}", instance_name);
-        let docker_compose_file = get_instance_docker_compose_file(instance_name.clone())?;
-
-        Command::new("docker")
-            .arg("compose")
-            .arg("-f")
-            .arg(docker_compose_file.clone())
+        docker_compose_cmd(instance_name.clone())?
             .arg("rm")
             .arg("--stop")
             .arg("--force")
             .spawn()?
             .wait()?;
 
-        Command::new("docker")
-            .arg("compose")
-            .arg("-f")
-            .arg(docker_compose_file)
+        docker_compose_cmd(instance_name)?
             .arg("down")
             .arg("--remove-orphans")
             .arg("--timeout")
@@ -723,7 +2331,7 @@ fn stop_cmd(args: StopCommandArgs) -> Result<()> {
         return Ok(());
     }
 
-    let instance_name = args.instance_name.unwrap_or(get_default_instance_name()?);
+    let instance_name = resolve_instance_name(args.instance_name)?;
     stop_instance(instance_name)?;
 
     Ok(())
@@ -748,15 +2356,10 @@ fn delete_cmd(args: DeleteCommandArgs) -> Result<()> {
 fn purge_cmd(args: PurgeCommandArgs) -> Result<()> {
     let instance_name = args.instance_name;
 
-    let docker_compose_file = get_instance_docker_compose_file(instance_name.clone())?;
-
     println!("Purging Pulsar instance data: {}", instance_name);
 
     println!("Running docker compose down");
-    Command::new("docker")
-        .arg("compose")
-        .arg("-f")
-        .arg(docker_compose_file.clone())
+    docker_compose_cmd(instance_name.clone())?
         .arg("down")
         .arg("--remove-orphans")
         .arg("--volumes")
@@ -764,10 +2367,7 @@ fn purge_cmd(args: PurgeCommandArgs) -> Result<()> {
         .wait()?;
 
     println!("Running docker compose rm");
-    Command::new("docker")
-        .arg("compose")
-        .arg("-f")
-        .arg(docker_compose_file.clone())
+    docker_compose_cmd(instance_name)?
         .arg("rm")
         .arg("--force")
         .arg("--stop")
@@ -778,15 +2378,297 @@ fn purge_cmd(args: PurgeCommandArgs) -> Result<()> {
     Ok(())
 }
 
+/// Memory of the Docker host or VM in bytes, None if Docker doesn't report it.
+fn docker_total_memory() -> Option<u64> {
+    let output = Command::new("docker")
+        .arg("info")
+        .arg("--format")
+        .arg("{{.MemTotal}}")
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8(output.stdout).ok()?.trim().parse().ok()
+}
+
+fn is_docker_volume_exists(docker_volume_name: &str) -> Result<bool> {
+    let exit_status = Command::new("docker")
+        .arg("volume")
+        .arg("inspect")
+        .arg(docker_volume_name)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+
+    Ok(exit_status.success())
+}
+
+/// Runs the script in a throwaway container with the docker volume mounted at `/volume`
+/// and the snapshot directory mounted at `/snapshot`.
+fn run_volume_script(
+    image: &str,
+    docker_volume_name: &str,
+    snapshot_dir: &Path,
+    script: &str,
+) -> Result<()> {
+    let exit_status = Command::new("docker")
+        .arg("run")
+        .arg("--rm")
+        .arg("--user")
+        .arg("root")
+        .arg("--volume")
+        .arg(format!("{docker_volume_name}:/volume"))
+        .arg("--volume")
+        .arg(format!("{}:/snapshot", snapshot_dir.display()))
+        .arg(image)
+        .args(["sh", "-c", script])
+        .status()?;
+
+    if !exit_status.success() {
+        return Err(anyhow!(
+            "Failed to run `{script}` for volume {docker_volume_name}"
+        ));
+    }
+
+    Ok(())
+}
+
+fn snapshot_cmd(args: SnapshotCommandArgs) -> Result<()> {
+    let instance_name = args.instance_name;
+    if !is_instance_exists(instance_name.clone())? {
+        return Err(anyhow!(
+            "Pulsar instance with such name does not exist: {instance_name}"
+        ));
+    }
+
+    let snapshot_dir = snapshot_dir(
+        &get_instance_dir(instance_name.clone())?,
+        &args.snapshot_name,
+    )?;
+    if snapshot_dir.exists() {
+        if !args.overwrite {
+            return Err(anyhow!(
+                "Snapshot \"{}\" already exists at {}. Use --overwrite to replace it",
+                args.snapshot_name,
+                snapshot_dir.display()
+            ));
+        }
+        std::fs::remove_dir_all(&snapshot_dir)?;
+    }
+
+    let instance_config = read_instance_config(instance_name.clone())?;
+    let instance_output =
+        generate_instance_with_ports(instance_name.clone(), instance_config.clone())?;
+
+    // Volumes are only consistent while nothing writes to them
+    if is_instance_running(instance_name.clone())? {
+        stop_cmd(StopCommandArgs {
+            instance_name: Some(instance_name.clone()),
+            all: false,
+        })?;
+    }
+
+    std::fs::create_dir_all(&snapshot_dir)?;
+    let image = format!("apachepulsar/pulsar:{}", instance_config.pulsar_version);
+
+    let mut volumes: Vec<String> = Vec::new();
+    for volume_name in instance_output.docker_compose.volumes.keys() {
+        let docker_volume_name = docker_volume_name(&project_name(&instance_name), volume_name);
+        if !is_docker_volume_exists(&docker_volume_name)? {
+            println!("Skipping volume {volume_name}, the instance didn't create it yet");
+            continue;
+        }
+
+        println!("Archiving volume {volume_name}");
+        run_volume_script(
+            &image,
+            &docker_volume_name,
+            &snapshot_dir,
+            &format!(
+                "tar -czf /snapshot/{} -C /volume .",
+                volume_archive_file_name(volume_name)
+            ),
+        )?;
+        volumes.push(volume_name.clone());
+    }
+
+    std::fs::copy(
+        get_instance_config_file(instance_name.clone())?,
+        snapshot_dir.join(SNAPSHOT_CONFIG_FILE_NAME),
+    )?;
+
+    let created_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    write_manifest(
+        &snapshot_dir,
+        &SnapshotManifest {
+            name: args.snapshot_name,
+            created_at,
+            pulsar_version: instance_config.pulsar_version,
+            volumes,
+        },
+    )?;
+
+    println!("Snapshot saved to {}", snapshot_dir.display());
+
+    Ok(())
+}
+
+fn restore_cmd(args: RestoreCommandArgs) -> Result<()> {
+    let instance_name = args.instance_name;
+    if !is_instance_exists(instance_name.clone())? {
+        return Err(anyhow!(
+            "Pulsar instance with such name does not exist: {instance_name}"
+        ));
+    }
+
+    let instance_dir = get_instance_dir(instance_name.clone())?;
+    let snapshot_dir = snapshot_dir(&instance_dir, &args.snapshot_name)?;
+    let manifest = read_manifest(&snapshot_dir).map_err(|err| {
+        let snapshot_names = list_snapshots(&instance_dir)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|snapshot| snapshot.name)
+            .collect::<Vec<String>>();
+        anyhow!("{err}. Available snapshots: {}", snapshot_names.join(", "))
+    })?;
+    check_snapshot_files(&snapshot_dir, &manifest)?;
+
+    let instance_config_file = get_instance_config_file(instance_name.clone())?;
+    let backup_config_file = instance_dir.join(RESTORE_BACKUP_CONFIG_FILE_NAME);
+    std::fs::copy(&instance_config_file, &backup_config_file)?;
+    println!(
+        "Saved the current config file to {}",
+        backup_config_file.display()
+    );
+
+    if is_instance_running(instance_name.clone())? {
+        stop_cmd(StopCommandArgs {
+            instance_name: Some(instance_name.clone()),
+            all: false,
+        })?;
+    }
+
+    // Volumes missing from the snapshot have to be empty after the restore
+    purge_cmd(PurgeCommandArgs {
+        instance_name: instance_name.clone(),
+    })?;
+
+    std::fs::copy(
+        snapshot_dir.join(SNAPSHOT_CONFIG_FILE_NAME),
+        instance_config_file,
+    )?;
+
+    let image = format!("apachepulsar/pulsar:{}", manifest.pulsar_version);
+    let project_name = project_name(&instance_name);
+    for volume_name in &manifest.volumes {
+        let docker_volume_name = docker_volume_name(&project_name, volume_name);
+
+        println!("Restoring volume {volume_name}");
+
+        // Compose reuses volumes labeled with its project without warnings
+        let exit_status = Command::new("docker")
+            .arg("volume")
+            .arg("create")
+            .arg("--label")
+            .arg(format!("com.docker.compose.project={project_name}"))
+            .arg("--label")
+            .arg(format!("com.docker.compose.volume={volume_name}"))
+            .arg(&docker_volume_name)
+            .stdout(Stdio::null())
+            .status()?;
+        if !exit_status.success() {
+            return Err(anyhow!("Failed to create volume {docker_volume_name}"));
+        }
+
+        run_volume_script(
+            &image,
+            &docker_volume_name,
+            &snapshot_dir,
+            &format!(
+                "find /volume -mindepth 1 -delete && tar -xzf /snapshot/{} -C /volume",
+                volume_archive_file_name(volume_name)
+            ),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn export_cmd(args: ExportCommandArgs, output: OutputFormat) -> Result<()> {
+    let instance_name = args.instance_name;
+    if !is_instance_exists(instance_name.clone())? {
+        return Err(anyhow!(
+            "Pulsar instance with such name does not exist: {instance_name}"
+        ));
+    }
+
+    let instance_config = read_instance_config(instance_name.clone())?;
+    let bundle = export_bundle(
+        &instance_name,
+        &instance_config,
+        &get_instance_dir(instance_name.clone())?,
+    )?;
+
+    // Bundles are YAML unless JSON is requested. Import reads both
+    if output.is_text() {
+        print!("{}", serde_yaml::to_string(&bundle)?);
+    } else {
+        output.print(&bundle)?;
+    }
+
+    Ok(())
+}
+
+fn import_cmd(args: ImportCommandArgs) -> Result<String> {
+    let bundle_yaml = if args.bundle_file == Path::new("-") {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(&args.bundle_file).map_err(|err| {
+            anyhow!(
+                "Failed to read bundle file at {}. {err}",
+                args.bundle_file.display()
+            )
+        })?
+    };
+
+    let bundle = parse_bundle(&bundle_yaml)?;
+    validate_topology(&bundle.instance_config)?;
+
+    let instance_name = args.name.unwrap_or(bundle.instance_name.clone());
+    println!("Importing Pulsar instance {instance_name}");
+
+    write_instance_config(
+        instance_name.clone(),
+        bundle.instance_config.clone(),
+        args.overwrite,
+    )?;
+    write_bundle_files(&bundle, &get_instance_dir(instance_name.clone())?)?;
+
+    if let Some(functions_dir) = &bundle.instance_config.functions_dir {
+        if !Path::new(functions_dir).exists() {
+            println!("Functions directory {functions_dir} doesn't exist on this machine. Update `functions_dir` with `puls edit {instance_name}`");
+        }
+    }
+
+    Ok(instance_name)
+}
+
 fn main() -> Result<()> {
     let args = Cli::parse();
+    let output = args.output;
 
     match args.command {
         Some(Commands::Template(args)) => template_cmd(args),
         Some(Commands::Create(args)) => {
             let event_name = if args.overwrite { "updated" } else { "created" };
 
-            match create_cmd(args.clone()) {
+            match create_cmd(*args.clone()) {
                 Ok(_) => {
                     println!("Pulsar instance successfully {event_name}");
                 }
@@ -841,6 +2723,65 @@ fn main() -> Result<()> {
             };
             Ok(())
         }
+        Some(Commands::Snapshot(args)) => {
+            match snapshot_cmd(args.clone()) {
+                Ok(_) => {
+                    println!(
+                        "Snapshot \"{}\" of Pulsar instance {} successfully created",
+                        args.snapshot_name, args.instance_name
+                    );
+                }
+                Err(err) => {
+                    println!("Failed to snapshot Pulsar instance: {}", args.instance_name);
+                    println!("{}", err);
+                    process::exit(1)
+                }
+            };
+            Ok(())
+        }
+        Some(Commands::Restore(args)) => {
+            match restore_cmd(args.clone()) {
+                Ok(_) => {
+                    println!(
+                        "Pulsar instance {} successfully restored from snapshot \"{}\"",
+                        args.instance_name, args.snapshot_name
+                    );
+                    println!("Start it with `puls start {}`", args.instance_name);
+                }
+                Err(err) => {
+                    println!("Failed to restore Pulsar instance: {}", args.instance_name);
+                    println!("{}", err);
+                    process::exit(1)
+                }
+            };
+            Ok(())
+        }
+        Some(Commands::Export(args)) => {
+            // Stdout is the bundle, so errors go to stderr
+            if let Err(err) = export_cmd(args.clone(), output) {
+                eprintln!("Failed to export Pulsar instance: {}", args.instance_name);
+                eprintln!("{}", err);
+                process::exit(1)
+            }
+            Ok(())
+        }
+        Some(Commands::Import(args)) => {
+            match import_cmd(args.clone()) {
+                Ok(instance_name) => {
+                    println!("Pulsar instance {instance_name} successfully imported");
+                    println!("Start it with `puls start {instance_name}`");
+                }
+                Err(err) => {
+                    println!(
+                        "Failed to import Pulsar instance from {}",
+                        args.bundle_file.display()
+                    );
+                    println!("{}", err);
+                    process::exit(1)
+                }
+            };
+            Ok(())
+        }
         Some(Commands::Delete(args)) => {
             let is_exists = is_instance_exists(args.instance_name.clone())?;
             if !is_exists {
@@ -867,59 +2808,112 @@ fn main() -> Result<()> {
             Ok(())
         }
         Some(Commands::Describe(args)) => {
-            match describe_cmd(args) {
+            match describe_cmd(args, output) {
                 Ok(_) => {}
                 Err(err) => {
-                    println!("{}", err);
+                    eprintln!("{}", err);
                     process::exit(1)
                 }
             };
             Ok(())
         }
         Some(Commands::Ps(args)) => {
-            match ps_cmd(args) {
+            match ps_cmd(args, output) {
                 Ok(_) => {}
                 Err(err) => {
-                    println!("{}", err);
+                    eprintln!("{}", err);
                     process::exit(1)
                 }
             };
             Ok(())
         }
         Some(Commands::Start(args)) => {
-            match start_cmd(args.clone()) {
+            match start_cmd(args.clone(), output) {
                 Ok(instance_output) => {
-                    println!("Successfully started Pulsar instance");
+                    status!(output, "Successfully started Pulsar instance");
 
-                    instance_output.print_info();
+                    if output.is_text() {
+                        instance_output.print_info();
+                    } else {
+                        output.print(&instance_output)?;
+                    }
 
                     if !args.no_open_browser {
                         for cluster_output in instance_output.clusters {
+                            if let Some(url) = cluster_output.grafana_host_url.clone() {
+                                status!(output, "Opening Grafana: {}", url);
+                                open_browser(&url, output);
+                            }
+
                             if let Some(url) = cluster_output.dekaf_host_url.clone() {
-                                println!(
+                                status!(
+                                    output,
                                     "Opening Dekaf UI: {}",
                                     cluster_output
                                         .dekaf_host_url
                                         .clone()
                                         .unwrap_or("".to_string())
                                 );
-                                webbrowser::open(&url).unwrap();
+                                open_browser(&url, output);
                             }
                         }
                     }
 
-                    println!(
+                    status!(
+                        output,
                         "See the `puls describe` command to display instance information again"
                     );
                 }
                 Err(err) => {
-                    println!("{}", err);
+                    eprintln!("{}", err);
                     process::exit(1)
                 }
             };
 
             Ok(())
         }
+        Some(Commands::Status(args)) => {
+            match status_cmd(args, output) {
+                Ok(_) => {}
+                Err(err) => {
+                    status!(output, "{}", err);
+                    process::exit(1)
+                }
+            };
+            Ok(())
+        }
+        Some(Commands::Scale(args)) => {
+            match scale_cmd(args) {
+                Ok(_) => {}
+                Err(err) => {
+                    println!("Failed to scale Pulsar instance");
+                    println!("{}", err);
+                    process::exit(1)
+                }
+            };
+            Ok(())
+        }
+        Some(Commands::Upgrade(args)) => {
+            match upgrade_cmd(args) {
+                Ok(_) => {}
+                Err(err) => {
+                    println!("Failed to upgrade Pulsar instance");
+                    println!("{}", err);
+                    process::exit(1)
+                }
+            };
+            Ok(())
+        }
+        Some(Commands::Wait(args)) => {
+            match wait_cmd(args, output) {
+                Ok(_) => {}
+                Err(err) => {
+                    status!(output, "{}", err);
+                    process::exit(1)
+                }
+            };
+            Ok(())
+        }
         Some(Commands::Stop(args)) => {
             match stop_cmd(args) {
                 Ok(_) => {
@@ -934,11 +2928,11 @@ fn main() -> Result<()> {
             Ok(())
         }
         Some(Commands::Ls(args)) => {
-            match ls_cmd(args) {
+            match ls_cmd(args, output) {
                 Ok(_) => {}
                 Err(err) => {
-                    println!("Failed to list Pulsar instances");
-                    println!("{}", err);
+                    eprintln!("Failed to list Pulsar instances");
+                    eprintln!("{}", err);
                     process::exit(1)
                 }
             };
@@ -984,8 +2978,78 @@ fn main() -> Result<()> {
             set_default_cluster_index(args.cluster_index)?;
             Ok(())
         }
+        Some(Commands::Functions(args)) => {
+            match args.command {
+                FunctionsCommands::Deploy(args) => match functions_deploy_cmd(args) {
+                    Ok(_) => {
+                        println!("Successfully deployed the package");
+                    }
+                    Err(err) => {
+                        println!("Failed to deploy the package");
+                        println!("{}", err);
+                        process::exit(1)
+                    }
+                },
+            };
+            Ok(())
+        }
+        Some(Commands::Config(args)) => {
+            match args.command {
+                ConfigCommands::Validate(args) => match config_validate_cmd(args) {
+                    Ok(_) => {}
+                    Err(err) => {
+                        println!("{}", err);
+                        process::exit(1)
+                    }
+                },
+                ConfigCommands::Sync(args) => match config_sync_cmd(args) {
+                    Ok(_) => {}
+                    Err(err) => {
+                        println!("Failed to sync Pulsar instance config");
+                        println!("{}", err);
+                        process::exit(1)
+                    }
+                },
+            };
+            Ok(())
+        }
+        Some(Commands::Token(args)) => {
+            match args.command {
+                TokenCommands::Create(args) => match token_create_cmd(args, output) {
+                    Ok(_) => {}
+                    Err(err) => {
+                        println!("Failed to create token");
+                        println!("{}", err);
+                        process::exit(1)
+                    }
+                },
+            };
+            Ok(())
+        }
+        Some(Commands::Produce(args)) => {
+            match produce_cmd(args) {
+                Ok(_) => {}
+                Err(err) => {
+                    println!("Failed to produce messages");
+                    println!("{}", err);
+                    process::exit(1)
+                }
+            };
+            Ok(())
+        }
+        Some(Commands::Consume(args)) => {
+            match consume_cmd(args) {
+                Ok(_) => {}
+                Err(err) => {
+                    println!("Failed to consume messages");
+                    println!("{}", err);
+                    process::exit(1)
+                }
+            };
+            Ok(())
+        }
         Some(Commands::Exec(args)) => {
-            let instance_name = args.instance.unwrap_or(get_default_instance_name()?);
+            let instance_name = resolve_instance_name(args.instance)?;
             let cluster_index = args.cluster.unwrap_or(get_default_cluster_index()?);
 
             exec_cmd(instance_name, cluster_index, args.command)?;
@@ -0,0 +1,30 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    Json,
+    Yaml,
+}
+
+impl OutputFormat {
+    pub fn is_text(&self) -> bool {
+        *self == OutputFormat::Text
+    }
+
+    /// Prints the value as JSON or YAML. Does nothing for the text format,
+    /// callers print human-readable output themselves.
+    pub fn print<T: Serialize>(&self, value: &T) -> Result<()> {
+        match self {
+            OutputFormat::Text => {}
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+            OutputFormat::Yaml => print!("{}", serde_yaml::to_string(value)?),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde_yaml::Value;
+use std::path::{Path, PathBuf};
+
+use crate::config_schema::parse_config;
+use crate::instance_config::InstanceConfig;
+
+/// Same file name as instance configs, so `puls edit` output can be committed as is.
+pub const PROJECT_CONFIG_FILE_NAME: &str = "puls.yml";
+
+/// Instance defined by a `puls.yml` committed to a project repository.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectConfig {
+    pub instance_name: String,
+    pub instance_config: InstanceConfig,
+}
+
+/// Looks for the project config in the directory and its parents, like `docker compose` does.
+pub fn find_project_config_file(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE_NAME))
+        .find(|file| file.is_file())
+}
+
+/// Instance name derived from the project directory name, with unsupported characters replaced by dashes.
+pub fn project_instance_name(project_dir: &Path) -> Result<String> {
+    let dir_name = project_dir
+        .file_name()
+        .map(|dir_name| dir_name.to_string_lossy().to_string())
+        .ok_or(anyhow!(
+            "Can't derive instance name from {}. Set `instance_name` in {PROJECT_CONFIG_FILE_NAME}",
+            project_dir.display()
+        ))?;
+
+    Ok(dir_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect())
+}
+
+/// Instance names become directory names, so only a safe subset of characters is allowed.
+pub fn check_instance_name(instance_name: &str) -> Result<()> {
+    let instance_name_regex = Regex::new(r"^[a-zA-Z0-9_-]+$").unwrap();
+    if !instance_name_regex.is_match(instance_name) {
+        return Err(anyhow!("Invalid instance name provided. Only alphanumeric characters, dashes, and underscores are allowed."));
+    }
+    Ok(())
+}
+
+/// Reads the project config: instance config fields plus an optional `instance_name`,
+/// which defaults to the project directory name.
+pub fn read_project_config(project_config_file: &Path) -> Result<ProjectConfig> {
+    let project_dir = project_config_file
+        .parent()
+        .ok_or(anyhow!("Invalid project config file path"))?;
+
+    let project_config_yaml = std::fs::read_to_string(project_config_file)?;
+    let project_config_value = serde_yaml::from_str::<Value>(&project_config_yaml)?;
+
+    let instance_name = match project_config_value.get("instance_name") {
+        Some(Value::String(instance_name)) => instance_name.clone(),
+        Some(_) => return Err(anyhow!("instance_name must be a string")),
+        None => project_instance_name(project_dir)?,
+    };
+    check_instance_name(&instance_name)?;
+
+    // Committed files are never migrated in place, only in memory
+    let (mut instance_config, _) = parse_config(&project_config_yaml)?;
+
+    // Relative to the project, not to the directory puls runs in
+    if let Some(functions_dir) = &instance_config.functions_dir {
+        instance_config.functions_dir = Some(project_dir.join(functions_dir).display().to_string());
+    }
+
+    Ok(ProjectConfig {
+        instance_name,
+        instance_config,
+    })
+}
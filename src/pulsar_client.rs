@@ -0,0 +1,214 @@
+use anyhow::{anyhow, Result};
+use clap::{Args, ValueEnum};
+
+/// Splits messages passed to `pulsar-client produce`. Messages are passed one per `-m` flag,
+/// so the separator must never occur in them, unlike the default comma.
+const MESSAGE_SEPARATOR: &str = "\u{1e}";
+
+/// Characters of messages passed to a single `pulsar-client produce` run. Keeps the
+/// command line well below `ARG_MAX` even for multi-byte characters.
+const MESSAGES_BATCH_SIZE: usize = 256 * 1024;
+
+/// Bash script that reads NUL-terminated messages from stdin and produces them in batches,
+/// so that messages don't have to fit on a single command line.
+/// `pulsar-client` arguments are passed as positional parameters.
+pub fn produce_script() -> String {
+    format!(
+        r#"batch=(); size=0;
+while IFS= read -r -d "" message; do
+  if [ ${{#batch[@]}} -gt 0 ] && [ $((size + ${{#message}})) -gt {MESSAGES_BATCH_SIZE} ]; then
+    bin/pulsar-client "$@" "${{batch[@]}}" || exit;
+    batch=(); size=0;
+  fi;
+  batch+=(--messages "$message"); size=$((size + ${{#message}}));
+done;
+[ ${{#batch[@]}} -eq 0 ] || exec bin/pulsar-client "$@" "${{batch[@]}}""#
+    )
+}
+
+/// Messages in the format read by [`produce_script`].
+pub fn produce_script_input(messages: &[String]) -> Result<Vec<u8>> {
+    let mut input = Vec::new();
+    for message in messages {
+        if message.contains('\0') {
+            return Err(anyhow!("Messages must not contain NUL characters"));
+        }
+        input.extend_from_slice(message.as_bytes());
+        input.push(0);
+    }
+    Ok(input)
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum SchemaType {
+    #[default]
+    Bytes,
+    String,
+    Json,
+    Avro,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum SubscriptionType {
+    #[default]
+    Exclusive,
+    Shared,
+    Failover,
+    KeyShared,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum SubscriptionPosition {
+    #[default]
+    Latest,
+    Earliest,
+}
+
+/// `pulsar-client produce` options
+#[derive(Args, Clone, Debug, Default, PartialEq)]
+pub struct ProduceOptions {
+    /// Topic name, e.g. `my-topic` or `persistent://public/default/my-topic`
+    pub topic: String,
+
+    /// Message to send. Can be repeated. Messages are read from the file or stdin otherwise, one per line
+    #[arg(short, long = "message")]
+    pub messages: Vec<String>,
+
+    /// Read messages from the file, one per line. Use `-` for stdin
+    #[arg(short, long)]
+    pub file: Option<String>,
+
+    /// Message key
+    #[arg(short, long)]
+    pub key: Option<String>,
+
+    /// Message property as `key=value`. Can be repeated
+    #[arg(short, long = "property")]
+    pub properties: Vec<String>,
+
+    /// Value schema
+    #[arg(long, value_enum, default_value_t = SchemaType::Bytes)]
+    pub schema_type: SchemaType,
+
+    /// File with the JSON or Avro schema definition
+    #[arg(long)]
+    pub schema_file: Option<String>,
+
+    /// Send every message this many times
+    #[arg(short, long, default_value_t = 1)]
+    pub num_produce: u32,
+
+    /// Messages per second. 0 means no limit
+    #[arg(short, long, default_value_t = 0.0)]
+    pub rate: f64,
+}
+
+impl ProduceOptions {
+    /// `pulsar-client` arguments without the messages, which are added by [`produce_script`].
+    /// The schema definition is required for JSON and Avro schemas.
+    pub fn pulsar_client_args(&self, schema_definition: Option<&str>) -> Vec<String> {
+        let mut args = vec![
+            "produce".to_string(),
+            self.topic.clone(),
+            "--separator".to_string(),
+            MESSAGE_SEPARATOR.to_string(),
+            "--num-produce".to_string(),
+            self.num_produce.to_string(),
+            "--rate".to_string(),
+            self.rate.to_string(),
+        ];
+
+        if let Some(key) = &self.key {
+            args.push("--key".to_string());
+            args.push(key.clone());
+        }
+
+        for property in &self.properties {
+            args.push("--properties".to_string());
+            args.push(property.clone());
+        }
+
+        let value_schema = match (self.schema_type, schema_definition) {
+            (SchemaType::Bytes, _) => None,
+            (SchemaType::String, _) => Some("string".to_string()),
+            (SchemaType::Json, Some(definition)) => Some(format!("json:{definition}")),
+            (SchemaType::Avro, Some(definition)) => Some(format!("avro:{definition}")),
+            (SchemaType::Json, None) => Some("json".to_string()),
+            (SchemaType::Avro, None) => Some("avro".to_string()),
+        };
+
+        if let Some(value_schema) = value_schema {
+            args.push("--value-schema".to_string());
+            args.push(value_schema);
+        }
+
+        args
+    }
+}
+
+/// `pulsar-client consume` options
+#[derive(Args, Clone, Debug, Default, PartialEq)]
+pub struct ConsumeOptions {
+    /// Topic name, e.g. `my-topic` or `persistent://public/default/my-topic`
+    pub topic: String,
+
+    /// Subscription name
+    #[arg(short, long, default_value = "puls")]
+    pub subscription: String,
+
+    #[arg(short = 't', long, value_enum, default_value_t = SubscriptionType::Exclusive)]
+    pub subscription_type: SubscriptionType,
+
+    /// Where a new subscription starts
+    #[arg(short = 'p', long, value_enum, default_value_t = SubscriptionPosition::Latest)]
+    pub subscription_position: SubscriptionPosition,
+
+    /// Stop after this many messages. 0 means consume until interrupted
+    #[arg(short, long, default_value_t = 0)]
+    pub num_messages: u32,
+
+    /// Messages per second. 0 means no limit
+    #[arg(short, long, default_value_t = 0.0)]
+    pub rate: f64,
+
+    /// Decode JSON and Avro messages with the topic schema
+    #[arg(long, default_value_t = false)]
+    pub auto_consume: bool,
+}
+
+impl ConsumeOptions {
+    pub fn pulsar_client_args(&self) -> Vec<String> {
+        let subscription_type = match self.subscription_type {
+            SubscriptionType::Exclusive => "Exclusive",
+            SubscriptionType::Shared => "Shared",
+            SubscriptionType::Failover => "Failover",
+            SubscriptionType::KeyShared => "Key_Shared",
+        };
+        let subscription_position = match self.subscription_position {
+            SubscriptionPosition::Latest => "Latest",
+            SubscriptionPosition::Earliest => "Earliest",
+        };
+
+        let mut args = vec![
+            "consume".to_string(),
+            self.topic.clone(),
+            "--subscription-name".to_string(),
+            self.subscription.clone(),
+            "--subscription-type".to_string(),
+            subscription_type.to_string(),
+            "--subscription-position".to_string(),
+            subscription_position.to_string(),
+            "--num-messages".to_string(),
+            self.num_messages.to_string(),
+            "--rate".to_string(),
+            self.rate.to_string(),
+        ];
+
+        if self.auto_consume {
+            args.push("--schema-type".to_string());
+            args.push("auto_consume".to_string());
+        }
+
+        args
+    }
+}
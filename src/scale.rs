@@ -0,0 +1,166 @@
+use anyhow::{anyhow, Result};
+
+use crate::docker_compose::clusters::cluster_instance_config;
+use crate::docker_compose::compose::ComposeFile;
+use crate::docker_compose::config_overrides::managed_ledger_quorum;
+use crate::docker_compose::docker_compose::{bookie_service_name, cluster_name};
+use crate::instance_config::{ClusterConfig, InstanceConfig};
+
+/// Bookies register with their host name, see `useHostNameAsBookieID`.
+const BOOKIE_PORT: u16 = 3181;
+
+/// Sets broker and bookie counts of the whole instance, or of a single cluster if `cluster_index` is set.
+/// Scaling the whole instance also scales clusters that set their own counts.
+pub fn set_scale(
+    instance_config: &mut InstanceConfig,
+    num_brokers: Option<u32>,
+    num_bookies: Option<u32>,
+    cluster_index: Option<u32>,
+) -> Result<()> {
+    if num_brokers.is_none() && num_bookies.is_none() {
+        return Err(anyhow!("Pass --brokers, --bookies or both"));
+    }
+
+    let Some(cluster_index) = cluster_index else {
+        if let Some(num_brokers) = num_brokers {
+            instance_config.num_brokers = num_brokers;
+        }
+        if let Some(num_bookies) = num_bookies {
+            instance_config.num_bookies = num_bookies;
+        }
+        for cluster_config in &mut instance_config.clusters {
+            if num_brokers.is_some() {
+                cluster_config.num_brokers = None;
+            }
+            if num_bookies.is_some() {
+                cluster_config.num_bookies = None;
+            }
+        }
+        return Ok(());
+    };
+
+    if cluster_index >= instance_config.num_clusters {
+        return Err(anyhow!(
+            "There is no {} in the instance, it has {} cluster(s)",
+            cluster_name(cluster_index),
+            instance_config.num_clusters
+        ));
+    }
+
+    let cluster_index = cluster_index as usize;
+    if instance_config.clusters.len() <= cluster_index {
+        instance_config
+            .clusters
+            .resize(cluster_index + 1, ClusterConfig::default());
+    }
+    let cluster_config = &mut instance_config.clusters[cluster_index];
+    if num_brokers.is_some() {
+        cluster_config.num_brokers = num_brokers;
+    }
+    if num_bookies.is_some() {
+        cluster_config.num_bookies = num_bookies;
+    }
+
+    Ok(())
+}
+
+/// A bookie that is removed by scaling down. Its ledgers are re-replicated before it goes away.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemovedBookie {
+    pub cluster_index: u32,
+    pub service_name: String,
+    /// Compose volume with the bookie's journal and ledgers
+    pub volume_name: String,
+}
+
+impl RemovedBookie {
+    pub fn bookie_id(&self) -> String {
+        format!("{}:{BOOKIE_PORT}", self.service_name)
+    }
+
+    /// Bookie of the same cluster that stays and runs the decommission command.
+    pub fn remaining_bookie_service_name(&self) -> String {
+        bookie_service_name(&cluster_name(self.cluster_index), 0)
+    }
+
+    /// Waits until the auditor re-replicated the bookie's ledgers, then deletes its cookie.
+    pub fn decommission_command(&self) -> Vec<String> {
+        [
+            "bin/bookkeeper",
+            "shell",
+            "decommissionbookie",
+            "-bookieid",
+            &self.bookie_id(),
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect()
+    }
+}
+
+/// Bookies of `instance_config` that `scaled_instance_config` doesn't have, the last ones first.
+pub fn removed_bookies(
+    instance_config: &InstanceConfig,
+    scaled_instance_config: &InstanceConfig,
+) -> Vec<RemovedBookie> {
+    let mut removed_bookies = Vec::new();
+    for cluster_index in 0..instance_config.num_clusters {
+        let num_bookies = cluster_instance_config(instance_config, cluster_index).num_bookies;
+        let scaled_num_bookies =
+            cluster_instance_config(scaled_instance_config, cluster_index).num_bookies;
+
+        let cluster_name = cluster_name(cluster_index);
+        for i in (scaled_num_bookies..num_bookies).rev() {
+            removed_bookies.push(RemovedBookie {
+                cluster_index,
+                service_name: bookie_service_name(&cluster_name, i),
+                volume_name: format!("bookie-data-{cluster_name}-{i}"),
+            });
+        }
+    }
+    removed_bookies
+}
+
+/// Fails if some cluster would have fewer bookies than its existing ledgers are written to,
+/// their copies on the decommissioned bookies couldn't be re-replicated then.
+pub fn check_scale_down(
+    instance_config: &InstanceConfig,
+    scaled_instance_config: &InstanceConfig,
+) -> Result<()> {
+    for cluster_index in 0..instance_config.num_clusters {
+        let scaled_num_bookies =
+            cluster_instance_config(scaled_instance_config, cluster_index).num_bookies;
+        let [ensemble_size, _, _] = managed_ledger_quorum(instance_config, cluster_index)?;
+        if scaled_num_bookies < ensemble_size {
+            return Err(anyhow!(
+                "{} writes ledgers to {ensemble_size} bookies, it can't be scaled down to {scaled_num_bookies}",
+                cluster_name(cluster_index)
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Services kept by scaling whose definition or mounted configs changed, e.g. brokers with
+/// a new managed ledger quorum or Prometheus with new scrape targets. `up --no-recreate`
+/// leaves them as they are, so they have to be recreated to pick up the change.
+pub fn changed_services(
+    docker_compose: &ComposeFile,
+    scaled_docker_compose: &ComposeFile,
+) -> Vec<String> {
+    scaled_docker_compose
+        .services
+        .iter()
+        .filter(|(service_name, scaled_service)| {
+            let Some(service) = docker_compose.services.get(*service_name) else {
+                return false;
+            };
+            let is_config_changed = scaled_service.configs.iter().any(|service_config| {
+                docker_compose.configs.get(&service_config.source)
+                    != scaled_docker_compose.configs.get(&service_config.source)
+            });
+            service != *scaled_service || is_config_changed
+        })
+        .map(|(service_name, _)| service_name.clone())
+        .collect()
+}
@@ -0,0 +1,104 @@
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Directory with snapshots, relative to the instance directory.
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+
+const MANIFEST_FILE_NAME: &str = "snapshot.yml";
+
+/// Copy of the instance config file the snapshot was taken with.
+pub const SNAPSHOT_CONFIG_FILE_NAME: &str = "puls.yml";
+
+/// Copy of the instance config file taken before a restore replaces it,
+/// relative to the instance directory.
+pub const RESTORE_BACKUP_CONFIG_FILE_NAME: &str = "puls.yml.bak";
+
+/// Describes the snapshot. Stored next to the volume archives.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotManifest {
+    pub name: String,
+
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+
+    pub pulsar_version: String,
+
+    /// Compose volumes archived in the snapshot. Volumes the instance never created are left out
+    pub volumes: Vec<String>,
+}
+
+pub fn volume_archive_file_name(volume_name: &str) -> String {
+    format!("{volume_name}.tar.gz")
+}
+
+/// Docker volume name of a compose volume. Compose prefixes volumes with the project name.
+pub fn docker_volume_name(project_name: &str, volume_name: &str) -> String {
+    format!("{project_name}_{volume_name}")
+}
+
+pub fn snapshot_dir(instance_dir: &Path, snapshot_name: &str) -> Result<PathBuf> {
+    let snapshot_name_regex = Regex::new(r"^[a-zA-Z0-9_.-]+$").unwrap();
+    if !snapshot_name_regex.is_match(snapshot_name) || snapshot_name.starts_with('.') {
+        return Err(anyhow!("Invalid snapshot name \"{snapshot_name}\". Only alphanumeric characters, dots, dashes, and underscores are allowed."));
+    }
+
+    Ok(instance_dir.join(SNAPSHOTS_DIR).join(snapshot_name))
+}
+
+pub fn write_manifest(snapshot_dir: &Path, manifest: &SnapshotManifest) -> Result<()> {
+    std::fs::write(
+        snapshot_dir.join(MANIFEST_FILE_NAME),
+        serde_yaml::to_string(manifest)?,
+    )?;
+    Ok(())
+}
+
+pub fn read_manifest(snapshot_dir: &Path) -> Result<SnapshotManifest> {
+    let manifest_file = snapshot_dir.join(MANIFEST_FILE_NAME);
+    let manifest_yaml = std::fs::read_to_string(&manifest_file)
+        .map_err(|_| anyhow!("No snapshot found at {}", snapshot_dir.display()))?;
+
+    Ok(serde_yaml::from_str(&manifest_yaml)?)
+}
+
+/// Checks that the config file and every volume archive of the snapshot can be read,
+/// so that a restore doesn't fail after the instance volumes are gone.
+pub fn check_snapshot_files(snapshot_dir: &Path, manifest: &SnapshotManifest) -> Result<()> {
+    let file_names = std::iter::once(SNAPSHOT_CONFIG_FILE_NAME.to_string()).chain(
+        manifest
+            .volumes
+            .iter()
+            .map(|volume_name| volume_archive_file_name(volume_name)),
+    );
+
+    for file_name in file_names {
+        let file = snapshot_dir.join(file_name);
+        std::fs::File::open(&file).map_err(|err| {
+            anyhow!(
+                "Snapshot \"{}\" is incomplete, failed to read {}. {err}",
+                manifest.name,
+                file.display()
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Snapshots of the instance, oldest first.
+pub fn list_snapshots(instance_dir: &Path) -> Result<Vec<SnapshotManifest>> {
+    let snapshots_dir = instance_dir.join(SNAPSHOTS_DIR);
+    if !snapshots_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = std::fs::read_dir(snapshots_dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| read_manifest(&entry.path()).ok())
+        .collect::<Vec<SnapshotManifest>>();
+    snapshots.sort_by_key(|snapshot| snapshot.created_at);
+
+    Ok(snapshots)
+}
@@ -0,0 +1,202 @@
+use serde::Serialize;
+
+use crate::docker_compose::auth::shell_curl_auth_header;
+use crate::docker_compose::clusters::cluster_instance_config;
+use crate::docker_compose::docker_compose::{
+    bookie_service_name, broker_service_name, cluster_name, dekaf_service_name,
+    pulsar_init_job_service_name, pulsar_proxy_service_name,
+};
+use crate::docker_compose::metadata_store::{
+    etcd_service_name, oxia_service_name, zookeeper_service_name,
+};
+use crate::instance_config::{InstanceConfig, MetadataStore};
+
+/// How a check is run against the service container.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckMethod {
+    /// `docker compose exec` in the running container
+    Exec,
+    /// `docker compose exec` of a plain command, for images without a shell like etcd
+    Command,
+    /// `docker compose run` in a new container, for jobs that already exited
+    Run,
+    /// No command, the job container has to exit with zero code
+    Completed,
+}
+
+/// A readiness check of a single component, run by `puls status`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StatusCheck {
+    /// None for services shared by all clusters, e.g. the metadata store
+    pub cluster_name: Option<String>,
+    pub component: String,
+    pub service_name: String,
+    pub method: CheckMethod,
+
+    /// Shell script that exits with zero if the component is healthy. Empty for [CheckMethod::Completed].
+    /// Space separated arguments for [CheckMethod::Command]
+    pub script: String,
+}
+
+/// Calls the admin API as the superuser if authentication is enabled.
+pub fn curl_command(url: &str, instance_config: &InstanceConfig) -> String {
+    match &instance_config.auth {
+        Some(auth_config) => format!(
+            "curl --fail --silent {} {url}",
+            shell_curl_auth_header(&auth_config.superuser_role)
+        ),
+        None => format!("curl --fail --silent {url}"),
+    }
+}
+
+fn curl_check(url: &str, instance_config: &InstanceConfig) -> String {
+    format!("{} --output /dev/null", curl_command(url, instance_config))
+}
+
+fn metadata_store_checks(instance_config: &InstanceConfig) -> Vec<StatusCheck> {
+    let check =
+        |component: &str, service_name: String, method: CheckMethod, script: &str| StatusCheck {
+            cluster_name: None,
+            component: component.to_string(),
+            service_name,
+            method,
+            script: script.to_string(),
+        };
+
+    match instance_config.metadata_store {
+        MetadataStore::Zookeeper => (0..instance_config.num_zookeepers)
+            .map(|i| {
+                check(
+                    "zookeeper",
+                    zookeeper_service_name(i),
+                    CheckMethod::Exec,
+                    "bin/pulsar-zookeeper-ruok.sh",
+                )
+            })
+            .collect(),
+        MetadataStore::Oxia => vec![check(
+            "oxia",
+            oxia_service_name(),
+            CheckMethod::Command,
+            "oxia health --port=6649",
+        )],
+        MetadataStore::Etcd => vec![check(
+            "etcd",
+            etcd_service_name(),
+            CheckMethod::Command,
+            "etcdctl endpoint health",
+        )],
+    }
+}
+
+fn cluster_checks(instance_config: &InstanceConfig, cluster_name: &str) -> Vec<StatusCheck> {
+    let check =
+        |component: &str, service_name: String, method: CheckMethod, script: String| StatusCheck {
+            cluster_name: Some(cluster_name.to_string()),
+            component: component.to_string(),
+            service_name,
+            method,
+            script,
+        };
+
+    let mut checks = vec![check(
+        "init-job",
+        pulsar_init_job_service_name(cluster_name),
+        CheckMethod::Run,
+        "test -f /pulsar/data/init-job/success".to_string(),
+    )];
+
+    for i in 0..instance_config.num_bookies {
+        checks.push(check(
+            "bookie",
+            bookie_service_name(cluster_name, i),
+            CheckMethod::Exec,
+            "bin/bookkeeper shell bookiesanity".to_string(),
+        ));
+    }
+
+    for i in 0..instance_config.num_brokers {
+        checks.push(check(
+            "broker",
+            broker_service_name(cluster_name, i),
+            CheckMethod::Exec,
+            curl_check(
+                "http://127.0.0.1:8080/admin/v2/brokers/health",
+                instance_config,
+            ),
+        ));
+    }
+
+    checks.push(check(
+        "proxy",
+        pulsar_proxy_service_name(cluster_name),
+        CheckMethod::Exec,
+        curl_check(
+            "http://127.0.0.1:8080/admin/v2/brokers/health",
+            instance_config,
+        ),
+    ));
+
+    if instance_config.with_dekaf {
+        checks.push(check(
+            "dekaf",
+            dekaf_service_name(cluster_name),
+            CheckMethod::Exec,
+            "curl --fail --silent --output /dev/null http://127.0.0.1:8090/".to_string(),
+        ));
+    }
+
+    checks
+}
+
+/// Checks of all components of the instance, instance-wide services first.
+pub fn status_checks(instance_config: &InstanceConfig) -> Vec<StatusCheck> {
+    let mut checks = metadata_store_checks(instance_config);
+
+    for cluster_index in 0..instance_config.num_clusters {
+        checks.extend(cluster_checks(
+            &cluster_instance_config(instance_config, cluster_index),
+            &cluster_name(cluster_index),
+        ));
+    }
+
+    checks
+}
+
+/// Arguments of `docker compose` that run the check, None for [CheckMethod::Completed].
+pub fn check_command_args(check: &StatusCheck) -> Option<Vec<String>> {
+    let service_name = check.service_name.clone();
+    let script = check.script.clone();
+
+    let args = match check.method {
+        CheckMethod::Exec => vec![
+            "exec".to_string(),
+            "-T".to_string(),
+            service_name,
+            "bash".to_string(),
+            "-c".to_string(),
+            script,
+        ],
+        // Jobs exit once done, the check runs in a new container with the same volumes
+        CheckMethod::Run => vec![
+            "run".to_string(),
+            "--rm".to_string(),
+            "--no-deps".to_string(),
+            "--entrypoint".to_string(),
+            "bash".to_string(),
+            "-T".to_string(),
+            service_name,
+            "-c".to_string(),
+            script,
+        ],
+        CheckMethod::Command => ["exec", "-T", &service_name]
+            .into_iter()
+            .chain(script.split_whitespace())
+            .map(str::to_string)
+            .collect(),
+        CheckMethod::Completed => return None,
+    };
+
+    Some(args)
+}
@@ -0,0 +1,78 @@
+use anyhow::{anyhow, Result};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use rand::RngCore;
+use regex::Regex;
+use serde::Serialize;
+use std::path::Path;
+
+pub fn secret_key_file_name() -> String {
+    "secret.key".to_string()
+}
+
+pub fn token_file_name(role: &str) -> String {
+    format!("{role}.token")
+}
+
+/// Roles end up in token file names, so they are limited to characters safe in file names.
+pub fn check_role(role: &str) -> Result<()> {
+    let role_regex = Regex::new(r"^[a-zA-Z0-9_.@-]+$").unwrap();
+    if !role_regex.is_match(role) || role.starts_with('.') {
+        return Err(anyhow!("Invalid role \"{role}\". Only alphanumeric characters, dots, dashes, underscores, and @ are allowed."));
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct Claims<'a> {
+    sub: &'a str,
+}
+
+/// Reads the HS256 secret key brokers validate tokens with, generating it on first use.
+pub fn read_or_create_secret_key(auth_dir: &Path) -> Result<Vec<u8>> {
+    let secret_key_file = auth_dir.join(secret_key_file_name());
+    if secret_key_file.exists() {
+        return Ok(std::fs::read(secret_key_file)?);
+    }
+
+    std::fs::create_dir_all(auth_dir)?;
+
+    let mut secret_key = vec![0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret_key);
+    std::fs::write(secret_key_file, &secret_key)?;
+
+    Ok(secret_key)
+}
+
+/// Creates a token without expiration for the role.
+pub fn create_token(secret_key: &[u8], role: &str) -> Result<String> {
+    let token = encode(
+        &Header::default(),
+        &Claims { sub: role },
+        &EncodingKey::from_secret(secret_key),
+    )?;
+
+    Ok(token)
+}
+
+/// Writes a token file per role. Existing tokens are kept.
+pub fn write_tokens(auth_dir: &Path, roles: &[String]) -> Result<()> {
+    let secret_key = read_or_create_secret_key(auth_dir)?;
+
+    for role in roles {
+        check_role(role)?;
+        let token_file = auth_dir.join(token_file_name(role));
+        if !token_file.exists() {
+            std::fs::write(token_file, create_token(&secret_key, role)?)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn read_token(auth_dir: &Path, role: &str) -> Result<String> {
+    check_role(role)?;
+    let token_file = auth_dir.join(token_file_name(role));
+    std::fs::read_to_string(&token_file)
+        .map_err(|_| anyhow!("No token found at {}", token_file.display()))
+}
@@ -0,0 +1,165 @@
+use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::cmp::Ordering;
+
+use crate::docker_compose::clusters::cluster_instance_config;
+use crate::docker_compose::compose::ComposeFile;
+use crate::docker_compose::docker_compose::{
+    bookie_service_name, broker_service_name, cluster_name, pulsar_proxy_service_name,
+};
+use crate::docker_compose::functions::functions_worker_service_name;
+use crate::docker_compose::metadata_store::zookeeper_service_name;
+use crate::instance_config::{ClusterConfig, FunctionsWorker, InstanceConfig, MetadataStore};
+
+/// A service recreated with a new image during `puls upgrade`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct UpgradeStep {
+    pub component: String,
+    pub service_name: String,
+    pub image: String,
+}
+
+impl UpgradeStep {
+    /// Each cluster has a single proxy, so all its client connections drop while it restarts.
+    pub fn drops_client_connections(&self) -> bool {
+        self.component == "proxy"
+    }
+}
+
+/// Compares dotted Pulsar versions, e.g. `3.3.1` and `4.0.0`. Suffixes like `-SNAPSHOT` are
+/// ignored. Returns None for versions that aren't numbered, e.g. `latest`.
+pub fn compare_pulsar_versions(version: &str, other_version: &str) -> Option<Ordering> {
+    fn numbers(version: &str) -> Option<Vec<u64>> {
+        version
+            .split(['-', '+'])
+            .next()?
+            .split('.')
+            .map(|number| number.parse().ok())
+            .collect()
+    }
+
+    Some(numbers(version)?.cmp(&numbers(other_version)?))
+}
+
+/// Clusters that would get an older Pulsar version than they run now, with their current
+/// versions. Pulsar doesn't support downgrades, the data may not be readable by older versions.
+pub fn downgraded_clusters(
+    instance_config: &InstanceConfig,
+    pulsar_version: &str,
+    cluster_index: Option<u32>,
+) -> Vec<(String, String)> {
+    (0..instance_config.num_clusters)
+        .filter(|index| cluster_index.is_none_or(|cluster_index| cluster_index == *index))
+        .map(|index| {
+            (
+                cluster_name(index),
+                cluster_instance_config(instance_config, index).pulsar_version,
+            )
+        })
+        .filter(|(_, current_version)| {
+            compare_pulsar_versions(pulsar_version, current_version) == Some(Ordering::Less)
+        })
+        .collect()
+}
+
+/// Sets the Pulsar version of the whole instance, or of a single cluster if `cluster_index` is set.
+/// Upgrading the whole instance also upgrades clusters that pin their own version.
+pub fn set_pulsar_version(
+    instance_config: &mut InstanceConfig,
+    pulsar_version: &str,
+    cluster_index: Option<u32>,
+) -> Result<()> {
+    let Some(cluster_index) = cluster_index else {
+        instance_config.pulsar_version = pulsar_version.to_string();
+        for cluster_config in &mut instance_config.clusters {
+            cluster_config.pulsar_version = None;
+        }
+        return Ok(());
+    };
+
+    if cluster_index >= instance_config.num_clusters {
+        return Err(anyhow!(
+            "There is no {} in the instance, it has {} cluster(s)",
+            cluster_name(cluster_index),
+            instance_config.num_clusters
+        ));
+    }
+
+    let cluster_index = cluster_index as usize;
+    if instance_config.clusters.len() <= cluster_index {
+        instance_config
+            .clusters
+            .resize(cluster_index + 1, ClusterConfig::default());
+    }
+    instance_config.clusters[cluster_index].pulsar_version = Some(pulsar_version.to_string());
+
+    Ok(())
+}
+
+/// Services in the order Pulsar recommends upgrading them in: ZooKeeper, bookies, brokers, proxies.
+/// Each layer keeps serving the older clients above it while it is being upgraded.
+fn upgrade_order(instance_config: &InstanceConfig) -> Vec<(&'static str, String)> {
+    let mut services = Vec::new();
+
+    if instance_config.metadata_store == MetadataStore::Zookeeper {
+        for i in 0..instance_config.num_zookeepers {
+            services.push(("zookeeper", zookeeper_service_name(i)));
+        }
+    }
+
+    let clusters = (0..instance_config.num_clusters)
+        .map(|cluster_index| {
+            (
+                cluster_name(cluster_index),
+                cluster_instance_config(instance_config, cluster_index),
+            )
+        })
+        .collect::<Vec<(String, InstanceConfig)>>();
+
+    for (cluster_name, cluster_instance_config) in &clusters {
+        for i in 0..cluster_instance_config.num_bookies {
+            services.push(("bookie", bookie_service_name(cluster_name, i)));
+        }
+    }
+
+    for (cluster_name, cluster_instance_config) in &clusters {
+        for i in 0..cluster_instance_config.num_brokers {
+            services.push(("broker", broker_service_name(cluster_name, i)));
+        }
+        if cluster_instance_config.functions_worker == FunctionsWorker::Dedicated {
+            services.push((
+                "functions-worker",
+                functions_worker_service_name(cluster_name),
+            ));
+        }
+    }
+
+    for (cluster_name, _) in &clusters {
+        services.push(("proxy", pulsar_proxy_service_name(cluster_name)));
+    }
+
+    services
+}
+
+/// Running services whose image differs from the one in the regenerated compose file.
+/// `running_images` maps service names to images of their containers. Services that
+/// aren't running are left for `puls start`.
+pub fn upgrade_plan(
+    instance_config: &InstanceConfig,
+    docker_compose: &ComposeFile,
+    running_images: &IndexMap<String, String>,
+) -> Vec<UpgradeStep> {
+    upgrade_order(instance_config)
+        .into_iter()
+        .filter_map(|(component, service_name)| {
+            let image = &docker_compose.services.get(&service_name)?.image;
+            let running_image = running_images.get(&service_name)?;
+            (running_image != image).then(|| UpgradeStep {
+                component: component.to_string(),
+                service_name,
+                image: image.clone(),
+            })
+        })
+        .collect()
+}
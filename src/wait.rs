@@ -0,0 +1,255 @@
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::docker_compose::docker_compose::{
+    cluster_name, post_cluster_create_job_service_name, pulsar_proxy_service_name,
+};
+use crate::instance_config::InstanceConfig;
+use crate::status::{curl_command, CheckMethod, StatusCheck};
+
+/// What `puls wait` waits for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WaitTarget {
+    /// Post-cluster-create jobs finished and default tenants and namespaces exist in all clusters
+    Ready,
+    /// Same as [WaitTarget::Ready], plus the topics from the `resources` section exist
+    TopicsCreated,
+    /// Same as [WaitTarget::Ready], but for a single cluster
+    Cluster(u32),
+}
+
+impl FromStr for WaitTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "ready" => Ok(WaitTarget::Ready),
+            "topics-created" => Ok(WaitTarget::TopicsCreated),
+            _ => value
+                .strip_prefix("cluster-")
+                .and_then(|cluster_index| cluster_index.parse().ok())
+                .map(WaitTarget::Cluster)
+                .ok_or(anyhow!(
+                    "Unknown wait target \"{value}\". Expected ready, topics-created or cluster-N"
+                )),
+        }
+    }
+}
+
+impl fmt::Display for WaitTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WaitTarget::Ready => write!(f, "ready"),
+            WaitTarget::TopicsCreated => write!(f, "topics-created"),
+            WaitTarget::Cluster(cluster_index) => write!(f, "{}", cluster_name(*cluster_index)),
+        }
+    }
+}
+
+/// Parses durations like `300s`, `5m` or `1h`. Plain numbers are seconds.
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let (number, unit_seconds) = match value.char_indices().last() {
+        Some((i, 's')) => (&value[..i], 1),
+        Some((i, 'm')) => (&value[..i], 60),
+        Some((i, 'h')) => (&value[..i], 60 * 60),
+        _ => (value, 1),
+    };
+
+    let number: u64 = number
+        .parse()
+        .map_err(|_| anyhow!("Invalid duration \"{value}\". Expected e.g. 300s, 5m or 1h"))?;
+
+    Ok(Duration::from_secs(number * unit_seconds))
+}
+
+/// Exits with zero if the command output contains all the values as JSON strings.
+fn output_contains_check(command: &str, values: &[String]) -> String {
+    let greps = values
+        .iter()
+        .map(|value| format!(" && echo \"$response\" | grep -q -F '\"{value}\"'"))
+        .collect::<String>();
+
+    format!("response=$({command}){greps}")
+}
+
+/// Gives up on the request once `max_time` is over, so a hanging broker doesn't outlast the wait timeout.
+fn wait_curl_command(url: &str, instance_config: &InstanceConfig, max_time: Duration) -> String {
+    format!(
+        "{} --max-time {:.1}",
+        curl_command(url, instance_config),
+        max_time.as_secs_f64().max(0.1)
+    )
+}
+
+fn curl_contains_check(
+    url: &str,
+    values: &[String],
+    instance_config: &InstanceConfig,
+    max_time: Duration,
+) -> String {
+    output_contains_check(&wait_curl_command(url, instance_config, max_time), values)
+}
+
+fn cluster_ready_checks(
+    instance_config: &InstanceConfig,
+    cluster_index: u32,
+    max_time: Duration,
+) -> Vec<StatusCheck> {
+    let cluster_name = cluster_name(cluster_index);
+    let local_tenant = format!("{cluster_name}-local");
+    let admin_url = "http://127.0.0.1:8080/admin/v2";
+
+    let check = |component: &str, script: String| StatusCheck {
+        cluster_name: Some(cluster_name.clone()),
+        component: component.to_string(),
+        service_name: pulsar_proxy_service_name(&cluster_name),
+        method: CheckMethod::Exec,
+        script,
+    };
+
+    let all_cluster_names = (0..instance_config.num_clusters)
+        .map(self::cluster_name)
+        .collect::<Vec<String>>();
+
+    vec![
+        StatusCheck {
+            cluster_name: Some(cluster_name.clone()),
+            component: "post-job".to_string(),
+            service_name: post_cluster_create_job_service_name(&cluster_name),
+            method: CheckMethod::Completed,
+            script: String::new(),
+        },
+        check(
+            "clusters",
+            curl_contains_check(
+                &format!("{admin_url}/clusters"),
+                &all_cluster_names,
+                instance_config,
+                max_time,
+            ),
+        ),
+        check(
+            "tenants",
+            curl_contains_check(
+                &format!("{admin_url}/tenants"),
+                &[local_tenant.clone(), "global".to_string()],
+                instance_config,
+                max_time,
+            ),
+        ),
+        check(
+            "namespaces",
+            format!(
+                "{} && {}",
+                curl_contains_check(
+                    &format!("{admin_url}/namespaces/{local_tenant}"),
+                    &[format!("{local_tenant}/default")],
+                    instance_config,
+                    max_time,
+                ),
+                curl_contains_check(
+                    &format!("{admin_url}/namespaces/global"),
+                    &["global/default".to_string()],
+                    instance_config,
+                    max_time,
+                ),
+            ),
+        ),
+    ]
+}
+
+/// Checks that the topics from the `resources` section exist in the cluster.
+fn cluster_topics_checks(
+    instance_config: &InstanceConfig,
+    cluster_index: u32,
+    max_time: Duration,
+) -> Vec<StatusCheck> {
+    let Some(resources) = &instance_config.resources else {
+        return Vec::new();
+    };
+
+    let cluster_name = cluster_name(cluster_index);
+    let mut checks = Vec::new();
+
+    for tenant in &resources.tenants {
+        for namespace in &tenant.namespaces {
+            if namespace.topics.is_empty() {
+                continue;
+            }
+
+            let namespace_url = format!(
+                "http://127.0.0.1:8080/admin/v2/persistent/{}/{}",
+                tenant.name, namespace.name
+            );
+            let topic_names = namespace
+                .topics
+                .iter()
+                .map(|topic| {
+                    format!(
+                        "persistent://{}/{}/{}",
+                        tenant.name, namespace.name, topic.name
+                    )
+                })
+                .collect::<Vec<String>>();
+
+            // Partitioned topics are only listed by the `partitioned` endpoint
+            let list_topics = format!(
+                "{} && {}",
+                wait_curl_command(&namespace_url, instance_config, max_time),
+                wait_curl_command(
+                    &format!("{namespace_url}/partitioned"),
+                    instance_config,
+                    max_time
+                )
+            );
+            checks.push(StatusCheck {
+                cluster_name: Some(cluster_name.clone()),
+                component: "topics".to_string(),
+                service_name: pulsar_proxy_service_name(&cluster_name),
+                method: CheckMethod::Exec,
+                script: output_contains_check(&list_topics, &topic_names),
+            });
+        }
+    }
+
+    checks
+}
+
+/// Checks that all pass once the instance reached the wait target.
+/// Admin API requests of the checks give up after `max_time`.
+pub fn wait_checks(
+    instance_config: &InstanceConfig,
+    target: WaitTarget,
+    max_time: Duration,
+) -> Result<Vec<StatusCheck>> {
+    let cluster_indexes = match target {
+        WaitTarget::Cluster(cluster_index) if cluster_index >= instance_config.num_clusters => {
+            return Err(anyhow!(
+                "Pulsar instance has no cluster with index {cluster_index}"
+            ));
+        }
+        WaitTarget::Cluster(cluster_index) => cluster_index..cluster_index + 1,
+        WaitTarget::Ready | WaitTarget::TopicsCreated => 0..instance_config.num_clusters,
+    };
+
+    let mut checks = Vec::new();
+    for cluster_index in cluster_indexes {
+        checks.extend(cluster_ready_checks(
+            instance_config,
+            cluster_index,
+            max_time,
+        ));
+
+        if target == WaitTarget::TopicsCreated {
+            checks.extend(cluster_topics_checks(
+                instance_config,
+                cluster_index,
+                max_time,
+            ));
+        }
+    }
+
+    Ok(checks)
+}
@@ -0,0 +1,76 @@
+use puls::bundle::{export_bundle, parse_bundle, write_bundle_files};
+use puls::instance_config::{AuthConfig, InstanceConfig};
+use uuid::Uuid;
+
+#[test]
+fn test_bundle_round_trip() {
+    let temp_dir = std::env::temp_dir().join(format!("puls-bundle-test-{}", Uuid::new_v4()));
+    let instance_dir = temp_dir.join("original");
+    std::fs::create_dir_all(instance_dir.join("auth")).unwrap();
+    std::fs::write(instance_dir.join("auth/secret.key"), [0u8, 159, 255]).unwrap();
+    std::fs::write(instance_dir.join("auth/admin.token"), "token").unwrap();
+    std::fs::write(instance_dir.join("ports.yml"), "ports: {}").unwrap();
+
+    let instance_config = InstanceConfig {
+        auth: Some(AuthConfig::default()),
+        ..Default::default()
+    };
+    let bundle = export_bundle("original", &instance_config, &instance_dir).unwrap();
+    assert_eq!(
+        bundle.files.keys().collect::<Vec<_>>(),
+        ["auth/admin.token", "auth/secret.key"]
+    );
+
+    let parsed_bundle = parse_bundle(&serde_yaml::to_string(&bundle).unwrap()).unwrap();
+    assert_eq!(parsed_bundle, bundle);
+
+    let imported_dir = temp_dir.join("imported");
+    write_bundle_files(&parsed_bundle, &imported_dir).unwrap();
+    assert_eq!(
+        std::fs::read(imported_dir.join("auth/secret.key")).unwrap(),
+        [0u8, 159, 255]
+    );
+    assert!(!imported_dir.join("ports.yml").exists());
+
+    std::fs::remove_dir_all(temp_dir).unwrap();
+}
+
+#[test]
+fn test_invalid_bundles_are_rejected() {
+    let bundle_yaml = |instance_config: &str, files: &str| {
+        format!("bundle_version: 1\ninstance_name: test\ninstance_config:\n{instance_config}\nfiles:\n{files}")
+    };
+    let valid_config = serde_yaml::to_string(&InstanceConfig::default())
+        .unwrap()
+        .lines()
+        .map(|line| format!("  {line}\n"))
+        .collect::<String>();
+
+    assert!(parse_bundle(&bundle_yaml(&valid_config, "  auth/a.token: dG9rZW4=")).is_ok());
+
+    let err = parse_bundle(&bundle_yaml(
+        &format!("{valid_config}  with_kafka: true"),
+        "  {}",
+    ))
+    .unwrap_err();
+    assert!(err.to_string().contains("with_kafka"));
+
+    let err = parse_bundle(&bundle_yaml(
+        &valid_config.replace("num_brokers: 1", "num_brokers: many"),
+        "  {}",
+    ))
+    .unwrap_err();
+    assert!(err.to_string().contains("num_brokers"));
+
+    for file in [
+        "auth/../../escape",
+        "/etc/passwd",
+        "ports.yml",
+        "auth/nested/a.token",
+    ] {
+        assert!(parse_bundle(&bundle_yaml(&valid_config, &format!("  {file}: dG9rZW4="))).is_err());
+    }
+
+    assert!(parse_bundle("bundle_version: 2").is_err());
+    assert!(parse_bundle("pulsar_version: 3.2.2").is_err());
+}
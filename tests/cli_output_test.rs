@@ -0,0 +1,262 @@
+use assert_cmd::cargo::CommandCargoExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use uuid::Uuid;
+
+fn temp_home_dir() -> PathBuf {
+    let home_dir = std::env::temp_dir().join(format!("puls-test-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(&home_dir).unwrap();
+    home_dir
+}
+
+fn rand_instance_name() -> String {
+    "pulsar-".to_string() + &Uuid::new_v4().to_string()
+}
+
+fn puls(home_dir: &Path) -> Command {
+    let mut command = Command::cargo_bin("puls").unwrap();
+    command.env("HOME", home_dir);
+    command
+}
+
+#[test]
+fn test_describe_error_goes_to_stderr() {
+    let home_dir = temp_home_dir();
+
+    let output = puls(&home_dir)
+        .arg("describe")
+        .arg(rand_instance_name())
+        .arg("--output")
+        .arg("json")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(!output.stderr.is_empty());
+}
+
+#[test]
+fn test_describe_json_output() {
+    let home_dir = temp_home_dir();
+    let instance_name = rand_instance_name();
+
+    let exit_status = puls(&home_dir)
+        .arg("create")
+        .arg("--num-clusters")
+        .arg("2")
+        .arg(instance_name.clone())
+        .output()
+        .unwrap()
+        .status;
+    assert!(exit_status.success());
+
+    let output = puls(&home_dir)
+        .arg("describe")
+        .arg(instance_name.clone())
+        .arg("--output")
+        .arg("json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let describe: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(describe["instance_name"], instance_name);
+    assert_eq!(describe["instance_config"]["num_clusters"], 2);
+    assert_eq!(describe["clusters"].as_array().unwrap().len(), 2);
+    assert!(describe["clusters"][1]["web_service_host_url"]
+        .as_str()
+        .unwrap()
+        .starts_with("http://localhost:"));
+}
+
+#[test]
+fn test_ls_yaml_output() {
+    let home_dir = temp_home_dir();
+    let instance_name = rand_instance_name();
+
+    puls(&home_dir)
+        .arg("create")
+        .arg(instance_name.clone())
+        .output()
+        .unwrap();
+
+    let output = puls(&home_dir)
+        .arg("--output")
+        .arg("yaml")
+        .arg("ls")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let instances: serde_yaml::Value = serde_yaml::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        instances[0]["instance_name"].as_str(),
+        Some(instance_name.as_str())
+    );
+    assert_eq!(instances[0]["is_default"].as_bool(), Some(false));
+}
+
+#[test]
+fn test_token_create_json_output() {
+    let home_dir = temp_home_dir();
+    let instance_name = rand_instance_name();
+
+    let exit_status = puls(&home_dir)
+        .arg("create")
+        .arg("--with-auth")
+        .arg(instance_name.clone())
+        .output()
+        .unwrap()
+        .status;
+    assert!(exit_status.success());
+
+    let output = puls(&home_dir)
+        .arg("--output")
+        .arg("json")
+        .arg("token")
+        .arg("create")
+        .arg("--role")
+        .arg("app")
+        .arg(instance_name.clone())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let token: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(token["role"], "app");
+    assert_eq!(token["token"].as_str().unwrap().split('.').count(), 3);
+}
+
+#[test]
+fn test_export_json_output() {
+    let home_dir = temp_home_dir();
+    let instance_name = rand_instance_name();
+
+    let exit_status = puls(&home_dir)
+        .arg("create")
+        .arg(instance_name.clone())
+        .output()
+        .unwrap()
+        .status;
+    assert!(exit_status.success());
+
+    let output = puls(&home_dir)
+        .arg("--output")
+        .arg("json")
+        .arg("export")
+        .arg(instance_name.clone())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let bundle: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(bundle["instance_name"], instance_name);
+
+    let bundle_file = home_dir.join("bundle.json");
+    std::fs::write(&bundle_file, &output.stdout).unwrap();
+    let exit_status = puls(&home_dir)
+        .arg("import")
+        .arg(&bundle_file)
+        .arg("--name")
+        .arg(rand_instance_name())
+        .output()
+        .unwrap()
+        .status;
+    assert!(exit_status.success());
+}
+
+#[test]
+fn test_project_config_is_synced_explicitly() {
+    let home_dir = temp_home_dir();
+    let instance_name = rand_instance_name();
+    let project_dir = home_dir.join("orders");
+    std::fs::create_dir_all(&project_dir).unwrap();
+    std::fs::write(
+        project_dir.join("puls.yml"),
+        format!("instance_name: {instance_name}\nnum_brokers: 2\n"),
+    )
+    .unwrap();
+
+    let sync = |home_dir: &Path| {
+        let exit_status = puls(home_dir)
+            .current_dir(&project_dir)
+            .arg("config")
+            .arg("sync")
+            .output()
+            .unwrap()
+            .status;
+        assert!(exit_status.success());
+    };
+
+    let describe = |home_dir: &Path| {
+        let output = puls(home_dir)
+            .current_dir(&project_dir)
+            .arg("describe")
+            .arg("--output")
+            .arg("json")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let describe: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        (describe, String::from_utf8(output.stderr).unwrap())
+    };
+
+    // The instance is created on first use, with the messages on stderr
+    let (describe_output, stderr) = describe(&home_dir);
+    assert_eq!(describe_output["instance_config"]["num_brokers"], 2);
+    assert!(stderr.contains(&format!("Creating Pulsar instance {instance_name}")));
+
+    // Changes made by other commands, e.g. `puls scale`, survive implicit resolution
+    let instance_config_file = home_dir
+        .join(".config/puls/instances")
+        .join(&instance_name)
+        .join("puls.yml");
+    let instance_config_yaml = std::fs::read_to_string(&instance_config_file)
+        .unwrap()
+        .replace("num_brokers: 2", "num_brokers: 3");
+    std::fs::write(&instance_config_file, &instance_config_yaml).unwrap();
+
+    let (describe_output, stderr) = describe(&home_dir);
+    assert_eq!(describe_output["instance_config"]["num_brokers"], 3);
+    assert!(stderr.contains("puls config sync"));
+
+    sync(&home_dir);
+    let (describe_output, _) = describe(&home_dir);
+    assert_eq!(describe_output["instance_config"]["num_brokers"], 2);
+}
+
+#[test]
+fn test_reading_old_config_keeps_the_file() {
+    let home_dir = temp_home_dir();
+    let instance_name = rand_instance_name();
+
+    let exit_status = puls(&home_dir)
+        .arg("create")
+        .arg(instance_name.clone())
+        .output()
+        .unwrap()
+        .status;
+    assert!(exit_status.success());
+
+    // Unversioned configs were written before the `version` field existed
+    let instance_dir = home_dir.join(".config/puls/instances").join(&instance_name);
+    let instance_config_yaml = "# Two brokers to test failover\nnum_brokers: 2\n";
+    std::fs::write(instance_dir.join("puls.yml"), instance_config_yaml).unwrap();
+
+    let output = puls(&home_dir)
+        .arg("describe")
+        .arg(instance_name.clone())
+        .arg("--output")
+        .arg("json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let describe: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(describe["instance_config"]["num_brokers"], 2);
+    assert_eq!(
+        std::fs::read_to_string(instance_dir.join("puls.yml")).unwrap(),
+        instance_config_yaml
+    );
+    assert!(!instance_dir.join("puls.yml.v0.bak").exists());
+}
@@ -0,0 +1,113 @@
+use puls::config_schema::{parse_config, validate_config, CONFIG_VERSION};
+use puls::instance_config::InstanceConfig;
+
+#[test]
+fn test_config_migration() {
+    let unversioned_config_yaml = "pulsar_version: 3.1.0\nnum_brokers: 2\n";
+    let (instance_config, migrated_from_version) = parse_config(unversioned_config_yaml).unwrap();
+    assert_eq!(migrated_from_version, Some(0));
+    assert_eq!(
+        instance_config,
+        InstanceConfig {
+            version: CONFIG_VERSION,
+            pulsar_version: "3.1.0".to_string(),
+            num_brokers: 2,
+            ..Default::default()
+        }
+    );
+
+    let current_config_yaml = serde_yaml::to_string(&instance_config).unwrap();
+    assert_eq!(
+        parse_config(&current_config_yaml).unwrap(),
+        (instance_config, None)
+    );
+
+    assert!(parse_config(&format!("version: {}\n", CONFIG_VERSION + 1)).is_err());
+}
+
+#[test]
+fn test_config_validation() {
+    let valid_config_yaml = serde_yaml::to_string(&InstanceConfig::default()).unwrap();
+    assert_eq!(validate_config(&valid_config_yaml, &[]), []);
+
+    let config_yaml = r#"num_bookies: 0
+with_kafka: true
+resources:
+  tenants:
+  - name: public-data
+    namespaces:
+    - name: events
+      topics:
+      - name: clicks
+        partitons: 3
+"#;
+    let issues = validate_config(config_yaml, &[])
+        .iter()
+        .map(|issue| issue.to_string())
+        .collect::<Vec<String>>();
+    assert_eq!(
+        issues,
+        [
+            "line 2: with_kafka: unknown field",
+            "line 10: resources.tenants[0].namespaces[0].topics[0].partitons: unknown field",
+            "line 1: num_bookies: must be at least 1",
+        ]
+    );
+
+    let issues = validate_config("num_clusters: 2\nnum_brokers: many\n", &[]);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].line, Some(2));
+    assert!(issues[0].to_string().contains("num_brokers"));
+
+    assert_eq!(
+        validate_config("instance_name: orders\n", &["instance_name"]),
+        []
+    );
+}
+
+#[test]
+fn test_config_overrides_validation() {
+    let config_yaml = r#"num_bookies: 2
+broker_config:
+  managedLedgerDefaultEnsembleSize: 3
+clusters:
+- bookie_config:
+    journal sync: false
+- broker_config:
+    allowAutoTopicCreation: false
+"#;
+    let issues = validate_config(config_yaml, &[])
+        .iter()
+        .map(|issue| issue.to_string())
+        .collect::<Vec<String>>();
+    assert_eq!(
+        issues,
+        [
+            "line 7: warning: clusters[1]: there is no cluster-1, the entry is ignored, increase num_clusters to add it",
+            "line 5: clusters[0].bookie_config: invalid setting name \"journal sync\"",
+            "line 2: warning: broker_config: ledger ensemble size 3 of cluster-0 is greater than 2 bookies, writes will fail",
+        ]
+    );
+
+    let issues = validate_config("broker_config:\n  managedLedgerDefaultAckQuorum: 3\n", &[]);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].line, Some(1));
+    assert!(issues[0].message.contains("ack quorum <= write quorum"));
+
+    let issues = validate_config("proxy_config:\n  tlsCiphers: [a, b]\n", &[]);
+    assert_eq!(issues.len(), 1);
+    assert!(issues[0].message.contains("tlsCiphers"));
+}
+
+#[test]
+fn test_cluster_fields_are_validated() {
+    let issues = validate_config(
+        "num_clusters: 2\nclusters:\n- num_brokers: 2\n- num_bookies: 0\n",
+        &[],
+    );
+    assert_eq!(issues.len(), 1);
+    assert_eq!(
+        issues[0].to_string(),
+        "line 4: clusters[1].num_bookies: must be at least 1"
+    );
+}
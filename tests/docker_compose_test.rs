@@ -0,0 +1,742 @@
+use puls::config_schema::parse_config;
+use puls::docker_compose::compose::{ComposeFile, DependsOnCondition};
+use puls::docker_compose::docker_compose::{generate_instance, project_name};
+use puls::host_ports::HostPorts;
+use puls::instance_config::{
+    AuthConfig, ComponentResources, ComponentResourcesConfig, ConfigurationStore, FunctionsWorker,
+    InstanceConfig, MetadataStore, ReplicationTopology, ResourceProfile,
+};
+
+#[test]
+fn test_generated_docker_compose_is_valid_yaml() {
+    let instance_config = InstanceConfig {
+        num_clusters: 2,
+        num_brokers: 2,
+        num_bookies: 3,
+        num_zookeepers: 3,
+        with_dekaf: true,
+        ..Default::default()
+    };
+
+    let mut host_ports = HostPorts::new(Some(30000));
+    let instance_output =
+        generate_instance("test".to_string(), instance_config, &mut host_ports).unwrap();
+    let parsed: ComposeFile =
+        serde_yaml::from_str(&instance_output.docker_compose_template).unwrap();
+
+    assert_eq!(parsed, instance_output.docker_compose);
+
+    // 3 zookeepers + 2 clusters * (init job + 2 brokers + 3 bookies + proxy + post create job + dekaf)
+    assert_eq!(parsed.services.len(), 3 + 2 * 9);
+    assert_eq!(parsed.volumes.len(), 2 + 2 + 2 * 3 + 3);
+    assert_eq!(parsed.name, Some("test".to_string()));
+    assert!(parsed.networks.contains_key("pulsar-net-test"));
+
+    let broker = &parsed.services["broker-cluster-1-0"];
+    assert_eq!(broker.environment["clusterName"], "cluster-1");
+    assert_eq!(broker.environment["managedLedgerDefaultEnsembleSize"], "2");
+    assert_eq!(
+        broker.depends_on["bookie-cluster-1-2"].condition,
+        DependsOnCondition::ServiceHealthy
+    );
+
+    let bookie = &parsed.services["bookie-cluster-0-0"];
+    assert_eq!(
+        bookie.depends_on["pulsar-init-job-cluster-0"].condition,
+        DependsOnCondition::ServiceCompletedSuccessfully
+    );
+}
+
+#[test]
+fn test_cluster_outputs_contain_cluster_services() {
+    let instance_config = InstanceConfig {
+        num_clusters: 2,
+        with_dekaf: false,
+        ..Default::default()
+    };
+
+    let mut host_ports = HostPorts::new(Some(30000));
+    let instance_output =
+        generate_instance("test".to_string(), instance_config, &mut host_ports).unwrap();
+
+    for cluster in &instance_output.clusters {
+        let proxy_service_name = format!("pulsar-proxy-cluster-{}", cluster.cluster_index);
+        assert!(cluster.services.contains_key(&proxy_service_name));
+        assert!(cluster.dekaf_host_url.is_none());
+
+        for service_name in cluster.services.keys() {
+            assert!(instance_output
+                .docker_compose
+                .services
+                .contains_key(service_name));
+        }
+    }
+}
+
+#[test]
+fn test_cluster_outputs_report_allocated_host_ports() {
+    let instance_config = InstanceConfig {
+        num_clusters: 8,
+        with_dekaf: true,
+        ..Default::default()
+    };
+
+    let mut host_ports = HostPorts::new(Some(30000));
+    let instance_output =
+        generate_instance("test".to_string(), instance_config, &mut host_ports).unwrap();
+
+    let cluster = &instance_output.clusters[7];
+    let web_service_host_port = cluster.web_service_host_port.unwrap();
+    assert_eq!(
+        cluster.web_service_host_url,
+        Some(format!("http://localhost:{web_service_host_port}"))
+    );
+    assert_eq!(host_ports.get("cluster-7.dekaf"), cluster.dekaf_host_port);
+
+    let proxy = &instance_output.docker_compose.services["pulsar-proxy-cluster-7"];
+    assert!(proxy
+        .ports
+        .contains(&format!("{web_service_host_port}:8080")));
+
+    // 8 clusters * (web service + broker service + dekaf)
+    assert_eq!(host_ports.ports.len(), 8 * 3);
+    assert!(host_ports.ports.values().all(|port| *port >= 30000));
+}
+
+#[test]
+fn test_compose_project_is_namespaced_by_instance_name() {
+    let instance_output = generate_instance(
+        "Feature-X".to_string(),
+        InstanceConfig::default(),
+        &mut HostPorts::new(Some(30000)),
+    )
+    .unwrap();
+
+    assert_eq!(
+        instance_output.docker_compose.name,
+        Some("feature-x".to_string())
+    );
+    assert!(instance_output
+        .docker_compose
+        .networks
+        .contains_key("pulsar-net-Feature-X"));
+}
+
+#[test]
+fn test_compose_project_name_starts_with_letter_or_digit() {
+    assert_eq!(project_name("Feature-X"), "feature-x");
+    assert_eq!(project_name("_feature"), "puls_feature");
+    assert_eq!(project_name("-feature"), "puls-feature");
+}
+
+#[test]
+fn test_oxia_and_etcd_metadata_stores() {
+    for (metadata_store, service_name, metadata_store_url) in [
+        (MetadataStore::Oxia, "oxia", "oxia://oxia:6648"),
+        (MetadataStore::Etcd, "etcd", "etcd:http://etcd:2379"),
+    ] {
+        let instance_config = InstanceConfig {
+            metadata_store,
+            num_zookeepers: 3,
+            ..Default::default()
+        };
+
+        let instance_output = generate_instance(
+            "test".to_string(),
+            instance_config,
+            &mut HostPorts::new(Some(30000)),
+        )
+        .unwrap();
+        let services = &instance_output.docker_compose.services;
+
+        assert!(services.contains_key(service_name));
+        assert!(!services.keys().any(|name| name.starts_with("zookeeper")));
+
+        let broker = &services["broker-cluster-0-0"];
+        assert_eq!(broker.environment["metadataStoreUrl"], metadata_store_url);
+        assert_eq!(
+            broker.environment["bookkeeperMetadataServiceUri"],
+            format!("metadata-store:{metadata_store_url}")
+        );
+        assert!(broker.depends_on.contains_key(service_name));
+
+        let bookie = &services["bookie-cluster-0-0"];
+        assert!(bookie.depends_on.contains_key(service_name));
+
+        let init_job = &services["pulsar-init-job-cluster-0"];
+        assert!(init_job.command[2].contains(&format!("--metadata-store {metadata_store_url}")));
+    }
+}
+
+#[test]
+fn test_tiered_storage() {
+    let instance_config = InstanceConfig {
+        num_clusters: 2,
+        with_tiered_storage: true,
+        ..Default::default()
+    };
+
+    let instance_output = generate_instance(
+        "test".to_string(),
+        instance_config,
+        &mut HostPorts::new(Some(30000)),
+    )
+    .unwrap();
+    let docker_compose = &instance_output.docker_compose;
+
+    assert!(docker_compose.services.contains_key("minio"));
+    assert!(docker_compose.volumes.contains_key("minio-data"));
+
+    let create_bucket_job = &docker_compose.services["minio-create-bucket-job"];
+    assert!(create_bucket_job.command[0].contains("minio/offload-cluster-0"));
+    assert!(create_bucket_job.command[0].contains("minio/offload-cluster-1"));
+
+    let broker = &docker_compose.services["broker-cluster-1-0"];
+    assert!(broker.image.starts_with("apachepulsar/pulsar-all:"));
+    assert_eq!(broker.environment["managedLedgerOffloadDriver"], "aws-s3");
+    assert_eq!(
+        broker.environment["s3ManagedLedgerOffloadBucket"],
+        "offload-cluster-1"
+    );
+    assert_eq!(
+        broker.environment["s3ManagedLedgerOffloadServiceEndpoint"],
+        "http://minio:9000"
+    );
+    assert_eq!(
+        broker.depends_on["minio-create-bucket-job"].condition,
+        DependsOnCondition::ServiceCompletedSuccessfully
+    );
+
+    let post_cluster_create_job =
+        &docker_compose.services["pulsar-post-cluster-create-job-cluster-1"];
+    assert!(post_cluster_create_job.command[2]
+        .contains("namespaces set-offload-threshold --size 10M cluster-1-local/default"));
+}
+
+#[test]
+fn test_tiered_storage_is_disabled_by_default() {
+    let instance_output = generate_instance(
+        "test".to_string(),
+        InstanceConfig::default(),
+        &mut HostPorts::new(Some(30000)),
+    )
+    .unwrap();
+    let docker_compose = &instance_output.docker_compose;
+
+    assert!(!docker_compose.services.contains_key("minio"));
+    let broker = &docker_compose.services["broker-cluster-0-0"];
+    assert!(!broker
+        .environment
+        .contains_key("managedLedgerOffloadDriver"));
+}
+
+#[test]
+fn test_monitoring() {
+    let instance_config = InstanceConfig {
+        num_clusters: 2,
+        with_monitoring: true,
+        ..Default::default()
+    };
+
+    let mut host_ports = HostPorts::new(Some(30000));
+    let instance_output =
+        generate_instance("test".to_string(), instance_config, &mut host_ports).unwrap();
+    let docker_compose = &instance_output.docker_compose;
+
+    assert!(docker_compose.services.contains_key("prometheus"));
+    assert!(docker_compose.services.contains_key("grafana-cluster-0"));
+    assert!(docker_compose.services.contains_key("grafana-cluster-1"));
+    assert!(docker_compose.volumes.contains_key("prometheus-data"));
+
+    let prometheus_config = &docker_compose.configs["prometheus-config"].content;
+    assert!(prometheus_config.contains("broker-cluster-0-0:8080"));
+    assert!(prometheus_config.contains("broker-cluster-1-0:8080"));
+    assert!(prometheus_config.contains("bookie-cluster-0-0:8000"));
+    assert!(prometheus_config.contains("zookeeper-0:8000"));
+
+    for cluster in &instance_output.clusters {
+        let grafana_host_url = cluster.grafana_host_url.clone().unwrap();
+        let grafana = &docker_compose.services[&format!("grafana-{}", cluster.cluster_name)];
+        assert!(grafana_host_url.starts_with("http://localhost:"));
+        assert!(grafana.ports[0].starts_with(grafana_host_url.rsplit(':').next().unwrap()));
+        assert_eq!(grafana.environment["PULSAR_CLUSTER"], cluster.cluster_name);
+        assert!(!grafana.image.ends_with(":latest"));
+    }
+
+    let yaml = serde_yaml::to_string(docker_compose).unwrap();
+    let parsed: ComposeFile = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(parsed.configs.len(), 1);
+}
+
+#[test]
+fn test_monitoring_is_disabled_by_default() {
+    let instance_output = generate_instance(
+        "test".to_string(),
+        InstanceConfig::default(),
+        &mut HostPorts::new(Some(30000)),
+    )
+    .unwrap();
+
+    assert!(instance_output.clusters[0].grafana_host_url.is_none());
+    assert!(instance_output.docker_compose.configs.is_empty());
+}
+
+#[test]
+fn test_hub_and_spoke_replication() {
+    let instance_config = InstanceConfig {
+        num_clusters: 3,
+        configuration_store: ConfigurationStore::PerCluster,
+        replication_topology: ReplicationTopology::HubAndSpoke,
+        ..Default::default()
+    };
+
+    let mut host_ports = HostPorts::new(Some(30000));
+    let instance_output =
+        generate_instance("test".to_string(), instance_config, &mut host_ports).unwrap();
+    let docker_compose = &instance_output.docker_compose;
+
+    let hub_job = &docker_compose.services["pulsar-post-cluster-create-job-cluster-0"];
+    assert!(hub_job.command[2]
+        .contains("set-clusters --clusters cluster-0,cluster-1,cluster-2 global/default"));
+
+    let spoke_job = &docker_compose.services["pulsar-post-cluster-create-job-cluster-2"];
+    assert!(
+        spoke_job.command[2].contains("set-clusters --clusters cluster-0,cluster-2 global/default")
+    );
+
+    let broker = &docker_compose.services["broker-cluster-2-0"];
+    assert_eq!(
+        broker.environment["configurationMetadataStoreUrl"],
+        "zk:zookeeper-0:2181/configuration-cluster-2"
+    );
+    assert_eq!(
+        broker.environment["bookkeeperMetadataServiceUri"],
+        "zk://zookeeper-0:2181/bookkeeper-cluster-2"
+    );
+
+    let init_job = &docker_compose.services["pulsar-init-job-cluster-2"];
+    assert!(init_job.command[2]
+        .contains("--configuration-metadata-store zk:zookeeper-0:2181/configuration-cluster-2"));
+}
+
+#[test]
+fn test_one_way_replication_needs_per_cluster_configuration_store() {
+    let instance_config = InstanceConfig {
+        num_clusters: 2,
+        replication_topology: ReplicationTopology::OneWay,
+        ..Default::default()
+    };
+
+    let result = generate_instance(
+        "test".to_string(),
+        instance_config.clone(),
+        &mut HostPorts::new(Some(30000)),
+    );
+    assert!(result.is_err());
+
+    let instance_config = InstanceConfig {
+        configuration_store: ConfigurationStore::PerCluster,
+        ..instance_config
+    };
+    let instance_output = generate_instance(
+        "test".to_string(),
+        instance_config,
+        &mut HostPorts::new(Some(30000)),
+    )
+    .unwrap();
+
+    let job = &instance_output.docker_compose.services["pulsar-post-cluster-create-job-cluster-1"];
+    assert!(job.command[2].contains("set-clusters --clusters cluster-1 global/default"));
+}
+
+#[test]
+fn test_tls() {
+    let instance_config = InstanceConfig {
+        tls: true,
+        ..Default::default()
+    };
+
+    let mut host_ports = HostPorts::new(Some(30000));
+    let instance_output =
+        generate_instance("test".to_string(), instance_config, &mut host_ports).unwrap();
+
+    let cluster = &instance_output.clusters[0];
+    assert_eq!(
+        cluster.web_service_host_url,
+        Some("https://localhost:30000".to_string())
+    );
+    assert_eq!(
+        cluster.broker_service_host_url,
+        Some("pulsar+ssl://localhost:30001".to_string())
+    );
+
+    let services = &instance_output.docker_compose.services;
+
+    let proxy = &services["pulsar-proxy-cluster-0"];
+    assert_eq!(proxy.ports, vec!["30000:8443", "30001:6651"]);
+    assert_eq!(proxy.environment["tlsEnabledWithBroker"], "true");
+    assert_eq!(
+        proxy.volumes,
+        vec![
+            "./certs/ca.cert.pem:/pulsar/certs/ca.cert.pem:ro",
+            "./certs/pulsar-proxy-cluster-0.cert.pem:/pulsar/certs/pulsar-proxy-cluster-0.cert.pem:ro",
+            "./certs/pulsar-proxy-cluster-0.key.pem:/pulsar/certs/pulsar-proxy-cluster-0.key.pem:ro",
+        ]
+    );
+    assert!(services
+        .values()
+        .flat_map(|service| &service.volumes)
+        .all(|volume| !volume.contains("ca.key.pem")));
+
+    let broker = &services["broker-cluster-0-0"];
+    assert_eq!(broker.environment["brokerServicePortTls"], "6651");
+    assert_eq!(
+        broker.environment["tlsCertificateFilePath"],
+        "/pulsar/certs/broker-cluster-0-0.cert.pem"
+    );
+    assert_eq!(
+        broker.environment["bookkeeperTLSClientAuthentication"],
+        "true"
+    );
+
+    let bookie = &services["bookie-cluster-0-0"];
+    assert_eq!(
+        bookie.environment["PULSAR_PREFIX_tlsKeyStore"],
+        "/pulsar/certs/bookie-cluster-0-0.key.pem"
+    );
+
+    let init_job = &services["pulsar-init-job-cluster-0"];
+    assert!(init_job.command[2]
+        .contains("--broker-service-url-tls pulsar+ssl://pulsar-proxy-cluster-0:6651"));
+}
+
+#[test]
+fn test_auth() {
+    let instance_config = InstanceConfig {
+        with_dekaf: true,
+        auth: Some(AuthConfig::default()),
+        ..Default::default()
+    };
+
+    let instance_output = generate_instance(
+        "test".to_string(),
+        instance_config,
+        &mut HostPorts::new(Some(30000)),
+    )
+    .unwrap();
+    let services = &instance_output.docker_compose.services;
+
+    let broker = &services["broker-cluster-0-0"];
+    assert_eq!(broker.environment["authenticationEnabled"], "true");
+    assert_eq!(broker.environment["authorizationEnabled"], "true");
+    assert_eq!(broker.environment["superUserRoles"], "admin,proxy");
+    assert_eq!(
+        broker.environment["tokenSecretKey"],
+        "file:///pulsar/auth/secret.key"
+    );
+    assert!(broker
+        .volumes
+        .contains(&"./auth:/pulsar/auth:ro".to_string()));
+    assert_eq!(broker.healthcheck.as_ref().unwrap().test[0], "CMD-SHELL");
+
+    let proxy = &services["pulsar-proxy-cluster-0"];
+    assert_eq!(
+        proxy.environment["brokerClientAuthenticationParameters"],
+        "file:///pulsar/auth/proxy.token"
+    );
+    assert_eq!(
+        proxy.environment["authParams"],
+        "file:///pulsar/auth/admin.token"
+    );
+
+    let post_cluster_create_job = &services["pulsar-post-cluster-create-job-cluster-0"];
+    assert_eq!(
+        post_cluster_create_job.environment["authParams"],
+        "file:///pulsar/auth/admin.token"
+    );
+
+    let dekaf = &services["dekaf-ui-cluster-0"];
+    assert_eq!(dekaf.env_file, vec!["./auth/dekaf.env"]);
+}
+
+#[test]
+fn test_functions_worker() {
+    let generate = |functions_worker| {
+        let instance_config = InstanceConfig {
+            functions_worker,
+            ..Default::default()
+        };
+
+        generate_instance(
+            "test".to_string(),
+            instance_config,
+            &mut HostPorts::new(Some(30000)),
+        )
+        .unwrap()
+        .docker_compose
+        .services
+    };
+
+    let services = generate(FunctionsWorker::Broker);
+    let broker = &services["broker-cluster-0-0"];
+    assert_eq!(broker.environment["functionsWorkerEnabled"], "true");
+    assert!(broker.command[2].starts_with("bin/gen-yml-from-env.py conf/functions_worker.yml"));
+    assert!(broker
+        .volumes
+        .contains(&"./functions:/pulsar/user-functions:ro".to_string()));
+    assert!(!services.contains_key("functions-worker-cluster-0"));
+
+    let services = generate(FunctionsWorker::Dedicated);
+    let functions_worker = &services["functions-worker-cluster-0"];
+    assert_eq!(
+        functions_worker.environment["PF_pulsarFunctionsCluster"],
+        "cluster-0"
+    );
+    assert!(!services["broker-cluster-0-0"]
+        .environment
+        .contains_key("functionsWorkerEnabled"));
+    assert_eq!(
+        services["pulsar-proxy-cluster-0"].environment["functionWorkerWebServiceURL"],
+        "http://functions-worker-cluster-0:6750"
+    );
+
+    let services = generate(FunctionsWorker::Disabled);
+    assert!(!services["pulsar-proxy-cluster-0"]
+        .volumes
+        .iter()
+        .any(|volume| volume.contains("/pulsar/user-functions")));
+}
+
+#[test]
+fn test_resources_are_applied_by_post_cluster_create_job() {
+    let instance_config: InstanceConfig = serde_yaml::from_str(
+        r#"
+pulsar_version: 3.2.2
+num_clusters: 2
+num_brokers: 1
+num_bookies: 1
+num_zookeepers: 1
+with_dekaf: false
+resources:
+  tenants:
+  - name: acme
+    namespaces:
+    - name: orders
+      replication_clusters: [cluster-0, cluster-1]
+      retention:
+        time: 7d
+        size: 1G
+      message_ttl: 1h
+      topics:
+      - name: created
+        partitions: 3
+        schema:
+          type: AVRO
+          definition: '{"type":"record","name":"Order","fields":[{"name":"id","type":"string"}]}'
+      - name: audit
+"#,
+    )
+    .unwrap();
+
+    let instance_output = generate_instance(
+        "test".to_string(),
+        instance_config,
+        &mut HostPorts::new(Some(30000)),
+    )
+    .unwrap();
+
+    let post_cluster_create_job =
+        &instance_output.docker_compose.services["pulsar-post-cluster-create-job-cluster-1"];
+    let script = &post_cluster_create_job.command[2];
+
+    assert!(script.contains("tenants create --allowed-clusters cluster-0,cluster-1 acme"));
+    assert!(script.starts_with("set -e;"));
+    assert!(script.contains("namespaces policies acme/orders > /dev/null 2>&1 || bin/pulsar-admin --admin-url http://pulsar-proxy-cluster-1:8080 namespaces create acme/orders);"));
+    assert!(script.contains("namespaces set-clusters --clusters cluster-0,cluster-1 acme/orders"));
+    assert!(script.contains("namespaces set-retention --time 7d --size 1G acme/orders"));
+    assert!(script.contains("namespaces set-message-ttl --messageTTL 1h acme/orders"));
+    assert!(script.contains(
+        "topics create-partitioned-topic --partitions 3 persistent://acme/orders/created"
+    ));
+    assert!(script.contains("topics stats persistent://acme/orders/audit > /dev/null 2>&1 || bin/pulsar-admin --admin-url http://pulsar-proxy-cluster-1:8080 topics create persistent://acme/orders/audit)"));
+    assert!(script.contains("tenants get global > /dev/null 2>&1 || "));
+    assert!(script.contains("schemas upload --filename /tmp/schema-persistent___acme_orders_created.json persistent://acme/orders/created"));
+    assert!(script.ends_with("echo success"));
+}
+
+#[test]
+fn test_resource_profiles() {
+    let generate = |instance_config: InstanceConfig| {
+        let mut host_ports = HostPorts::new(Some(30000));
+        generate_instance("test".to_string(), instance_config, &mut host_ports)
+            .unwrap()
+            .docker_compose
+    };
+
+    let docker_compose = generate(InstanceConfig::default());
+    let broker = &docker_compose.services["broker-cluster-0-0"];
+    assert_eq!(
+        broker.environment["PULSAR_MEM"],
+        "-Xms256m -Xmx256m -XX:MaxDirectMemorySize=256m -XX:+ExitOnOutOfMemoryError"
+    );
+    let broker_limits = &broker.deploy.as_ref().unwrap().resources.limits;
+    assert_eq!(broker_limits.memory.as_deref(), Some("512M"));
+    assert_eq!(broker_limits.cpus, None);
+
+    let docker_compose = generate(InstanceConfig {
+        resource_profile: ResourceProfile::Perf,
+        component_resources: Some(ComponentResourcesConfig {
+            broker: Some(ComponentResources {
+                heap: Some("2g".to_string()),
+                memory: Some("4G".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+    let broker = &docker_compose.services["broker-cluster-0-0"];
+    assert_eq!(
+        broker.environment["PULSAR_MEM"],
+        "-Xms2g -Xmx2g -XX:MaxDirectMemorySize=1g -XX:+ExitOnOutOfMemoryError"
+    );
+    let broker_limits = &broker.deploy.as_ref().unwrap().resources.limits;
+    assert_eq!(broker_limits.memory.as_deref(), Some("4G"));
+    assert_eq!(broker_limits.cpus.as_deref(), Some("2"));
+
+    let bookie = &docker_compose.services["bookie-cluster-0-0"];
+    assert_eq!(
+        bookie.environment["BOOKIE_MEM"],
+        "-Xms1g -Xmx1g -XX:MaxDirectMemorySize=1g"
+    );
+    assert_eq!(bookie.environment["dbStorage_writeCacheMaxSizeMb"], "256");
+}
+
+#[test]
+fn test_config_overrides() {
+    let (instance_config, _) = parse_config(
+        r#"
+num_clusters: 2
+num_bookies: 3
+broker_config:
+  allowAutoTopicCreation: false
+  managedLedgerDefaultEnsembleSize: 3
+  customInterceptorSetting: enabled
+bookie_config:
+  journalSyncData: false
+zookeeper_config:
+  maxClientCnxns: 100
+clusters:
+- {}
+- broker_config:
+    brokerDeduplicationEnabled: true
+    allowAutoTopicCreation: true
+  proxy_config:
+    maxConcurrentLookupRequests: 1000
+"#,
+    )
+    .unwrap();
+
+    let mut host_ports = HostPorts::new(Some(30000));
+    let docker_compose = generate_instance("test".to_string(), instance_config, &mut host_ports)
+        .unwrap()
+        .docker_compose;
+
+    // Prefixed, so keys missing from broker.conf are added too
+    let broker_0 = &docker_compose.services["broker-cluster-0-0"].environment;
+    assert_eq!(broker_0["PULSAR_PREFIX_allowAutoTopicCreation"], "false");
+    assert_eq!(
+        broker_0["PULSAR_PREFIX_customInterceptorSetting"],
+        "enabled"
+    );
+    assert!(!broker_0.contains_key("customInterceptorSetting"));
+    assert_eq!(
+        broker_0["PULSAR_PREFIX_managedLedgerDefaultEnsembleSize"],
+        "3"
+    );
+    assert!(!broker_0.contains_key("managedLedgerDefaultEnsembleSize"));
+    assert_eq!(broker_0["managedLedgerDefaultWriteQuorum"], "2");
+    assert!(!broker_0.contains_key("PULSAR_PREFIX_brokerDeduplicationEnabled"));
+
+    let broker_1 = &docker_compose.services["broker-cluster-1-0"].environment;
+    assert_eq!(broker_1["PULSAR_PREFIX_allowAutoTopicCreation"], "true");
+    assert_eq!(broker_1["PULSAR_PREFIX_brokerDeduplicationEnabled"], "true");
+
+    assert_eq!(
+        docker_compose.services["bookie-cluster-1-2"].environment["PULSAR_PREFIX_journalSyncData"],
+        "false"
+    );
+    assert_eq!(
+        docker_compose.services["zookeeper-0"].environment["PULSAR_PREFIX_maxClientCnxns"],
+        "100"
+    );
+    assert!(!docker_compose.services["pulsar-proxy-cluster-0"]
+        .environment
+        .contains_key("PULSAR_PREFIX_maxConcurrentLookupRequests"));
+    assert_eq!(
+        docker_compose.services["pulsar-proxy-cluster-1"].environment
+            ["PULSAR_PREFIX_maxConcurrentLookupRequests"],
+        "1000"
+    );
+}
+
+#[test]
+fn test_heterogeneous_clusters() {
+    let (instance_config, _) = parse_config(
+        r#"
+num_clusters: 2
+num_brokers: 1
+component_resources:
+  broker:
+    heap: 384m
+    memory: 1G
+clusters:
+- num_brokers: 3
+  num_bookies: 2
+  component_resources:
+    broker:
+      memory: 2G
+- pulsar_version: 3.3.1
+  with_dekaf: false
+  resource_profile: tiny
+"#,
+    )
+    .unwrap();
+
+    let mut host_ports = HostPorts::new(Some(30000));
+    let instance_output =
+        generate_instance("test".to_string(), instance_config, &mut host_ports).unwrap();
+    let services = &instance_output.docker_compose.services;
+
+    assert!(services.contains_key("broker-cluster-0-2"));
+    assert!(services.contains_key("bookie-cluster-0-1"));
+    assert!(services.contains_key("broker-cluster-1-0"));
+    assert!(!services.contains_key("broker-cluster-1-1"));
+    assert!(!services.contains_key("bookie-cluster-1-1"));
+    assert!(instance_output
+        .docker_compose
+        .volumes
+        .contains_key("bookie-data-cluster-0-1"));
+
+    assert!(services.contains_key("dekaf-ui-cluster-0"));
+    assert!(!services.contains_key("dekaf-ui-cluster-1"));
+
+    assert_eq!(
+        services["broker-cluster-0-0"].image,
+        "apachepulsar/pulsar:3.2.2"
+    );
+    assert_eq!(
+        services["broker-cluster-1-0"].image,
+        "apachepulsar/pulsar:3.3.1"
+    );
+
+    // Cluster resources are merged into the instance-wide ones field by field
+    let broker_0 = &services["broker-cluster-0-0"];
+    assert!(broker_0.environment["PULSAR_MEM"].starts_with("-Xms384m -Xmx384m"));
+    assert_eq!(
+        broker_0.deploy.as_ref().unwrap().resources.limits.memory,
+        Some("2G".to_string())
+    );
+    let bookie_1 = &services["bookie-cluster-1-0"];
+    assert!(bookie_1.environment["BOOKIE_MEM"].starts_with("-Xms192m -Xmx192m"));
+}
@@ -0,0 +1,73 @@
+use puls::host_ports::{legacy_port, HostPorts};
+
+#[test]
+fn test_pinned_base_port_allocates_sequentially() {
+    let mut host_ports = HostPorts::new(Some(40000));
+
+    assert_eq!(
+        host_ports
+            .allocate("cluster-0.web_service", Some(8080))
+            .unwrap(),
+        40000
+    );
+    assert_eq!(
+        host_ports
+            .allocate("cluster-0.broker_service", Some(6650))
+            .unwrap(),
+        40001
+    );
+    assert_eq!(
+        host_ports
+            .allocate("cluster-0.web_service", Some(8080))
+            .unwrap(),
+        40000
+    );
+}
+
+#[test]
+fn test_reserved_ports_are_skipped() {
+    let mut host_ports = HostPorts::new(Some(40000));
+    host_ports.reserve([40000, 40001]);
+
+    assert_eq!(
+        host_ports.allocate("cluster-0.web_service", None).unwrap(),
+        40002
+    );
+}
+
+#[test]
+fn test_busy_host_ports_are_skipped_with_pinned_base_port() {
+    let _listener = std::net::TcpListener::bind(("0.0.0.0", 41000)).unwrap();
+    let mut host_ports = HostPorts::new(Some(41000));
+
+    assert_eq!(
+        host_ports.allocate("cluster-0.web_service", None).unwrap(),
+        41001
+    );
+}
+
+#[test]
+fn test_recorded_ports_are_dropped_when_base_port_changes() {
+    let mut host_ports = HostPorts::new(Some(40000));
+    host_ports.allocate("cluster-0.web_service", None).unwrap();
+
+    let same_base_port = host_ports.clone().with_base_port(Some(40000));
+    assert_eq!(same_base_port.get("cluster-0.web_service"), Some(40000));
+
+    let mut new_base_port = host_ports.with_base_port(Some(50000));
+    assert_eq!(new_base_port.get("cluster-0.web_service"), None);
+    assert_eq!(
+        new_base_port
+            .allocate("cluster-0.web_service", None)
+            .unwrap(),
+        50000
+    );
+}
+
+#[test]
+fn test_legacy_port_overflow() {
+    assert_eq!(legacy_port(0, 8080), Some(8080));
+    assert_eq!(legacy_port(2, 6650), Some(26650));
+    assert_eq!(legacy_port(6, 8090), None);
+    assert_eq!(legacy_port(7, 8080), None);
+}
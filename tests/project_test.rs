@@ -0,0 +1,50 @@
+use puls::instance_config::InstanceConfig;
+use puls::project::{find_project_config_file, read_project_config, PROJECT_CONFIG_FILE_NAME};
+use uuid::Uuid;
+
+#[test]
+fn test_project_config_discovery() {
+    let temp_dir = std::env::temp_dir().join(format!("puls-project-test-{}", Uuid::new_v4()));
+    let project_dir = temp_dir.join("orders.service");
+    let nested_dir = project_dir.join("src").join("main");
+    std::fs::create_dir_all(&nested_dir).unwrap();
+
+    assert_eq!(find_project_config_file(&nested_dir), None);
+
+    let project_config_file = project_dir.join(PROJECT_CONFIG_FILE_NAME);
+    std::fs::write(
+        &project_config_file,
+        "num_clusters: 2\nfunctions_dir: functions\n",
+    )
+    .unwrap();
+    assert_eq!(
+        find_project_config_file(&nested_dir),
+        Some(project_config_file.clone())
+    );
+
+    let project_config = read_project_config(&project_config_file).unwrap();
+    assert_eq!(project_config.instance_name, "orders-service");
+    assert_eq!(
+        project_config.instance_config,
+        InstanceConfig {
+            num_clusters: 2,
+            functions_dir: Some(project_dir.join("functions").display().to_string()),
+            ..Default::default()
+        }
+    );
+
+    std::fs::write(&project_config_file, "instance_name: orders\n").unwrap();
+    let project_config = read_project_config(&project_config_file).unwrap();
+    assert_eq!(project_config.instance_name, "orders");
+    assert_eq!(project_config.instance_config, InstanceConfig::default());
+
+    std::fs::write(&project_config_file, "num_brokers: many\n").unwrap();
+    let err = read_project_config(&project_config_file).unwrap_err();
+    assert!(err.to_string().contains("num_brokers"));
+
+    // The name becomes a directory name, so it's checked before anything is written
+    std::fs::write(&project_config_file, "instance_name: ../orders\n").unwrap();
+    assert!(read_project_config(&project_config_file).is_err());
+
+    std::fs::remove_dir_all(temp_dir).unwrap();
+}
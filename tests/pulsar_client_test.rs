@@ -0,0 +1,114 @@
+use puls::pulsar_client::{
+    produce_script, produce_script_input, ConsumeOptions, ProduceOptions, SchemaType,
+    SubscriptionPosition, SubscriptionType,
+};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use uuid::Uuid;
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Vec<&'a str> {
+    args.windows(2)
+        .filter(|pair| pair[0] == flag)
+        .map(|pair| pair[1].as_str())
+        .collect()
+}
+
+#[test]
+fn test_produce_args() {
+    let options = ProduceOptions {
+        topic: "orders".to_string(),
+        key: Some("order-1".to_string()),
+        properties: vec!["source=puls".to_string()],
+        schema_type: SchemaType::Json,
+        num_produce: 1,
+        rate: 10.0,
+        ..Default::default()
+    };
+
+    let schema = r#"{"type":"record","name":"Order","fields":[{"name":"id","type":"string"}]}"#;
+    let args = options.pulsar_client_args(Some(schema));
+
+    assert_eq!(args[0..2], ["produce", "orders"]);
+    assert!(flag_value(&args, "--messages").is_empty());
+    assert_ne!(flag_value(&args, "--separator"), [","]);
+    assert_eq!(flag_value(&args, "--key"), ["order-1"]);
+    assert_eq!(flag_value(&args, "--properties"), ["source=puls"]);
+    assert_eq!(flag_value(&args, "--rate"), ["10"]);
+    assert_eq!(
+        flag_value(&args, "--value-schema"),
+        [format!("json:{schema}")]
+    );
+
+    let args = ProduceOptions::default().pulsar_client_args(None);
+    assert!(flag_value(&args, "--value-schema").is_empty());
+}
+
+#[test]
+fn test_produce_script_streams_messages_in_batches() {
+    // Stands in for pulsar-client and records the messages of every run
+    let work_dir = std::env::temp_dir().join(format!("puls-produce-test-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(work_dir.join("bin")).unwrap();
+    std::fs::write(
+        work_dir.join("bin/pulsar-client"),
+        "#!/bin/bash\necho \"run $# $1 $2\" >> runs.txt\n",
+    )
+    .unwrap();
+    Command::new("chmod")
+        .arg("+x")
+        .arg(work_dir.join("bin/pulsar-client"))
+        .status()
+        .unwrap();
+
+    let large_message = "x".repeat(100 * 1024);
+    let messages = vec![
+        r#"{"id": "1, 2"}"#.to_string(),
+        "it's $HOME".to_string(),
+        large_message.clone(),
+        large_message.clone(),
+        large_message,
+    ];
+    let input = produce_script_input(&messages).unwrap();
+
+    let mut child = Command::new("bash")
+        .arg("-c")
+        .arg(produce_script())
+        .arg("bash")
+        .arg("produce")
+        .arg("orders")
+        .current_dir(&work_dir)
+        .stdin(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(&input).unwrap();
+    assert!(child.wait().unwrap().success());
+
+    // 2 fixed arguments and 2 per message, split before the command line gets too long
+    let runs = std::fs::read_to_string(work_dir.join("runs.txt")).unwrap();
+    assert_eq!(runs, "run 10 produce orders\nrun 4 produce orders\n");
+
+    assert!(produce_script_input(&["a\0b".to_string()]).is_err());
+
+    std::fs::remove_dir_all(&work_dir).unwrap();
+}
+
+#[test]
+fn test_consume_args() {
+    let options = ConsumeOptions {
+        topic: "orders".to_string(),
+        subscription: "audit".to_string(),
+        subscription_type: SubscriptionType::KeyShared,
+        subscription_position: SubscriptionPosition::Earliest,
+        num_messages: 5,
+        auto_consume: true,
+        ..Default::default()
+    };
+
+    let args = options.pulsar_client_args();
+
+    assert_eq!(args[0..2], ["consume", "orders"]);
+    assert_eq!(flag_value(&args, "--subscription-name"), ["audit"]);
+    assert_eq!(flag_value(&args, "--subscription-type"), ["Key_Shared"]);
+    assert_eq!(flag_value(&args, "--subscription-position"), ["Earliest"]);
+    assert_eq!(flag_value(&args, "--num-messages"), ["5"]);
+    assert_eq!(flag_value(&args, "--schema-type"), ["auto_consume"]);
+}
@@ -2,7 +2,8 @@ mod utils;
 
 use anyhow::Result;
 use assert_cmd::cargo::CommandCargoExt;
-use puls::instance_config::InstanceConfig;
+use clap::ValueEnum;
+use puls::instance_config::{InstanceConfig, MetadataStore};
 use std::process::Command;
 use tokio::time::{sleep, Duration};
 use utils::{
@@ -18,11 +19,17 @@ async fn test_start_pulsar_instance(
     println!("Testing instance: {instance_config_yaml}");
 
     let instance_config_clone = instance_config.clone();
+    let instance_name_clone = instance_name.clone();
     tokio::spawn(async move {
+        let instance_name = instance_name_clone;
         let num_clusters = instance_config_clone.num_clusters;
         let num_zookeepers = instance_config_clone.num_zookeepers;
         let num_bookies = instance_config_clone.num_bookies;
         let num_brokers = instance_config_clone.num_brokers;
+        let metadata_store = instance_config_clone
+            .metadata_store
+            .to_possible_value()
+            .unwrap();
 
         println!("Creating instance: {instance_name}");
         let exit_status = Command::cargo_bin("puls")
@@ -36,6 +43,8 @@ async fn test_start_pulsar_instance(
             .arg(num_bookies.to_string())
             .arg("--num-brokers")
             .arg(num_brokers.to_string())
+            .arg("--metadata-store")
+            .arg(metadata_store.get_name())
             .arg(instance_name.clone())
             .spawn()
             .unwrap()
@@ -57,11 +66,13 @@ async fn test_start_pulsar_instance(
         assert!(exit_status.success());
     });
 
+    let instance_name_clone = instance_name.clone();
     let is_able_to_connect_to_all_clusters = tokio::spawn(async move {
+        let instance_name = instance_name_clone;
         let started_at = std::time::Instant::now();
         loop {
             let futures = (0..instance_config.num_clusters)
-                .map(check_cluster_connection)
+                .map(|i| check_cluster_connection(instance_name.clone(), i))
                 .collect::<Vec<_>>();
             let results = futures::future::join_all(futures).await;
 
@@ -82,11 +93,13 @@ async fn test_start_pulsar_instance(
 
     assert!(is_able_to_connect_to_all_clusters);
 
+    let instance_name_clone = instance_name.clone();
     let are_clusters_created = tokio::spawn(async move {
+        let instance_name = instance_name_clone;
         let started_at = std::time::Instant::now();
         loop {
             let futures = (0..instance_config.num_clusters)
-                .map(|i| check_cluster_exists(i, format!("cluster-{i}")))
+                .map(|i| check_cluster_exists(instance_name.clone(), i, format!("cluster-{i}")))
                 .collect::<Vec<_>>();
             let results = futures::future::join_all(futures).await;
 
@@ -107,11 +120,15 @@ async fn test_start_pulsar_instance(
 
     assert!(are_clusters_created);
 
+    let instance_name_clone = instance_name.clone();
     let are_tenants_created = tokio::spawn(async move {
+        let instance_name = instance_name_clone;
         let started_at = std::time::Instant::now();
         loop {
             let futures = (0..instance_config.num_clusters)
-                .map(|i| check_tenant_exists(i, format!("cluster-{i}-local")))
+                .map(|i| {
+                    check_tenant_exists(instance_name.clone(), i, format!("cluster-{i}-local"))
+                })
                 .collect::<Vec<_>>();
             let results = futures::future::join_all(futures).await;
 
@@ -137,7 +154,12 @@ async fn test_start_pulsar_instance(
         loop {
             let futures = (0..instance_config.num_clusters)
                 .map(|i| {
-                    check_namespace_exists(i, format!("cluster-{i}-local"), "default".to_string())
+                    check_namespace_exists(
+                        instance_name.clone(),
+                        i,
+                        format!("cluster-{i}-local"),
+                        "default".to_string(),
+                    )
                 })
                 .collect::<Vec<_>>();
 
@@ -181,6 +203,7 @@ async fn test_start_pulsar_instance_c1_br1_bk1_zk1() {
             num_bookies: 1,
             num_zookeepers: 1,
             with_dekaf: false,
+            ..Default::default()
         },
     )
     .await
@@ -199,6 +222,7 @@ async fn test_start_pulsar_instance_c2_br1_bk1_zk1() {
             num_bookies: 1,
             num_zookeepers: 1,
             with_dekaf: false,
+            ..Default::default()
         },
     )
     .await
@@ -217,6 +241,7 @@ async fn test_start_pulsar_instance_c3_br1_bk1_zk1() {
             num_bookies: 1,
             num_zookeepers: 1,
             with_dekaf: false,
+            ..Default::default()
         },
     )
     .await
@@ -235,6 +260,7 @@ async fn test_start_pulsar_instance_c1_br3_bk3_zk3() {
             num_bookies: 3,
             num_zookeepers: 3,
             with_dekaf: false,
+            ..Default::default()
         },
     )
     .await
@@ -253,6 +279,45 @@ async fn test_start_pulsar_instance_c1_br2_bk2_zk1() {
             num_bookies: 2,
             num_zookeepers: 1,
             with_dekaf: false,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_start_pulsar_instance_c1_br1_bk1_oxia() {
+    let instance_name = rand_instance_name();
+    test_start_pulsar_instance(
+        instance_name,
+        InstanceConfig {
+            pulsar_version: "3.2.2".to_string(),
+            num_clusters: 1,
+            num_brokers: 1,
+            num_bookies: 1,
+            metadata_store: MetadataStore::Oxia,
+            with_dekaf: false,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_start_pulsar_instance_c1_br1_bk1_etcd() {
+    let instance_name = rand_instance_name();
+    test_start_pulsar_instance(
+        instance_name,
+        InstanceConfig {
+            pulsar_version: "3.2.2".to_string(),
+            num_clusters: 1,
+            num_brokers: 1,
+            num_bookies: 1,
+            metadata_store: MetadataStore::Etcd,
+            with_dekaf: false,
+            ..Default::default()
         },
     )
     .await
@@ -333,6 +398,7 @@ async fn test_restart_pulsar_instance_c1_br1_bk1_zk1() {
         num_bookies: 1,
         num_zookeepers: 1,
         with_dekaf: false,
+        ..Default::default()
     };
 
     test_restart_pulsar_instance(instance_name, instance_config)
@@ -350,6 +416,7 @@ async fn test_restart_pulsar_instance_c2_br2_bk2_zk3() {
         num_bookies: 2,
         num_zookeepers: 3,
         with_dekaf: false,
+        ..Default::default()
     };
 
     test_restart_pulsar_instance(instance_name, instance_config)
@@ -0,0 +1,114 @@
+use puls::docker_compose::compose::ComposeFile;
+use puls::docker_compose::docker_compose::generate_instance;
+use puls::host_ports::HostPorts;
+use puls::instance_config::{ClusterConfig, InstanceConfig};
+use puls::scale::{changed_services, check_scale_down, removed_bookies, set_scale, RemovedBookie};
+
+fn docker_compose(instance_config: &InstanceConfig) -> ComposeFile {
+    let mut host_ports = HostPorts::new(Some(30000));
+    generate_instance("test".to_string(), instance_config.clone(), &mut host_ports)
+        .unwrap()
+        .docker_compose
+}
+
+#[test]
+fn test_set_scale() {
+    let instance_config = InstanceConfig {
+        num_clusters: 2,
+        clusters: vec![ClusterConfig {
+            num_brokers: Some(3),
+            num_bookies: Some(3),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let mut scaled_instance_config = instance_config.clone();
+    set_scale(&mut scaled_instance_config, Some(2), None, None).unwrap();
+    assert_eq!(scaled_instance_config.num_brokers, 2);
+    assert_eq!(scaled_instance_config.clusters[0].num_brokers, None);
+    assert_eq!(scaled_instance_config.clusters[0].num_bookies, Some(3));
+
+    let mut scaled_instance_config = instance_config.clone();
+    set_scale(&mut scaled_instance_config, None, Some(4), Some(1)).unwrap();
+    assert_eq!(scaled_instance_config.num_bookies, 1);
+    assert_eq!(scaled_instance_config.clusters[1].num_bookies, Some(4));
+
+    let mut scaled_instance_config = instance_config.clone();
+    assert!(set_scale(&mut scaled_instance_config, None, None, None).is_err());
+    assert!(set_scale(&mut scaled_instance_config, Some(2), None, Some(2)).is_err());
+}
+
+#[test]
+fn test_scale_down_decommissions_last_bookies() {
+    let instance_config = InstanceConfig {
+        num_clusters: 2,
+        num_bookies: 4,
+        ..Default::default()
+    };
+
+    let mut scaled_instance_config = instance_config.clone();
+    set_scale(&mut scaled_instance_config, None, Some(2), Some(0)).unwrap();
+    check_scale_down(&instance_config, &scaled_instance_config).unwrap();
+
+    let removed_bookies = removed_bookies(&instance_config, &scaled_instance_config);
+    assert_eq!(
+        removed_bookies,
+        [
+            RemovedBookie {
+                cluster_index: 0,
+                service_name: "bookie-cluster-0-3".to_string(),
+                volume_name: "bookie-data-cluster-0-3".to_string(),
+            },
+            RemovedBookie {
+                cluster_index: 0,
+                service_name: "bookie-cluster-0-2".to_string(),
+                volume_name: "bookie-data-cluster-0-2".to_string(),
+            },
+        ]
+    );
+    assert_eq!(
+        removed_bookies[0].decommission_command().join(" "),
+        "bin/bookkeeper shell decommissionbookie -bookieid bookie-cluster-0-3:3181"
+    );
+    assert_eq!(
+        removed_bookies[0].remaining_bookie_service_name(),
+        "bookie-cluster-0-0"
+    );
+
+    // Existing ledgers are written to 2 bookies, one bookie can't hold their copies
+    set_scale(&mut scaled_instance_config, None, Some(1), None).unwrap();
+    assert!(check_scale_down(&instance_config, &scaled_instance_config).is_err());
+}
+
+#[test]
+fn test_scale_up_bookies_recreates_changed_services() {
+    let instance_config = InstanceConfig {
+        with_monitoring: true,
+        ..Default::default()
+    };
+
+    let mut scaled_instance_config = instance_config.clone();
+    set_scale(&mut scaled_instance_config, None, Some(3), None).unwrap();
+    assert!(removed_bookies(&instance_config, &scaled_instance_config).is_empty());
+
+    let scaled_docker_compose = docker_compose(&scaled_instance_config);
+    for i in 0..3 {
+        assert!(scaled_docker_compose
+            .services
+            .contains_key(&format!("bookie-cluster-0-{i}")));
+    }
+
+    // The broker writes ledgers to 2 bookies now, Prometheus scrapes the new bookies
+    let recreated_services =
+        changed_services(&docker_compose(&instance_config), &scaled_docker_compose);
+    assert!(recreated_services.contains(&"broker-cluster-0-0".to_string()));
+    assert!(recreated_services.contains(&"prometheus".to_string()));
+    assert!(!recreated_services.contains(&"bookie-cluster-0-0".to_string()));
+    assert!(!recreated_services.contains(&"bookie-cluster-0-1".to_string()));
+
+    assert_eq!(
+        changed_services(&scaled_docker_compose, &scaled_docker_compose),
+        Vec::<String>::new()
+    );
+}
@@ -0,0 +1,55 @@
+use puls::snapshots::{
+    check_snapshot_files, docker_volume_name, list_snapshots, read_manifest, snapshot_dir,
+    volume_archive_file_name, write_manifest, SnapshotManifest, SNAPSHOT_CONFIG_FILE_NAME,
+};
+use uuid::Uuid;
+
+#[test]
+fn test_snapshot_manifests() {
+    let instance_dir = std::env::temp_dir().join(format!("puls-snapshots-test-{}", Uuid::new_v4()));
+
+    assert!(snapshot_dir(&instance_dir, "../escape").is_err());
+    assert!(snapshot_dir(&instance_dir, "").is_err());
+
+    for (name, created_at) in [("golden-v2", 200), ("golden-v1", 100)] {
+        let snapshot_dir = snapshot_dir(&instance_dir, name).unwrap();
+        std::fs::create_dir_all(&snapshot_dir).unwrap();
+        write_manifest(
+            &snapshot_dir,
+            &SnapshotManifest {
+                name: name.to_string(),
+                created_at,
+                pulsar_version: "3.2.2".to_string(),
+                volumes: vec!["bookie-data-cluster-0-0".to_string()],
+            },
+        )
+        .unwrap();
+    }
+
+    let manifest = read_manifest(&snapshot_dir(&instance_dir, "golden-v1").unwrap()).unwrap();
+    assert_eq!(manifest.volumes, ["bookie-data-cluster-0-0"]);
+    assert!(read_manifest(&snapshot_dir(&instance_dir, "missing").unwrap()).is_err());
+
+    // A restore must not start before every file of the snapshot is there
+    let golden_v1_dir = snapshot_dir(&instance_dir, "golden-v1").unwrap();
+    std::fs::write(golden_v1_dir.join(SNAPSHOT_CONFIG_FILE_NAME), "").unwrap();
+    assert!(check_snapshot_files(&golden_v1_dir, &manifest).is_err());
+    std::fs::write(
+        golden_v1_dir.join(volume_archive_file_name("bookie-data-cluster-0-0")),
+        "",
+    )
+    .unwrap();
+    assert!(check_snapshot_files(&golden_v1_dir, &manifest).is_ok());
+
+    let snapshot_names = list_snapshots(&instance_dir)
+        .unwrap()
+        .into_iter()
+        .map(|snapshot| snapshot.name)
+        .collect::<Vec<String>>();
+    assert_eq!(snapshot_names, ["golden-v1", "golden-v2"]);
+
+    assert_eq!(
+        docker_volume_name("my-instance", "zookeeper-data-0"),
+        "my-instance_zookeeper-data-0"
+    );
+}
@@ -0,0 +1,150 @@
+use puls::instance_config::{AuthConfig, InstanceConfig, MetadataStore};
+use puls::status::{check_command_args, status_checks, CheckMethod};
+
+#[test]
+fn test_status_checks_cover_every_component() {
+    let instance_config = InstanceConfig {
+        num_clusters: 2,
+        num_brokers: 2,
+        num_bookies: 3,
+        num_zookeepers: 3,
+        with_dekaf: true,
+        ..Default::default()
+    };
+
+    let checks = status_checks(&instance_config);
+
+    // 3 zookeepers + 2 clusters * (init job + 3 bookies + 2 brokers + proxy + dekaf)
+    assert_eq!(checks.len(), 3 + 2 * 8);
+
+    assert_eq!(checks[0].service_name, "zookeeper-0");
+    assert_eq!(checks[0].cluster_name, None);
+
+    let init_job = checks
+        .iter()
+        .find(|check| check.service_name == "pulsar-init-job-cluster-1")
+        .unwrap();
+    assert_eq!(init_job.cluster_name, Some("cluster-1".to_string()));
+    assert_eq!(init_job.method, CheckMethod::Run);
+
+    let broker = checks
+        .iter()
+        .find(|check| check.service_name == "broker-cluster-0-1")
+        .unwrap();
+    assert_eq!(broker.method, CheckMethod::Exec);
+    assert!(broker.script.contains("/admin/v2/brokers/health"));
+    assert!(!broker.script.contains("Authorization"));
+}
+
+#[test]
+fn test_status_checks_authenticate_with_superuser_token() {
+    let instance_config = InstanceConfig {
+        auth: Some(AuthConfig::default()),
+        with_dekaf: false,
+        ..Default::default()
+    };
+
+    let checks = status_checks(&instance_config);
+    assert!(!checks.iter().any(|check| check.component == "dekaf"));
+
+    let proxy = checks
+        .iter()
+        .find(|check| check.component == "proxy")
+        .unwrap();
+    assert!(proxy
+        .script
+        .contains("Bearer $(cat /pulsar/auth/admin.token)"));
+}
+
+#[test]
+fn test_check_command_args() {
+    let instance_config = InstanceConfig {
+        num_zookeepers: 1,
+        num_brokers: 1,
+        num_bookies: 1,
+        with_dekaf: false,
+        ..Default::default()
+    };
+    let health_check =
+        "curl --fail --silent http://127.0.0.1:8080/admin/v2/brokers/health --output /dev/null";
+
+    let args = status_checks(&instance_config)
+        .iter()
+        .map(|check| check_command_args(check).unwrap())
+        .collect::<Vec<Vec<String>>>();
+
+    assert_eq!(
+        args,
+        vec![
+            vec![
+                "exec",
+                "-T",
+                "zookeeper-0",
+                "bash",
+                "-c",
+                "bin/pulsar-zookeeper-ruok.sh"
+            ],
+            vec![
+                "run",
+                "--rm",
+                "--no-deps",
+                "--entrypoint",
+                "bash",
+                "-T",
+                "pulsar-init-job-cluster-0",
+                "-c",
+                "test -f /pulsar/data/init-job/success"
+            ],
+            vec![
+                "exec",
+                "-T",
+                "bookie-cluster-0-0",
+                "bash",
+                "-c",
+                "bin/bookkeeper shell bookiesanity"
+            ],
+            vec![
+                "exec",
+                "-T",
+                "broker-cluster-0-0",
+                "bash",
+                "-c",
+                health_check
+            ],
+            vec![
+                "exec",
+                "-T",
+                "pulsar-proxy-cluster-0",
+                "bash",
+                "-c",
+                health_check
+            ],
+        ]
+    );
+}
+
+#[test]
+fn test_metadata_store_checks_run_without_shell() {
+    let instance_config = InstanceConfig {
+        metadata_store: MetadataStore::Etcd,
+        ..Default::default()
+    };
+
+    let etcd = &status_checks(&instance_config)[0];
+    assert_eq!(etcd.method, CheckMethod::Command);
+    assert_eq!(
+        check_command_args(etcd).unwrap(),
+        vec!["exec", "-T", "etcd", "etcdctl", "endpoint", "health"]
+    );
+
+    let instance_config = InstanceConfig {
+        metadata_store: MetadataStore::Oxia,
+        ..Default::default()
+    };
+
+    let oxia = &status_checks(&instance_config)[0];
+    assert_eq!(
+        check_command_args(oxia).unwrap(),
+        vec!["exec", "-T", "oxia", "oxia", "health", "--port=6649"]
+    );
+}
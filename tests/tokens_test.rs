@@ -0,0 +1,44 @@
+use jsonwebtoken::{decode, DecodingKey, Validation};
+use puls::tokens::{check_role, read_or_create_secret_key, read_token, write_tokens};
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+struct Claims {
+    sub: String,
+}
+
+#[test]
+fn test_write_tokens_signs_tokens_with_secret_key() {
+    let auth_dir = std::env::temp_dir().join(format!("puls-tokens-test-{}", Uuid::new_v4()));
+
+    write_tokens(&auth_dir, &["admin".to_string(), "app".to_string()]).unwrap();
+
+    let secret_key = read_or_create_secret_key(&auth_dir).unwrap();
+    assert_eq!(secret_key.len(), 32);
+
+    let mut validation = Validation::default();
+    validation.required_spec_claims.clear();
+    validation.validate_exp = false;
+
+    let token = read_token(&auth_dir, "app").unwrap();
+    let claims = decode::<Claims>(&token, &DecodingKey::from_secret(&secret_key), &validation)
+        .unwrap()
+        .claims;
+    assert_eq!(claims.sub, "app");
+
+    write_tokens(&auth_dir, &["app".to_string()]).unwrap();
+    assert_eq!(read_token(&auth_dir, "app").unwrap(), token);
+    assert!(read_token(&auth_dir, "missing").is_err());
+}
+
+#[test]
+fn test_roles_unsafe_in_file_names_are_rejected() {
+    let auth_dir = std::env::temp_dir().join(format!("puls-tokens-test-{}", Uuid::new_v4()));
+
+    assert!(check_role("app-1@example.com").is_ok());
+    for role in ["../admin", "a/b", "..", ""] {
+        assert!(check_role(role).is_err());
+        assert!(write_tokens(&auth_dir, &[role.to_string()]).is_err());
+    }
+}
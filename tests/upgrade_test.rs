@@ -0,0 +1,182 @@
+use indexmap::IndexMap;
+use puls::docker_compose::docker_compose::generate_instance;
+use puls::host_ports::HostPorts;
+use puls::instance_config::{ClusterConfig, InstanceConfig};
+use puls::upgrade::{
+    compare_pulsar_versions, downgraded_clusters, set_pulsar_version, upgrade_plan, UpgradeStep,
+};
+use std::cmp::Ordering;
+
+fn running_images(instance_config: &InstanceConfig) -> IndexMap<String, String> {
+    let mut host_ports = HostPorts::new(Some(30000));
+    generate_instance("test".to_string(), instance_config.clone(), &mut host_ports)
+        .unwrap()
+        .docker_compose
+        .services
+        .into_iter()
+        .map(|(service_name, service)| (service_name, service.image))
+        .collect()
+}
+
+fn plan(
+    instance_config: &InstanceConfig,
+    upgraded_instance_config: &InstanceConfig,
+) -> Vec<(String, String)> {
+    steps(instance_config, upgraded_instance_config)
+        .into_iter()
+        .map(|step| (step.component, step.service_name))
+        .collect()
+}
+
+fn steps(
+    instance_config: &InstanceConfig,
+    upgraded_instance_config: &InstanceConfig,
+) -> Vec<UpgradeStep> {
+    let mut host_ports = HostPorts::new(Some(30000));
+    let docker_compose = generate_instance(
+        "test".to_string(),
+        upgraded_instance_config.clone(),
+        &mut host_ports,
+    )
+    .unwrap()
+    .docker_compose;
+
+    upgrade_plan(
+        upgraded_instance_config,
+        &docker_compose,
+        &running_images(instance_config),
+    )
+}
+
+#[test]
+fn test_upgrade_order() {
+    let instance_config = InstanceConfig {
+        num_clusters: 2,
+        num_brokers: 2,
+        num_bookies: 2,
+        ..Default::default()
+    };
+
+    let mut upgraded_instance_config = instance_config.clone();
+    set_pulsar_version(&mut upgraded_instance_config, "3.3.1", None).unwrap();
+    assert_eq!(upgraded_instance_config.pulsar_version, "3.3.1");
+
+    let steps = plan(&instance_config, &upgraded_instance_config)
+        .into_iter()
+        .map(|(component, service_name)| format!("{component} {service_name}"))
+        .collect::<Vec<String>>();
+    assert_eq!(
+        steps,
+        [
+            "zookeeper zookeeper-0",
+            "bookie bookie-cluster-0-0",
+            "bookie bookie-cluster-0-1",
+            "bookie bookie-cluster-1-0",
+            "bookie bookie-cluster-1-1",
+            "broker broker-cluster-0-0",
+            "broker broker-cluster-0-1",
+            "broker broker-cluster-1-0",
+            "broker broker-cluster-1-1",
+            "proxy pulsar-proxy-cluster-0",
+            "proxy pulsar-proxy-cluster-1",
+        ]
+    );
+
+    // Nothing is left once all services run the new version
+    assert_eq!(
+        plan(&upgraded_instance_config, &upgraded_instance_config),
+        []
+    );
+}
+
+#[test]
+fn test_upgrade_single_cluster() {
+    let instance_config = InstanceConfig {
+        num_clusters: 2,
+        clusters: vec![ClusterConfig {
+            pulsar_version: Some("3.1.0".to_string()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let mut upgraded_instance_config = instance_config.clone();
+    set_pulsar_version(&mut upgraded_instance_config, "3.3.1", Some(1)).unwrap();
+    assert_eq!(
+        upgraded_instance_config.clusters[1]
+            .pulsar_version
+            .as_deref(),
+        Some("3.3.1")
+    );
+
+    let service_names = plan(&instance_config, &upgraded_instance_config)
+        .into_iter()
+        .map(|(_, service_name)| service_name)
+        .collect::<Vec<String>>();
+    assert_eq!(
+        service_names,
+        [
+            "bookie-cluster-1-0",
+            "broker-cluster-1-0",
+            "pulsar-proxy-cluster-1"
+        ]
+    );
+
+    // Upgrading the whole instance drops pinned cluster versions
+    let mut upgraded_instance_config = instance_config.clone();
+    set_pulsar_version(&mut upgraded_instance_config, "3.3.1", None).unwrap();
+    assert_eq!(upgraded_instance_config.clusters[0].pulsar_version, None);
+
+    assert!(set_pulsar_version(&mut upgraded_instance_config, "3.3.1", Some(2)).is_err());
+}
+
+#[test]
+fn test_only_proxies_drop_client_connections() {
+    let instance_config = InstanceConfig::default();
+    let mut upgraded_instance_config = instance_config.clone();
+    set_pulsar_version(&mut upgraded_instance_config, "3.3.1", None).unwrap();
+
+    let disconnecting_services = steps(&instance_config, &upgraded_instance_config)
+        .into_iter()
+        .filter(|step| step.drops_client_connections())
+        .map(|step| step.service_name)
+        .collect::<Vec<String>>();
+    assert_eq!(disconnecting_services, ["pulsar-proxy-cluster-0"]);
+}
+
+#[test]
+fn test_downgrades_are_detected() {
+    assert_eq!(
+        compare_pulsar_versions("3.3.1", "3.10.0"),
+        Some(Ordering::Less)
+    );
+    assert_eq!(
+        compare_pulsar_versions("4.0.0-SNAPSHOT", "3.3.1"),
+        Some(Ordering::Greater)
+    );
+    assert_eq!(compare_pulsar_versions("latest", "3.3.1"), None);
+
+    let instance_config = InstanceConfig {
+        num_clusters: 2,
+        pulsar_version: "3.2.2".to_string(),
+        clusters: vec![
+            ClusterConfig::default(),
+            ClusterConfig {
+                pulsar_version: Some("3.3.1".to_string()),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(
+        downgraded_clusters(&instance_config, "3.3.0", None),
+        [("cluster-1".to_string(), "3.3.1".to_string())]
+    );
+    assert!(downgraded_clusters(&instance_config, "3.3.0", Some(0)).is_empty());
+    assert!(downgraded_clusters(&instance_config, "3.3.1", None).is_empty());
+    assert_eq!(
+        downgraded_clusters(&instance_config, "3.1.0", Some(0)),
+        [("cluster-0".to_string(), "3.2.2".to_string())]
+    );
+}
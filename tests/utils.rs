@@ -1,4 +1,5 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use assert_cmd::cargo::CommandCargoExt;
 use std::process::{Command, Stdio};
 use uuid::Uuid;
 
@@ -28,8 +29,29 @@ pub fn cleanup_docker_resources() -> Result<()> {
     Ok(())
 }
 
-pub async fn check_cluster_exists(cluster_index: u32, cluster_name: String) -> Result<()> {
-    let port: u32 = format!("{cluster_index}8080").parse().unwrap();
+/// Host port of the cluster's web service, as allocated for the instance.
+pub fn web_service_port(instance_name: &str, cluster_index: u32) -> Result<u16> {
+    let out = Command::cargo_bin("puls")?
+        .arg("describe")
+        .arg(instance_name)
+        .arg("--output")
+        .arg("json")
+        .output()?
+        .stdout;
+
+    let instance_output: serde_json::Value = serde_json::from_slice(&out)?;
+    instance_output["clusters"][cluster_index as usize]["web_service_host_port"]
+        .as_u64()
+        .and_then(|port| u16::try_from(port).ok())
+        .ok_or(anyhow!("No web service port for cluster {cluster_index}"))
+}
+
+pub async fn check_cluster_exists(
+    instance_name: String,
+    cluster_index: u32,
+    cluster_name: String,
+) -> Result<()> {
+    let port = web_service_port(&instance_name, cluster_index)?;
     let out = Command::new("pulsar-admin")
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -53,8 +75,12 @@ pub async fn check_cluster_exists(cluster_index: u32, cluster_name: String) -> R
     }
 }
 
-pub async fn check_tenant_exists(cluster_index: u32, tenant: String) -> Result<()> {
-    let port: u32 = format!("{cluster_index}8080").parse().unwrap();
+pub async fn check_tenant_exists(
+    instance_name: String,
+    cluster_index: u32,
+    tenant: String,
+) -> Result<()> {
+    let port = web_service_port(&instance_name, cluster_index)?;
     let out = Command::new("pulsar-admin")
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -79,11 +105,12 @@ pub async fn check_tenant_exists(cluster_index: u32, tenant: String) -> Result<(
 }
 
 pub async fn check_namespace_exists(
+    instance_name: String,
     cluster_index: u32,
     tenant: String,
     namespace: String,
 ) -> Result<()> {
-    let port: u32 = format!("{cluster_index}8080").parse().unwrap();
+    let port = web_service_port(&instance_name, cluster_index)?;
     let out = Command::new("pulsar-admin")
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -109,9 +136,10 @@ pub async fn check_namespace_exists(
 }
 
 pub async fn check_cluster_connection(
+    instance_name: String,
     cluster_index: u32,
 ) -> Result<()> {
-    let port: u32 = format!("{cluster_index}8080").parse().unwrap();
+    let port = web_service_port(&instance_name, cluster_index)?;
     let is_ok = Command::new("pulsar-admin")
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
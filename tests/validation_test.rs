@@ -0,0 +1,162 @@
+use puls::docker_compose::docker_compose::generate_instance;
+use puls::docker_compose::validation::{
+    check_topology, estimate_memory, format_memory, parse_memory, validate_topology, Severity,
+};
+use puls::host_ports::HostPorts;
+use puls::instance_config::{
+    ComponentResources, ComponentResourcesConfig, InstanceConfig, MetadataStore,
+};
+
+#[test]
+fn test_impossible_topologies_are_rejected() {
+    for instance_config in [
+        InstanceConfig {
+            num_clusters: 0,
+            ..Default::default()
+        },
+        InstanceConfig {
+            num_brokers: 0,
+            ..Default::default()
+        },
+        InstanceConfig {
+            num_bookies: 0,
+            ..Default::default()
+        },
+        InstanceConfig {
+            num_zookeepers: 0,
+            ..Default::default()
+        },
+        InstanceConfig {
+            base_port: Some(65534),
+            ..Default::default()
+        },
+    ] {
+        let mut host_ports = HostPorts::new(instance_config.base_port);
+        assert!(generate_instance("test".to_string(), instance_config, &mut host_ports).is_err());
+    }
+
+    // ZooKeeper count doesn't matter with other metadata stores
+    assert_eq!(
+        validate_topology(&InstanceConfig {
+            metadata_store: MetadataStore::Oxia,
+            num_zookeepers: 0,
+            ..Default::default()
+        })
+        .unwrap(),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn test_risky_topologies_produce_warnings() {
+    let issues = check_topology(&InstanceConfig {
+        num_zookeepers: 2,
+        num_clusters: 7,
+        ..Default::default()
+    });
+    assert_eq!(
+        issues
+            .iter()
+            .map(|issue| (issue.severity, issue.field.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            (Severity::Warning, "num_zookeepers"),
+            (Severity::Warning, "num_clusters"),
+        ]
+    );
+
+    assert!(check_topology(&InstanceConfig::default()).is_empty());
+}
+
+#[test]
+fn test_memory_estimation() {
+    assert_eq!(parse_memory("512M").unwrap(), 512 * 1024 * 1024);
+    assert_eq!(parse_memory("1.5g").unwrap(), 3 * 512 * 1024 * 1024);
+    assert_eq!(parse_memory("256mb").unwrap(), 256 * 1024 * 1024);
+    assert_eq!(parse_memory("1024").unwrap(), 1024);
+    assert!(parse_memory("1x").is_err());
+
+    assert_eq!(format_memory(512 * 1024 * 1024), "512 MiB");
+    assert_eq!(format_memory(3 * 512 * 1024 * 1024), "1.5 GiB");
+
+    let mut host_ports = HostPorts::new(Some(30000));
+    let instance_output = generate_instance(
+        "test".to_string(),
+        InstanceConfig::default(),
+        &mut host_ports,
+    )
+    .unwrap();
+    assert!(instance_output.estimated_memory > 0);
+    assert_eq!(
+        instance_output.estimated_memory,
+        estimate_memory(&instance_output.docker_compose).unwrap()
+    );
+
+    // Init and post-create jobs are done before the instance is up
+    let mut docker_compose_without_jobs = instance_output.docker_compose.clone();
+    docker_compose_without_jobs
+        .services
+        .retain(|service_name, _| !service_name.contains("-job-"));
+    assert!(
+        docker_compose_without_jobs.services.len() < instance_output.docker_compose.services.len()
+    );
+    assert_eq!(
+        instance_output.estimated_memory,
+        estimate_memory(&docker_compose_without_jobs).unwrap()
+    );
+
+    let mut host_ports = HostPorts::new(Some(30000));
+    let bigger_instance_output = generate_instance(
+        "test".to_string(),
+        InstanceConfig {
+            num_brokers: 3,
+            ..Default::default()
+        },
+        &mut host_ports,
+    )
+    .unwrap();
+    assert!(bigger_instance_output.estimated_memory > instance_output.estimated_memory);
+}
+
+#[test]
+fn test_component_resources_are_validated() {
+    let with_bookie_resources = |bookie: ComponentResources| InstanceConfig {
+        component_resources: Some(ComponentResourcesConfig {
+            bookie: Some(bookie),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let err = validate_topology(&with_bookie_resources(ComponentResources {
+        heap: Some("lots".to_string()),
+        ..Default::default()
+    }))
+    .unwrap_err();
+    assert!(err.to_string().contains("component_resources.bookie.heap"));
+
+    // Valid container memory limit, but the JVM doesn't take fractions
+    let err = validate_topology(&with_bookie_resources(ComponentResources {
+        direct_memory: Some("1.5g".to_string()),
+        ..Default::default()
+    }))
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("component_resources.bookie.direct_memory"));
+    assert!(
+        validate_topology(&with_bookie_resources(ComponentResources {
+            memory: Some("1.5g".to_string()),
+            ..Default::default()
+        }))
+        .is_ok()
+    );
+
+    let warnings = validate_topology(&with_bookie_resources(ComponentResources {
+        heap: Some("1g".to_string()),
+        ..Default::default()
+    }))
+    .unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].starts_with("component_resources.bookie:"));
+}
@@ -0,0 +1,129 @@
+use puls::instance_config::{
+    InstanceConfig, NamespaceConfig, ResourcesConfig, TenantConfig, TopicConfig,
+};
+use puls::status::{check_command_args, CheckMethod, StatusCheck};
+use puls::wait::{parse_duration, wait_checks, WaitTarget};
+use std::process::Command;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[test]
+fn test_parse_wait_arguments() {
+    assert_eq!(parse_duration("300s").unwrap(), Duration::from_secs(300));
+    assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+    assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+    assert!(parse_duration("soon").is_err());
+
+    assert_eq!("ready".parse::<WaitTarget>().unwrap(), WaitTarget::Ready);
+    assert_eq!(
+        "topics-created".parse::<WaitTarget>().unwrap(),
+        WaitTarget::TopicsCreated
+    );
+    assert_eq!(
+        "cluster-1".parse::<WaitTarget>().unwrap(),
+        WaitTarget::Cluster(1)
+    );
+    assert!("cluster-x".parse::<WaitTarget>().is_err());
+}
+
+#[test]
+fn test_wait_checks() {
+    let instance_config = InstanceConfig {
+        num_clusters: 2,
+        resources: Some(ResourcesConfig {
+            tenants: vec![TenantConfig {
+                name: "shop".to_string(),
+                namespaces: vec![NamespaceConfig {
+                    name: "orders".to_string(),
+                    topics: vec![TopicConfig {
+                        name: "created".to_string(),
+                        partitions: 3,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }),
+        ..Default::default()
+    };
+
+    let max_time = Duration::from_secs(300);
+    let ready_checks = wait_checks(&instance_config, WaitTarget::Ready, max_time).unwrap();
+    assert_eq!(ready_checks.len(), 2 * 4);
+    assert_eq!(
+        ready_checks[0].service_name,
+        "pulsar-post-cluster-create-job-cluster-0"
+    );
+    assert_eq!(ready_checks[0].method, CheckMethod::Completed);
+    assert!(ready_checks[1].script.contains("'\"cluster-1\"'"));
+    assert!(ready_checks[1].script.contains("--max-time 300.0"));
+
+    let cluster_checks = wait_checks(&instance_config, WaitTarget::Cluster(1), max_time).unwrap();
+    assert!(cluster_checks
+        .iter()
+        .all(|check| check.cluster_name == Some("cluster-1".to_string())));
+    assert!(wait_checks(&instance_config, WaitTarget::Cluster(2), max_time).is_err());
+
+    let topics_checks = wait_checks(&instance_config, WaitTarget::TopicsCreated, max_time).unwrap();
+    let topics_check = topics_checks
+        .iter()
+        .find(|check| check.component == "topics")
+        .unwrap();
+    assert!(topics_check
+        .script
+        .contains("/admin/v2/persistent/shop/orders/partitioned"));
+    assert!(topics_check
+        .script
+        .contains("'\"persistent://shop/orders/created\"'"));
+}
+
+/// Runs the command of the check that `docker compose` would run in the container,
+/// with a `curl` that prints the given admin API response.
+fn check_passes(check: &StatusCheck, response: &str) -> bool {
+    let work_dir = std::env::temp_dir().join(format!("puls-wait-test-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(&work_dir).unwrap();
+    std::fs::write(
+        work_dir.join("curl"),
+        format!("#!/bin/bash\necho '{response}'\n"),
+    )
+    .unwrap();
+    Command::new("chmod")
+        .arg("+x")
+        .arg(work_dir.join("curl"))
+        .status()
+        .unwrap();
+
+    let args = check_command_args(check).unwrap();
+    let (exec, command) = args.split_at(3);
+    assert_eq!(exec, ["exec", "-T", "pulsar-proxy-cluster-0"]);
+
+    let path = format!("{}:{}", work_dir.display(), std::env::var("PATH").unwrap());
+    let success = Command::new(&command[0])
+        .args(&command[1..])
+        .env("PATH", path)
+        .status()
+        .unwrap()
+        .success();
+
+    std::fs::remove_dir_all(&work_dir).unwrap();
+    success
+}
+
+#[test]
+fn test_wait_checks_fail_until_target_is_reached() {
+    let instance_config = InstanceConfig {
+        num_clusters: 2,
+        ..Default::default()
+    };
+
+    let checks = wait_checks(&instance_config, WaitTarget::Ready, Duration::from_secs(5)).unwrap();
+    let clusters_check = checks
+        .iter()
+        .find(|check| check.component == "clusters")
+        .unwrap();
+
+    assert!(!check_passes(clusters_check, "[]"));
+    assert!(!check_passes(clusters_check, r#"["cluster-0"]"#));
+    assert!(check_passes(clusters_check, r#"["cluster-0","cluster-1"]"#));
+}